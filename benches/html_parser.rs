@@ -1,19 +1,43 @@
-use monzo_crawler::{parse_links, PageContent};
+use spider_crab::{parse_links, PageContent};
 
 use criterion::{criterion_group, criterion_main, Criterion};
+use http::HeaderMap;
 use std::hint::black_box;
 
+fn page(url: &str, html: String) -> PageContent {
+    PageContent {
+        url: url.parse().expect("Failed to parse URL"),
+        status_code: reqwest::StatusCode::OK,
+        content: html,
+        content_type: Some("text/html".parse().expect("Failed to parse header")),
+        headers: HeaderMap::new(),
+        http_version: None,
+    }
+}
+
+/// Parse the Monzo home page, and a page of many links to measure building the link sets.
 fn criterion_benchmark(c: &mut Criterion) {
-    let html = std::fs::read_to_string("./tests/test_data/monzo/home.html").unwrap();
+    let html = std::fs::read_to_string("./tests/test_data/monzo/home.html")
+        .expect("Failed to read test data");
+    let monzo = page("https://monzo.com", html);
 
-    let page = PageContent {
-        url: "https://monzo.com".parse().unwrap(),
-        status_code: reqwest::StatusCode::OK,
-        content: html.clone(),
-        content_type: Some("text/html".parse().unwrap()),
-    };
+    // A page of 5,000 links, a quarter of them repeated and a fifth external
+    let links: String = (0..5_000)
+        .map(|i| match i % 20 {
+            0..=3 => format!(r#"<a href="https://github.com/monzo/{}">GitHub</a>"#, i),
+            4..=8 => format!(r#"<a href="/docs/{}">Docs</a>"#, i % 100),
+            _ => format!(r#"<a href="/page/{}">Page {}</a>"#, i, i),
+        })
+        .collect();
+    let link_heavy = page(
+        "https://monzo.com",
+        format!("<html><body>{}</body></html>", links),
+    );
 
-    c.bench_function("parse html", |b| b.iter(|| parse_links(black_box(&page))));
+    c.bench_function("parse html", |b| b.iter(|| parse_links(black_box(&monzo))));
+    c.bench_function("parse link heavy html", |b| {
+        b.iter(|| parse_links(black_box(&link_heavy)))
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,382 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::parser::{AllPages, Page};
+
+impl AllPages {
+    /// Pages with at least `min_out` outbound in-scope links, most linked first.
+    /// Only meaningful if the crawler retained links (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn hubs(&self, min_out: usize) -> Vec<&Page> {
+        let mut hubs: Vec<&Page> = self
+            .pages
+            .iter()
+            .filter(|page| page.links.len() >= min_out)
+            .collect();
+        hubs.sort_by_key(|page| std::cmp::Reverse(page.links.len()));
+        hubs
+    }
+
+    /// Visited pages with no outbound in-scope links.
+    /// Only meaningful if the crawler retained links (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn dead_ends(&self) -> Vec<&Page> {
+        self.pages
+            .iter()
+            .filter(|page| page.links.is_empty())
+            .collect()
+    }
+
+    /// Visited pages whose number of outbound in-scope links is more than `z_score` standard
+    /// deviations from the mean across all visited pages, most linked first. Unusually many links
+    /// can mean spam was injected into a page, and unusually few a broken template.
+    /// Only meaningful if the crawler retained links (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn outlier_pages(&self, z_score: f64) -> Vec<&Page> {
+        let counts: Vec<f64> = self
+            .pages
+            .iter()
+            .map(|page| page.links.len() as f64)
+            .collect();
+        let n = counts.len() as f64;
+        let mean = counts.iter().sum::<f64>() / n;
+        let std_dev = (counts
+            .iter()
+            .map(|count| (count - mean).powi(2))
+            .sum::<f64>()
+            / n)
+            .sqrt();
+        if std_dev == 0.0 || std_dev.is_nan() {
+            return Vec::new();
+        }
+
+        let mut outliers: Vec<&Page> = self
+            .pages
+            .iter()
+            .filter(|page| ((page.links.len() as f64 - mean) / std_dev).abs() > z_score)
+            .collect();
+        outliers.sort_by_key(|page| std::cmp::Reverse(page.links.len()));
+        outliers
+    }
+
+    /// Every URL discovered in the crawl: each visited page, each failed visit and each link found,
+    /// whether or not it was visited.
+    /// Only includes links if the crawler retained them (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn all_urls(&self) -> BTreeSet<Url> {
+        let mut urls: BTreeSet<Url> = self
+            .failed
+            .iter()
+            .map(|failed| failed.url.clone())
+            .collect();
+        for page in self.pages.iter() {
+            urls.insert(page.url.clone());
+            urls.extend(page.links.iter().cloned());
+            urls.extend(page.external_links.iter().cloned());
+        }
+        urls
+    }
+
+    /// The [content hash](Page::content_hash) of each visited page, to compare a later crawl against.
+    /// See [CrawlerBuilder::with_prior_manifest](crate::CrawlerBuilder::with_prior_manifest).
+    pub fn content_manifest(&self) -> HashMap<Url, u64> {
+        self.pages
+            .iter()
+            .map(|page| (page.url.clone(), page.content_hash))
+            .collect()
+    }
+
+    /// Visited pages that did not respond with a 2xx status, with their status and the pages that
+    /// link to them, in URL order. Links to an alias of a page count as links to the page.
+    /// Only meaningful if the crawler retained links (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn broken_links_report(&self) -> HashMap<Url, (StatusCode, Vec<Url>)> {
+        let mut report: HashMap<Url, (StatusCode, Vec<Url>)> = self
+            .pages
+            .iter()
+            .filter(|page| !page.status_code.is_success())
+            .map(|page| (page.url.clone(), (page.status_code, Vec::new())))
+            .collect();
+
+        for page in self.pages.iter() {
+            for link in page.links.iter() {
+                let target = self.aliases.get(link).unwrap_or(link);
+                if let Some((_, referrers)) = report.get_mut(target) {
+                    referrers.push(page.url.clone());
+                }
+            }
+        }
+        for (_, referrers) in report.values_mut() {
+            referrers.sort();
+            referrers.dedup();
+        }
+        report
+    }
+
+    /// The number of other visited pages linking to each visited page, to rank pages by how linked-to
+    /// they are within the site. Links to an alias of a page count as links to the page, and each
+    /// linking page is counted once. Only meaningful if the crawler retained links (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn in_degrees(&self) -> HashMap<Url, usize> {
+        let mut in_degrees: HashMap<Url, usize> = self
+            .pages
+            .iter()
+            .map(|page| (page.url.clone(), 0))
+            .collect();
+
+        for page in self.pages.iter() {
+            let targets: HashSet<&Url> = page
+                .links
+                .iter()
+                .map(|link| self.aliases.get(link).unwrap_or(link))
+                .filter(|&target| target != &page.url)
+                .collect();
+            for target in targets {
+                if let Some(in_degree) = in_degrees.get_mut(target) {
+                    *in_degree += 1;
+                }
+            }
+        }
+        in_degrees
+    }
+
+    /// Successfully visited HTML pages that do not declare an [alternate](Page::alternates) for
+    /// every language in `expected`, with the languages they are missing. Languages are compared
+    /// ignoring case. Only meaningful if the crawler retained links (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn missing_hreflang_report(&self, expected: &[&str]) -> HashMap<Url, Vec<String>> {
+        self.pages
+            .iter()
+            .filter(|page| page.status_code.is_success() && page.is_html())
+            .filter_map(|page| {
+                let missing: Vec<String> = expected
+                    .iter()
+                    .filter(|&&language| {
+                        !page
+                            .alternates
+                            .iter()
+                            .any(|(alternate, _)| alternate.eq_ignore_ascii_case(language))
+                    })
+                    .map(|language| language.to_string())
+                    .collect();
+                (!missing.is_empty()).then(|| (page.url.clone(), missing))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use http::HeaderMap;
+    use reqwest::StatusCode;
+    use url::Url;
+
+    use crate::{parse_links, AllPages, PageContent};
+
+    fn all_pages(pages: &[(&str, &str)]) -> anyhow::Result<AllPages> {
+        let pages: Vec<_> = pages
+            .iter()
+            .map(|&(url, content)| (url, StatusCode::OK, content))
+            .collect();
+        all_pages_with_status(&pages)
+    }
+
+    fn all_pages_with_status(pages: &[(&str, StatusCode, &str)]) -> anyhow::Result<AllPages> {
+        let pages = pages
+            .iter()
+            .map(|&(url, status_code, content)| {
+                Ok(parse_links(&PageContent {
+                    url: Url::parse(url)?,
+                    status_code,
+                    content: content.to_string(),
+                    content_type: None,
+                    headers: HeaderMap::new(),
+                    http_version: None,
+                }))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(AllPages {
+            pages,
+            ..Default::default()
+        })
+    }
+
+    fn urls<'a>(pages: impl IntoIterator<Item = &'a crate::Page>) -> Vec<&'a str> {
+        pages.into_iter().map(|page| page.url.as_str()).collect()
+    }
+
+    #[test]
+    fn test_hubs_and_dead_ends() -> anyhow::Result<()> {
+        let all_pages = all_pages(&[
+            (
+                "https://monzo.com/",
+                r#"<a href="/about"></a> <a href="/cost"></a> <a href="/help"></a>"#,
+            ),
+            (
+                "https://monzo.com/about",
+                r#"<a href="/"></a> <a href="/careers"></a>"#,
+            ),
+            (
+                "https://monzo.com/cost",
+                r#"<a href="https://github.com/monzo"></a>"#,
+            ),
+            ("https://monzo.com/help", "<p>No links here</p>"),
+        ])?;
+
+        assert_eq!(
+            urls(all_pages.hubs(2)),
+            ["https://monzo.com/", "https://monzo.com/about"]
+        );
+        assert_eq!(urls(all_pages.hubs(3)), ["https://monzo.com/"]);
+        assert_eq!(
+            urls(all_pages.dead_ends()),
+            ["https://monzo.com/cost", "https://monzo.com/help"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_outlier_pages() -> anyhow::Result<()> {
+        let links = |count: usize| -> String {
+            (0..count)
+                .map(|i| format!(r#"<a href="/link-{i}"></a>"#))
+                .collect()
+        };
+        let mut pages: Vec<(String, String)> = (0..20)
+            .map(|i| (format!("https://monzo.com/{i}"), links(28 + i % 5)))
+            .collect();
+        pages.push(("https://monzo.com/spam".to_string(), links(5000)));
+        let pages: Vec<(&str, &str)> = pages
+            .iter()
+            .map(|(url, content)| (url.as_str(), content.as_str()))
+            .collect();
+        let all_pages = all_pages(&pages)?;
+
+        assert_eq!(
+            urls(all_pages.outlier_pages(3.0)),
+            ["https://monzo.com/spam"]
+        );
+        assert!(all_pages.outlier_pages(10.0).is_empty());
+        assert!(AllPages::default().outlier_pages(3.0).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_all_urls() -> anyhow::Result<()> {
+        let all_pages = all_pages(&[
+            (
+                "https://monzo.com/",
+                r#"<a href="/about"></a> <a href="https://github.com/monzo"></a>"#,
+            ),
+            (
+                "https://monzo.com/about",
+                r#"<a href="/"></a> <a href="/careers"></a>"#,
+            ),
+        ])?;
+
+        assert_eq!(
+            all_pages
+                .all_urls()
+                .iter()
+                .map(Url::as_str)
+                .collect::<Vec<_>>(),
+            [
+                "https://github.com/monzo",
+                "https://monzo.com/",
+                "https://monzo.com/about",
+                "https://monzo.com/careers",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_broken_links_report() -> anyhow::Result<()> {
+        let all_pages = all_pages_with_status(&[
+            (
+                "https://monzo.com/",
+                StatusCode::OK,
+                r#"<a href="/about"></a> <a href="/broken"></a>"#,
+            ),
+            (
+                "https://monzo.com/about",
+                StatusCode::OK,
+                r#"<a href="/broken"></a> <a href="/broken"></a>"#,
+            ),
+            ("https://monzo.com/broken", StatusCode::NOT_FOUND, ""),
+        ])?;
+
+        assert_eq!(
+            all_pages.broken_links_report(),
+            HashMap::from([(
+                Url::parse("https://monzo.com/broken")?,
+                (
+                    StatusCode::NOT_FOUND,
+                    vec![
+                        Url::parse("https://monzo.com/")?,
+                        Url::parse("https://monzo.com/about")?
+                    ]
+                )
+            )])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_in_degrees() -> anyhow::Result<()> {
+        let mut all_pages = all_pages(&[
+            (
+                "https://monzo.com/",
+                r#"<a href="/"></a> <a href="/about"></a> <a href="/careers/"></a>"#,
+            ),
+            (
+                "https://monzo.com/about",
+                r#"<a href="/"></a> <a href="/careers"></a> <a href="/careers/"></a>"#,
+            ),
+            ("https://monzo.com/careers/", r#"<a href="/"></a>"#),
+            ("https://monzo.com/blog", r#"<a href="/unvisited"></a>"#),
+        ])?;
+        all_pages.aliases.insert(
+            Url::parse("https://monzo.com/careers")?,
+            Url::parse("https://monzo.com/careers/")?,
+        );
+
+        assert_eq!(
+            all_pages.in_degrees(),
+            HashMap::from([
+                (Url::parse("https://monzo.com/")?, 2),
+                (Url::parse("https://monzo.com/about")?, 1),
+                (Url::parse("https://monzo.com/careers/")?, 2),
+                (Url::parse("https://monzo.com/blog")?, 0),
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_hreflang_report() -> anyhow::Result<()> {
+        let all_pages = all_pages(&[
+            (
+                "https://monzo.com/",
+                r#"<link rel="alternate" hreflang="en-GB" href="/">
+                   <link rel="alternate" hreflang="fr" href="/fr/">"#,
+            ),
+            (
+                "https://monzo.com/about",
+                r#"<link rel="alternate" hreflang="en-gb" href="/about">"#,
+            ),
+        ])?;
+
+        assert_eq!(
+            all_pages.missing_hreflang_report(&["en-GB", "fr"]),
+            HashMap::from([(
+                Url::parse("https://monzo.com/about")?,
+                vec!["fr".to_string()]
+            )])
+        );
+
+        Ok(())
+    }
+}
@@ -1,26 +1,81 @@
-use std::path::PathBuf;
+use std::{io::BufRead, path::PathBuf};
 
-use clap::Parser;
+use clap::{builder::RangedU64ValueParser, Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 /// Welcome to the Monzo Crawler! Try not to get rate limited!
 pub struct Cli {
-    /// Root URL to start crawling from.
-    pub url: url::Url,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Root URLs to start crawling from, or `-` to read newline-separated seed URLs from stdin.
+    /// Each seed host's robots.txt applies to its own URLs. Limits such as `--max-pages` are shared
+    /// by all seeds, and a page reachable from several is visited once.
+    #[arg(required_unless_present("seeds_file"), value_parser = parse_seeds)]
+    pub url: Vec<Seeds>,
 
-    /// Root URL to start crawling from.
+    /// File of newline-separated seed URLs to crawl, in addition to any given as arguments.
+    #[arg(long, default_value = None)]
+    pub seeds_file: Option<PathBuf>,
+
+    /// File to write the output to instead of stdout. Paths ending in `.gz` are gzipped.
     #[arg(short, long)]
     pub output: Option<PathBuf>,
 
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
     /// Hide links when displaying output.
     #[arg(short('l'), long)]
     pub hide_links: bool,
 
+    /// When to colour output. `auto` colours output only when it is shown in a terminal.
+    /// Output written to a file with `--output` is never coloured.
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Print a table of status codes and crawl totals when the crawl finishes.
+    #[arg(short('s'), long)]
+    pub summary: bool,
+
+    /// File to periodically write the crawl's progress to as JSON, for monitoring crawls run
+    /// without a terminal. It is written one last time when the crawl finishes.
+    #[arg(long, default_value = None)]
+    pub status_file: Option<PathBuf>,
+
+    /// Seconds between writes of the status file.
+    #[arg(long, default_value_t = 5, requires("status_file"), value_parser = clap::value_parser!(u64).range(1..))]
+    pub status_interval: u64,
+
     /// Maximum number of concurrent connections
     #[arg(short('c'), long, default_value_t = 500)]
     pub max_concurrent_connections: usize,
 
+    /// After a `429 Too Many Requests` response, halve the number of concurrent connections, then
+    /// add one back every this many seconds. Default is to keep the limit fixed.
+    #[arg(long, default_value = None, value_parser = clap::value_parser!(u64).range(1..))]
+    pub slow_start: Option<u64>,
+
+    /// Maximum number of concurrent connections to each host. Default is only the overall limit.
+    #[arg(long, default_value = None, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub max_concurrent_per_host: Option<usize>,
+
+    /// Maximum number of concurrent DNS lookups. Default is unlimited.
+    #[arg(long, default_value = None, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub max_concurrent_dns: Option<usize>,
+
+    /// Seconds to wait for a host to be resolved and connected to before recording the visit as failed.
+    #[arg(long, default_value_t = 10)]
+    pub connect_timeout: u64,
+
+    /// Accept TLS certificates that do not match the host name, such as when crawling a server by
+    /// its IP address before a DNS cutover. Certificates must still be valid and trusted, but any
+    /// trusted certificate is accepted for any host, so only use this against servers you control.
+    #[arg(long)]
+    pub accept_invalid_hostnames: bool,
+
     /// Maximum crawl time in seconds. Default is unlimited.
     #[arg(short('m'), long, default_value = None)]
     pub max_time: Option<u64>,
@@ -29,11 +84,195 @@ pub struct Cli {
     #[arg(short('p'), long, default_value = None)]
     pub max_pages: Option<u64>,
 
+    /// Maximum total size of page bodies to download, in bytes. Default is unlimited.
+    #[arg(long, default_value = None)]
+    pub max_bytes: Option<u64>,
+
+    /// The order to visit pages in. `breadth-first` visits the pages closest to the seeds first, so
+    /// runs limited by `--max-pages` cover the most important pages. Default is to visit every page as
+    /// soon as it is found.
+    #[arg(long, value_enum, default_value = None)]
+    pub strategy: Option<Strategy>,
+
+    /// Only follow links under these path prefixes, such as `/docs`. Repeat or separate with commas for multiple prefixes.
+    #[arg(long, value_delimiter = ',')]
+    pub path_prefix: Vec<String>,
+
+    /// Maximum number of in-scope redirects to follow for a single page before treating it as a redirect loop.
+    #[arg(long, default_value_t = 10)]
+    pub max_redirects: usize,
+
     /// Ignore robots.txt files.
     #[arg(short, long)]
     pub ignore_robots: bool,
 
+    /// Ignore robots.txt for the seed's host only, still honouring it for other in-scope hosts.
+    #[arg(long, conflicts_with("ignore_robots"))]
+    pub ignore_robots_for_seed_host: bool,
+
+    /// Treat subdomains of the root URL's registrable domain as in scope, using this public suffix list file
+    /// (https://publicsuffix.org/list/public_suffix_list.dat).
+    #[arg(long, default_value = None)]
+    pub public_suffix_list: Option<PathBuf>,
+
+    /// Enable full politeness: honour robots.txt, meta robots nofollow/noindex, rel=nofollow and crawl-delay.
+    #[arg(
+        long,
+        alias("respect-nofollow-robots-meta"),
+        conflicts_with("ignore_robots")
+    )]
+    pub strict_politeness: bool,
+
+    /// URL to POST a login form to before crawling. Session cookies it sets are used for the crawl.
+    #[arg(long, default_value = None)]
+    pub login_url: Option<url::Url>,
+
+    /// Login form field as `name=value`. Repeat or separate with commas for multiple fields.
+    #[arg(long, value_delimiter = ',', value_parser = parse_form_field, requires("login_url"))]
+    pub login_field: Vec<(String, String)>,
+
     /// OTL tracing endpoint.
     #[arg(short('t'), long, default_value = None)]
     pub otl_endpoint: Option<url::Url>,
 }
+
+/// Parse a `name=value` form field.
+fn parse_form_field(field: &str) -> Result<(String, String), String> {
+    field
+        .split_once('=')
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected `name=value`, got `{}`", field))
+}
+
+/// Where the crawl's seed URLs come from.
+#[derive(Clone, Debug)]
+pub enum Seeds {
+    /// A single URL given on the command line.
+    Url(url::Url),
+    /// Newline-separated URLs read from stdin.
+    Stdin,
+}
+
+/// Parse the `url` argument, where `-` means stdin.
+fn parse_seeds(arg: &str) -> Result<Seeds, String> {
+    if arg == "-" {
+        return Ok(Seeds::Stdin);
+    }
+    url::Url::parse(arg)
+        .map(Seeds::Url)
+        .map_err(|e| e.to_string())
+}
+
+/// Seed URLs read from a list, and the lines that could not be parsed as URLs.
+#[derive(Debug, Default)]
+pub struct SeedList {
+    pub urls: Vec<url::Url>,
+    /// Line number (starting from 1) and content of each invalid line.
+    pub invalid: Vec<(usize, String)>,
+}
+
+/// Read newline-separated seed URLs. Blank lines are skipped.
+pub fn read_seeds(reader: impl BufRead) -> std::io::Result<SeedList> {
+    let mut seeds = SeedList::default();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match url::Url::parse(line) {
+            Ok(url) => seeds.urls.push(url),
+            Err(_) => seeds.invalid.push((index + 1, line.to_string())),
+        }
+    }
+    Ok(seeds)
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    /// Each visited page followed by its links.
+    Text,
+    /// An XML sitemap of successfully visited HTML pages.
+    Sitemap,
+    /// Each page that did not respond with a 2xx status, followed by the pages linking to it.
+    BrokenLinks,
+    /// Every URL discovered, visited or not, sorted with one per line.
+    Urls,
+    /// A JSON report of crawl totals, broken links, external links, orphaned pages and duplicated content.
+    Report,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Strategy {
+    BreadthFirst,
+    DepthFirst,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check whether a URL is allowed by a robots.txt file.
+    RobotsCheck(RobotsCheckArgs),
+}
+
+#[derive(Args)]
+pub struct RobotsCheckArgs {
+    /// URL or file path of the robots.txt file.
+    pub robots: String,
+
+    /// URL to check.
+    pub url: url::Url,
+
+    /// User agent to check for. Defaults to the crawler's user agent.
+    #[arg(short, long)]
+    pub agent: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::{read_seeds, Cli};
+
+    #[test]
+    fn test_read_seeds() -> anyhow::Result<()> {
+        let input =
+            "https://monzo.com/\n\n  https://monzo.com/about  \nnot a url\nhttp://example.com\n";
+        let seeds = read_seeds(input.as_bytes())?;
+
+        let urls: Vec<&str> = seeds.urls.iter().map(|url| url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://monzo.com/",
+                "https://monzo.com/about",
+                "http://example.com/"
+            ]
+        );
+        assert_eq!(seeds.invalid, [(4, "not a url".to_string())]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_limits_rejected() {
+        let parse = |args: &[&str]| {
+            Cli::try_parse_from(["spider_crab", "https://monzo.com/"].iter().chain(args))
+        };
+
+        assert!(parse(&["--max-concurrent-per-host", "0"]).is_err());
+        assert!(parse(&["--max-concurrent-per-host", "1"]).is_ok());
+        assert!(parse(&["--max-concurrent-dns", "0"]).is_err());
+        assert!(parse(&["--max-concurrent-dns", "1"]).is_ok());
+        assert!(parse(&["--slow-start", "0"]).is_err());
+        assert!(parse(&["--slow-start", "1"]).is_ok());
+        assert!(parse(&["--status-file", "status.json", "--status-interval", "0"]).is_err());
+        assert!(parse(&["--status-file", "status.json", "--status-interval", "1"]).is_ok());
+    }
+}
@@ -1,55 +1,490 @@
-use http::{Extensions, StatusCode};
-use reqwest::{Request, Response};
+use encoding_rs::{Encoding, UTF_8};
+use http::{Extensions, HeaderValue, Method, StatusCode};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::{CONTENT_TYPE, IF_NONE_MATCH, USER_AGENT},
+    redirect, Request, Response,
+};
 use reqwest_middleware::{ClientWithMiddleware, Middleware, Next};
 use std::{
+    collections::HashMap,
     fmt::{self},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::{Duration, SystemTime},
 };
 
+use thiserror::Error;
 use tokio::sync::Semaphore;
 use tracing::debug;
 
-use crate::{crawler::VisitorError, PageContent, SiteVisitor};
+use crate::{
+    clock::{Clock, TokioClock},
+    crawler::VisitorError,
+    feed::is_feed_content_type,
+    parser::is_html_content_type,
+    scope::{Scope, ScopeMode},
+    PageContent, SiteVisitor,
+};
 
 /// A [SiteVisitor] that uses a [ClientWithMiddleware] internally.
+///
+/// Redirects are followed by the client. To stop them leaving the crawl's scope, build the client
+/// with [scoped_redirect_policy]:
+///
+/// ```no_run
+/// use reqwest_middleware::ClientBuilder;
+/// use spider_crab::{
+///     client_middleware::scoped_redirect_policy, ClientWithMiddlewareVisitor, CrawlerBuilder,
+///     ScopeMode,
+/// };
+///
+/// # async fn crawl() -> anyhow::Result<()> {
+/// let seed = url::Url::parse("https://monzo.com/")?;
+/// let client = reqwest::Client::builder()
+///     .redirect(scoped_redirect_policy(ScopeMode::SameDomain, seed.clone(), 10))
+///     .build()?;
+/// let visitor = ClientWithMiddlewareVisitor::new(ClientBuilder::new(client).build());
+/// let pages = CrawlerBuilder::new(visitor).build().crawl(seed).await;
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Clone, Debug)]
 pub struct ClientWithMiddlewareVisitor {
     client: ClientWithMiddleware,
+    user_agents: Arc<Vec<String>>,
+    next_user_agent: Arc<AtomicUsize>,
+    strict_decoding: bool,
+    html_sniff: bool,
+    sniff_bytes: usize,
+    min_download_rate: Option<u64>,
+    min_rate_grace: Duration,
 }
 
 impl ClientWithMiddlewareVisitor {
     pub fn new(client: ClientWithMiddleware) -> Self {
-        Self { client }
+        Self {
+            client,
+            user_agents: Arc::new(Vec::new()),
+            next_user_agent: Arc::new(AtomicUsize::new(0)),
+            strict_decoding: false,
+            html_sniff: false,
+            sniff_bytes: 4096,
+            min_download_rate: None,
+            min_rate_grace: Duration::ZERO,
+        }
     }
-}
 
-impl SiteVisitor for ClientWithMiddlewareVisitor {
-    async fn visit(&mut self, url: url::Url) -> Result<PageContent, VisitorError> {
-        let response = self
-            .client
-            .get(url.as_str())
-            .send()
-            .await
-            .map_err(|e| VisitorError(e.into()))?;
+    /// Send each request with a `User-Agent` taken round-robin from `user_agents`, instead of the
+    /// client's own. Clones share the rotation.
+    ///
+    /// robots.txt rules are still evaluated for the agent given to
+    /// [CrawlerBuilder::with_robot](crate::CrawlerBuilder::with_robot), whatever agent is sent.
+    pub fn with_user_agent_pool(mut self, user_agents: Vec<String>) -> Self {
+        self.user_agents = Arc::new(user_agents);
+        self
+    }
+
+    /// Fail to visit pages containing byte sequences that are invalid in the page's charset. By
+    /// default such sequences are replaced with `U+FFFD`, so links can still be extracted from
+    /// partly binary pages.
+    pub fn with_strict_decoding(mut self, strict_decoding: bool) -> Self {
+        self.strict_decoding = strict_decoding;
+        self
+    }
+
+    /// Before downloading a successful response whose `Content-Type` is not HTML or a feed, read
+    /// the first [sniff bytes](ClientWithMiddlewareVisitor::with_sniff_bytes) of the body and
+    /// abandon the download unless they contain `<!doctype html` or `<html`. Abandoned visits
+    /// return [VisitorError::NotHtml], which the crawler counts as skipped.
+    ///
+    /// This avoids downloading large files linked from a site without a `HEAD` request first.
+    pub fn with_html_sniff(mut self, html_sniff: bool) -> Self {
+        self.html_sniff = html_sniff;
+        self
+    }
+
+    /// Set how many bytes of a body [with_html_sniff](ClientWithMiddlewareVisitor::with_html_sniff)
+    /// reads before deciding whether it is HTML. Defaults to 4096.
+    pub fn with_sniff_bytes(mut self, sniff_bytes: usize) -> Self {
+        self.sniff_bytes = sniff_bytes;
+        self
+    }
+
+    /// Abandon downloads whose body arrives slower than `bytes_per_sec`, such as from a server that
+    /// stalls part way through a transfer. Connecting and waiting for the response headers are not
+    /// limited, and the body may fall up to `grace` behind the rate before it is abandoned, so
+    /// pauses shorter than `grace` are always tolerated. Bytes arriving faster than the rate build
+    /// up at most `grace` of credit, so a stall after a fast start is still caught. Abandoned
+    /// visits fail with a [DownloadTooSlowError].
+    ///
+    /// Unlike a single timeout for the whole request, this gives large pages as long as they need
+    /// while they keep arriving. A rate of zero disables the limit.
+    pub fn with_min_download_rate(mut self, bytes_per_sec: u64, grace: Duration) -> Self {
+        self.min_download_rate = Some(bytes_per_sec).filter(|&rate| rate > 0);
+        self.min_rate_grace = grace;
+        self
+    }
+
+    /// The next `User-Agent` from the pool, if there is one.
+    fn next_user_agent(&self) -> Option<&str> {
+        if self.user_agents.is_empty() {
+            return None;
+        }
+        let index = self.next_user_agent.fetch_add(1, Ordering::Relaxed) % self.user_agents.len();
+        Some(&self.user_agents[index])
+    }
+
+    /// Request `url` with `method`, with an `If-None-Match` header if `etag` is given.
+    async fn send(
+        &mut self,
+        method: Method,
+        url: url::Url,
+        etag: Option<&str>,
+    ) -> Result<PageContent, VisitorError> {
+        let sniff = self.html_sniff && method == Method::GET;
+        let mut request = self.client.request(method, url.as_str());
+        if let Some(user_agent) = self.next_user_agent() {
+            request = request.header(USER_AGENT, user_agent);
+        }
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            if let Some(redirect_loop) = redirect_loop(&e) {
+                VisitorError::RedirectLoop {
+                    url: redirect_loop.url.clone(),
+                    hops: redirect_loop.hops,
+                }
+            } else if is_dns_error(&e) {
+                VisitorError::Dns {
+                    host: url.host_str().unwrap_or_default().to_string(),
+                    source: e.into(),
+                }
+            } else {
+                VisitorError::Request(e.into())
+            }
+        })?;
 
+        // Relative links resolve against where any redirects landed, not the requested URL
+        let url = response.url().clone();
         let status_code = response.status();
-        let mut headers = response.headers().clone();
+        let http_version = response.version();
+        let headers = response.headers().clone();
 
-        let content_type = headers.remove("Content-Type");
+        let content_type = headers.get(CONTENT_TYPE).cloned();
 
-        let content = response.text().await.map_err(|e| VisitorError(e.into()))?;
+        let body = BodyReader::new(response, self.min_download_rate, self.min_rate_grace);
+        let body = if sniff
+            && status_code.is_success()
+            && !is_html_content_type(content_type.as_ref())
+            && !is_feed_content_type(content_type.as_ref())
+        {
+            sniff_body(body, self.sniff_bytes)
+                .await?
+                .ok_or_else(|| VisitorError::NotHtml {
+                    url: url.clone(),
+                    content_type: content_type.clone(),
+                })?
+        } else {
+            body.read_to_end().await?
+        };
+        let content = decode_body(&body, content_type.as_ref(), self.strict_decoding)
+            .map_err(VisitorError::Request)?;
 
         Ok(PageContent {
             content,
             status_code,
             url,
             content_type,
+            headers,
+            http_version: Some(http_version),
         })
     }
 }
 
-/// A middleware that delays the next request if a `Retry-After` header is received.
+impl SiteVisitor for ClientWithMiddlewareVisitor {
+    async fn visit(&mut self, url: url::Url) -> Result<PageContent, VisitorError> {
+        self.send(Method::GET, url, None).await
+    }
+
+    async fn visit_if_none_match(
+        &mut self,
+        url: url::Url,
+        etag: &str,
+    ) -> Result<PageContent, VisitorError> {
+        self.send(Method::GET, url, Some(etag)).await
+    }
+
+    async fn head(&mut self, url: url::Url) -> Result<PageContent, VisitorError> {
+        self.send(Method::HEAD, url, None).await
+    }
+}
+
+/// Error raised by [ClientWithMiddlewareVisitor] when a body arrives slower than its
+/// [minimum download rate](ClientWithMiddlewareVisitor::with_min_download_rate).
+/// It is reported as [VisitorError::Request].
+#[derive(Error, Debug)]
+#[error("download of {url} fell below {min_rate} bytes/s after {received} bytes")]
+pub struct DownloadTooSlowError {
+    pub url: url::Url,
+    pub min_rate: u64,
+    pub received: u64,
+}
+
+/// Reads a response body in chunks, failing if it falls behind a minimum download rate.
+struct BodyReader {
+    response: Response,
+    min_download_rate: Option<u64>,
+    /// How far the body may fall behind the minimum rate.
+    grace: Duration,
+    /// When the next chunk must arrive by to keep up with the minimum rate.
+    due: tokio::time::Instant,
+    received: u64,
+}
+
+impl BodyReader {
+    fn new(response: Response, min_download_rate: Option<u64>, grace: Duration) -> Self {
+        Self {
+            response,
+            min_download_rate,
+            grace,
+            due: tokio::time::Instant::now() + grace,
+            received: 0,
+        }
+    }
+
+    /// Append the next chunk of the body to `body`, returning `false` once the body has ended.
+    async fn read_chunk(&mut self, body: &mut Vec<u8>) -> Result<bool, VisitorError> {
+        let chunk = match self.min_download_rate {
+            Some(min_rate) => tokio::time::timeout_at(self.due, self.response.chunk())
+                .await
+                .map_err(|_| {
+                    VisitorError::Request(
+                        DownloadTooSlowError {
+                            url: self.response.url().clone(),
+                            min_rate,
+                            received: self.received,
+                        }
+                        .into(),
+                    )
+                })?,
+            None => self.response.chunk().await,
+        }
+        .map_err(|e| VisitorError::Request(e.into()))?;
+
+        match chunk {
+            Some(chunk) => {
+                if let Some(min_rate) = self.min_download_rate {
+                    // Each chunk buys time at the minimum rate, but never more than the grace
+                    // from now, so a fast start does not excuse a later stall
+                    let earned = Duration::from_secs_f64(chunk.len() as f64 / min_rate as f64);
+                    self.due = (self.due + earned).min(tokio::time::Instant::now() + self.grace);
+                }
+                self.received += chunk.len() as u64;
+                body.extend_from_slice(&chunk);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn read_to_end(mut self) -> Result<Vec<u8>, VisitorError> {
+        let mut body = Vec::new();
+        while self.read_chunk(&mut body).await? {}
+        Ok(body)
+    }
+}
+
+/// Read the body of a response if its first `sniff_bytes` bytes contain an HTML signature,
+/// otherwise stop reading and return `None`.
+async fn sniff_body(
+    mut reader: BodyReader,
+    sniff_bytes: usize,
+) -> Result<Option<Vec<u8>>, VisitorError> {
+    let mut body = Vec::new();
+    let mut sniffed = false;
+    while reader.read_chunk(&mut body).await? {
+        if !sniffed && body.len() >= sniff_bytes {
+            if !has_html_signature(&body[..sniff_bytes]) {
+                return Ok(None);
+            }
+            sniffed = true;
+        }
+    }
+    Ok((sniffed || has_html_signature(&body)).then_some(body))
+}
+
+/// Whether the start of a body contains `<!doctype html` or `<html`, ignoring case.
+fn has_html_signature(prefix: &[u8]) -> bool {
+    let prefix = prefix.to_ascii_lowercase();
+    [b"<!doctype html".as_slice(), b"<html".as_slice()]
+        .iter()
+        .any(|signature| {
+            prefix
+                .windows(signature.len())
+                .any(|window| window == *signature)
+        })
+}
+
+/// Decode a response body using the charset from its `Content-Type`, defaulting to UTF-8.
+/// A byte order mark overrides the declared charset. Invalid byte sequences are an error if
+/// `strict`, and are otherwise replaced with `U+FFFD`.
+fn decode_body(
+    body: &[u8],
+    content_type: Option<&HeaderValue>,
+    strict: bool,
+) -> anyhow::Result<String> {
+    let declared = content_type
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| {
+            content_type.split(';').skip(1).find_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                name.trim()
+                    .eq_ignore_ascii_case("charset")
+                    .then(|| value.trim().trim_matches('"'))
+            })
+        })
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(UTF_8);
+    let (encoding, bom_length) = Encoding::for_bom(body).unwrap_or((declared, 0));
+    let body = &body[bom_length..];
+
+    if !strict {
+        let (content, _) = encoding.decode_without_bom_handling(body);
+        return Ok(content.into_owned());
+    }
+
+    encoding
+        .decode_without_bom_handling_and_without_replacement(body)
+        .map(|content| content.into_owned())
+        .ok_or_else(|| anyhow::anyhow!("Response body is not valid {}", encoding.name()))
+}
+
+/// Log in to a site by POSTing a form to `login_url`, so that the session cookies it sets are used by later requests.
+/// The client must be built with a cookie store (see [reqwest::ClientBuilder::cookie_store]).
+pub async fn login(
+    client: &ClientWithMiddleware,
+    login_url: &url::Url,
+    fields: &[(String, String)],
+) -> anyhow::Result<()> {
+    let response = client
+        .post(login_url.as_str())
+        .form(fields)
+        .send()
+        .await?
+        .error_for_status()?;
+    debug!("Logged in with status {}", response.status());
+    Ok(())
+}
+
+/// Find a [RedirectLoopError] in the sources of an error.
+fn redirect_loop(error: &reqwest_middleware::Error) -> Option<&RedirectLoopError> {
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        if let Some(redirect_loop) = error.downcast_ref::<RedirectLoopError>() {
+            return Some(redirect_loop);
+        }
+        source = error.source();
+    }
+    None
+}
+
+/// Whether an error was caused by failing to resolve a host name.
+/// The connector only exposes this through the message of its error.
+fn is_dns_error(error: &reqwest_middleware::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        if error.to_string().starts_with("dns error") {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Error raised by [scoped_redirect_policy] when a redirect chain revisits a URL or has too many hops.
+/// [ClientWithMiddlewareVisitor] reports it as [VisitorError::RedirectLoop].
+#[derive(Error, Debug)]
+#[error("redirect loop at {url} after {hops} redirects")]
+pub struct RedirectLoopError {
+    pub url: url::Url,
+    pub hops: usize,
+}
+
+/// A redirect policy that only follows redirects that stay within the crawl scope of `seed`.
+/// When a hop would leave the scope the redirect response is returned instead, so the crawler
+/// records it as a [Redirect](crate::Redirect) to an external URL.
+///
+/// In-scope redirects that revisit a URL already seen in the same chain, or that would take the
+/// chain beyond `max_redirects` hops, fail with a [RedirectLoopError].
+pub fn scoped_redirect_policy(
+    scope_mode: ScopeMode,
+    seed: url::Url,
+    max_redirects: usize,
+) -> redirect::Policy {
+    seeds_redirect_policy(scope_mode, [seed], max_redirects)
+}
+
+/// A [scoped_redirect_policy] for a crawl from several `seeds`. Each redirect chain is checked
+/// against the scope of the seeds its first request is in scope of, so redirects on any seed's
+/// host are followed.
+pub fn seeds_redirect_policy(
+    scope_mode: ScopeMode,
+    seeds: impl IntoIterator<Item = url::Url>,
+    max_redirects: usize,
+) -> redirect::Policy {
+    let scopes: Vec<Scope> = seeds
+        .into_iter()
+        .map(|seed| Scope::new(scope_mode.clone(), seed))
+        .collect();
+
+    redirect::Policy::custom(move |attempt| {
+        let hops = attempt.previous().len();
+        if hops > max_redirects || attempt.previous().contains(attempt.url()) {
+            let error = RedirectLoopError {
+                url: attempt.url().clone(),
+                hops,
+            };
+            return attempt.error(error);
+        }
+
+        let in_scope = match (attempt.previous().first(), attempt.previous().last()) {
+            (Some(first), Some(previous)) => scopes
+                .iter()
+                .filter(|scope| scope.contains(first, first))
+                .any(|scope| scope.contains(previous, attempt.url())),
+            _ => false,
+        };
+        if in_scope {
+            attempt.follow()
+        } else {
+            debug!("Not following out of scope redirect to {}", attempt.url());
+            attempt.stop()
+        }
+    })
+}
+
+/// A [scoped_redirect_policy] using the default [ScopeMode], for building the client of a
+/// [ClientWithMiddlewareVisitor] that crawls from `seed`.
+pub fn redirect_policy_same_scope(seed: url::Url, max_redirects: usize) -> redirect::Policy {
+    scoped_redirect_policy(ScopeMode::default(), seed, max_redirects)
+}
+
+/// The host and port a request is made to.
+fn host_key(url: &url::Url) -> String {
+    format!(
+        "{}:{}",
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    )
+}
+
+/// A middleware that delays the next request to a host if it responds with a `Retry-After` header.
+/// Requests to other hosts are not delayed.
 /// It does not retry the requests on its own. It can be used in conjunction with a retry middleware (see example).
 ///
 /// # Example
@@ -75,8 +510,10 @@ impl SiteVisitor for ClientWithMiddlewareVisitor {
 ///     .build();
 /// ````
 pub struct RetryTooManyRequestsMiddleware {
-    retry_after: tokio::sync::RwLock<Option<SystemTime>>,
+    /// When requests to each host (and port) may resume.
+    retry_after: tokio::sync::RwLock<HashMap<String, SystemTime>>,
     default_retry_after: Duration,
+    clock: Arc<dyn Clock>,
 }
 
 impl std::fmt::Debug for RetryTooManyRequestsMiddleware {
@@ -90,10 +527,17 @@ impl std::fmt::Debug for RetryTooManyRequestsMiddleware {
 impl RetryTooManyRequestsMiddleware {
     pub fn new(default_retry_after: Duration) -> Self {
         Self {
-            retry_after: tokio::sync::RwLock::new(None),
+            retry_after: tokio::sync::RwLock::new(HashMap::new()),
             default_retry_after,
+            clock: Arc::new(TokioClock::new()),
         }
     }
+
+    /// Set the [Clock] used to wait for `Retry-After` delays. Defaults to [TokioClock].
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -105,15 +549,16 @@ impl Middleware for RetryTooManyRequestsMiddleware {
         extensions: &mut Extensions,
         next: Next<'_>,
     ) -> reqwest_middleware::Result<Response> {
-        let retry_after = *self.retry_after.read().await;
+        let host = host_key(req.url());
+        let retry_after = self.retry_after.read().await.get(&host).copied();
 
         if let Some(retry_after) = retry_after {
-            let now = SystemTime::now();
+            let now = self.clock.now();
             if let Ok(duration) = retry_after.duration_since(now) {
-                debug!("Sleeping for {:?}", duration);
-                tokio::time::sleep(duration).await;
+                debug!("Sleeping for {:?} before requesting {}", duration, host);
+                self.clock.sleep(duration).await;
             } else {
-                *self.retry_after.write().await = None;
+                self.retry_after.write().await.remove(&host);
             }
         }
 
@@ -130,7 +575,7 @@ impl Middleware for RetryTooManyRequestsMiddleware {
                                     debug!("Retry-After header is greater than 60 seconds.");
                                     seconds = 60;
                                 }
-                                let retry_after = SystemTime::now() + Duration::from_secs(seconds);
+                                let retry_after = self.clock.now() + Duration::from_secs(seconds);
 
                                 Some(retry_after)
                             }
@@ -148,15 +593,14 @@ impl Middleware for RetryTooManyRequestsMiddleware {
                         }
                     };
 
-                    if retry_after.is_none() {
-                        *self.retry_after.write().await =
-                            Some(SystemTime::now() + self.default_retry_after);
-                    } else {
-                        *self.retry_after.write().await = retry_after;
-                    }
+                    let retry_after =
+                        retry_after.unwrap_or_else(|| self.clock.now() + self.default_retry_after);
+                    self.retry_after.write().await.insert(host, retry_after);
                 } else {
-                    *self.retry_after.write().await =
-                        Some(SystemTime::now() + self.default_retry_after);
+                    self.retry_after
+                        .write()
+                        .await
+                        .insert(host, self.clock.now() + self.default_retry_after);
                 }
             }
         }
@@ -164,25 +608,275 @@ impl Middleware for RetryTooManyRequestsMiddleware {
     }
 }
 
+/// A middleware that spaces out requests to a host when it responds slowly, as a sign that it is
+/// under load. Each response slower than the latency threshold multiplies the host's delay between
+/// requests by the increase factor (starting from the initial delay), and each faster response
+/// multiplies it by the decrease factor until it falls below the initial delay and is removed.
+/// Requests to other hosts are not delayed.
+pub struct LatencyBackoffMiddleware {
+    latency_threshold: Duration,
+    initial_delay: Duration,
+    max_delay: Duration,
+    increase_factor: f64,
+    decrease_factor: f64,
+    /// The delay between requests to each host (and port), and when the next request may start.
+    hosts: std::sync::Mutex<HashMap<String, HostBackoff>>,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct HostBackoff {
+    delay: Duration,
+    next_slot: SystemTime,
+}
+
+impl std::fmt::Debug for LatencyBackoffMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LatencyBackoffMiddleware")
+            .field("latency_threshold", &self.latency_threshold)
+            .field("initial_delay", &self.initial_delay)
+            .field("max_delay", &self.max_delay)
+            .field("increase_factor", &self.increase_factor)
+            .field("decrease_factor", &self.decrease_factor)
+            .finish()
+    }
+}
+
+impl LatencyBackoffMiddleware {
+    /// Back off from hosts whose responses take longer than `latency_threshold`.
+    /// The delay starts at 100ms, doubles with each slow response up to 30s, and halves with each
+    /// fast response.
+    pub fn new(latency_threshold: Duration) -> Self {
+        Self {
+            latency_threshold,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            increase_factor: 2.0,
+            decrease_factor: 0.5,
+            hosts: std::sync::Mutex::new(HashMap::new()),
+            clock: Arc::new(TokioClock::new()),
+        }
+    }
+
+    /// Set the delay applied after the first slow response from a host. Default is 100ms.
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Set the largest delay between requests to a host. Default is 30s.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set the factor the delay is multiplied by after a slow response. Default is 2.
+    pub fn with_increase_factor(mut self, increase_factor: f64) -> Self {
+        self.increase_factor = increase_factor;
+        self
+    }
+
+    /// Set the factor the delay is multiplied by after a fast response. Default is 0.5.
+    pub fn with_decrease_factor(mut self, decrease_factor: f64) -> Self {
+        self.decrease_factor = decrease_factor;
+        self
+    }
+
+    /// Set the [Clock] used to measure latency and wait between requests. Defaults to [TokioClock].
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The current delay between requests to the host (and port) of `url`.
+    pub fn delay(&self, url: &url::Url) -> Duration {
+        self.hosts
+            .lock()
+            .expect("Could not acquire lock")
+            .get(&host_key(url))
+            .map(|backoff| backoff.delay)
+            .unwrap_or_default()
+    }
+
+    /// Reserve the next request slot for `host`, returning how long to wait for it.
+    fn reserve(&self, host: &str) -> Option<Duration> {
+        let mut hosts = self.hosts.lock().expect("Could not acquire lock");
+        let backoff = hosts.get_mut(host)?;
+        let now = self.clock.now();
+        let slot = backoff.next_slot.max(now);
+        backoff.next_slot = slot + backoff.delay;
+        slot.duration_since(now).ok()
+    }
+
+    /// Adjust the delay for `host` after a response that took `latency`.
+    fn record(&self, host: String, latency: Duration) {
+        let mut hosts = self.hosts.lock().expect("Could not acquire lock");
+        if latency > self.latency_threshold {
+            let backoff = hosts.entry(host).or_insert(HostBackoff {
+                delay: Duration::ZERO,
+                next_slot: self.clock.now(),
+            });
+            let delay = if backoff.delay.is_zero() {
+                self.initial_delay
+            } else {
+                backoff.delay.mul_f64(self.increase_factor)
+            };
+            backoff.delay = delay.min(self.max_delay);
+            debug!("Slow response. Delaying requests by {:?}", backoff.delay);
+        } else if let Some(backoff) = hosts.get_mut(&host) {
+            backoff.delay = backoff.delay.mul_f64(self.decrease_factor);
+            if backoff.delay < self.initial_delay {
+                hosts.remove(&host);
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for LatencyBackoffMiddleware {
+    #[tracing::instrument(name = "LatencyBackoffMiddleware", skip_all)]
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let host = host_key(req.url());
+        if let Some(wait) = self.reserve(&host).filter(|wait| !wait.is_zero()) {
+            debug!("Sleeping for {:?} before requesting {}", wait, host);
+            self.clock.sleep(wait).await;
+        }
+
+        let start = self.clock.now();
+        let result = next.run(req, extensions).await;
+        if result.is_ok() {
+            let latency = self.clock.now().duration_since(start).unwrap_or_default();
+            self.record(host, latency);
+        }
+        result
+    }
+}
+
 /// A middleware that limits the number of concurrent requests being made by the client.
+///
+/// With [MaxConcurrentMiddleware::with_slow_start], a `429 Too Many Requests` response from any
+/// host is treated as a sign the whole site is stressed: the limit shrinks for every request, then
+/// grows back over time.
 pub struct MaxConcurrentMiddleware {
     semaphore: Arc<Semaphore>,
+    max_concurrent: usize,
+    slow_start: Option<SlowStart>,
+    limit: std::sync::Mutex<ConcurrencyLimit>,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SlowStart {
+    shrink_factor: f64,
+    recovery_interval: Duration,
+}
+
+#[derive(Debug)]
+struct ConcurrencyLimit {
+    /// The current number of requests allowed in flight.
+    limit: usize,
+    /// Permits to forget as in-flight requests complete, because they were held when the limit shrank.
+    owed: usize,
+    /// When the limit last shrank or grew by one.
+    changed_at: SystemTime,
 }
 
 impl std::fmt::Debug for MaxConcurrentMiddleware {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("MaxConcurrentMiddleware")
             .field("available_permits", &self.semaphore.available_permits())
+            .field("max_concurrent", &self.max_concurrent)
+            .field("slow_start", &self.slow_start)
+            .field("limit", &self.limit)
             .finish()
     }
 }
 
 impl MaxConcurrentMiddleware {
     pub fn new(max_concurrent: usize) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(TokioClock::new());
         Self {
             semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            slow_start: None,
+            limit: std::sync::Mutex::new(ConcurrencyLimit {
+                limit: max_concurrent,
+                owed: 0,
+                changed_at: clock.now(),
+            }),
+            clock,
         }
     }
+
+    /// After each `429 Too Many Requests` response, multiply the limit by `shrink_factor` (keeping
+    /// at least one request in flight), then raise it by one every `recovery_interval` until it is
+    /// back to the maximum. Off by default.
+    pub fn with_slow_start(mut self, shrink_factor: f64, recovery_interval: Duration) -> Self {
+        self.slow_start = Some(SlowStart {
+            shrink_factor,
+            recovery_interval,
+        });
+        self
+    }
+
+    /// Set the [Clock] used to recover from slow start. Defaults to [TokioClock].
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.limit
+            .get_mut()
+            .expect("Could not acquire lock")
+            .changed_at = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// The number of requests currently allowed in flight.
+    pub fn limit(&self) -> usize {
+        let mut limit = self.limit.lock().expect("Could not acquire lock");
+        self.recover(&mut limit);
+        limit.limit
+    }
+
+    /// Raise the limit by one for each recovery interval since it last changed.
+    fn recover(&self, limit: &mut ConcurrencyLimit) {
+        let Some(slow_start) = self.slow_start else {
+            return;
+        };
+        let now = self.clock.now();
+        while limit.limit < self.max_concurrent
+            && now
+                .duration_since(limit.changed_at)
+                .is_ok_and(|elapsed| elapsed >= slow_start.recovery_interval)
+        {
+            if limit.owed > 0 {
+                limit.owed -= 1;
+            } else {
+                self.semaphore.add_permits(1);
+            }
+            limit.limit += 1;
+            limit.changed_at += slow_start.recovery_interval;
+        }
+    }
+
+    /// Shrink the limit after a `429 Too Many Requests` response.
+    fn shrink(&self, limit: &mut ConcurrencyLimit) {
+        let Some(slow_start) = self.slow_start else {
+            return;
+        };
+        let shrunk = ((limit.limit as f64 * slow_start.shrink_factor) as usize).max(1);
+        let removed = limit.limit.saturating_sub(shrunk);
+        limit.owed += removed - self.semaphore.forget_permits(removed);
+        limit.limit -= removed;
+        limit.changed_at = self.clock.now();
+        debug!(
+            "Too many requests. Concurrency limit is now {}",
+            limit.limit
+        );
+    }
 }
 
 /// A middleware that limits the number of concurrent requests being made by the client.
@@ -195,7 +889,8 @@ impl Middleware for MaxConcurrentMiddleware {
         extensions: &mut Extensions,
         next: Next<'_>,
     ) -> reqwest_middleware::Result<Response> {
-        let _permit = self
+        self.recover(&mut self.limit.lock().expect("Could not acquire lock"));
+        let permit = self
             .semaphore
             .clone()
             .acquire_owned()
@@ -208,8 +903,125 @@ impl Middleware for MaxConcurrentMiddleware {
 
         let res = next.clone().run(req, extensions).await;
 
-        drop(_permit);
+        {
+            let mut limit = self.limit.lock().expect("Could not acquire lock");
+            self.recover(&mut limit);
+            if res
+                .as_ref()
+                .is_ok_and(|resp| resp.status() == StatusCode::TOO_MANY_REQUESTS)
+            {
+                self.shrink(&mut limit);
+            }
+            if limit.owed > 0 {
+                limit.owed -= 1;
+                permit.forget();
+            } else {
+                drop(permit);
+            }
+        }
         debug!("dropped permit: {}", self.semaphore.available_permits());
         res
     }
 }
+
+/// A middleware that limits the number of concurrent requests being made to each host (and port),
+/// so one host cannot use up a global limit and starve the others.
+/// It can be used in conjunction with [MaxConcurrentMiddleware] to also limit the total.
+pub struct PerHostConcurrentMiddleware {
+    max_per_host: usize,
+    semaphores: std::sync::Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl std::fmt::Debug for PerHostConcurrentMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PerHostConcurrentMiddleware")
+            .field("max_per_host", &self.max_per_host)
+            .finish()
+    }
+}
+
+impl PerHostConcurrentMiddleware {
+    /// Allow `max_per_host` requests to each host at once, and at least one.
+    pub fn new(max_per_host: usize) -> Self {
+        Self {
+            max_per_host: max_per_host.max(1),
+            semaphores: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The semaphore limiting requests to `host`, created on first use.
+    fn semaphore(&self, host: String) -> Arc<Semaphore> {
+        self.semaphores
+            .lock()
+            .expect("Could not acquire lock")
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_per_host)))
+            .clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for PerHostConcurrentMiddleware {
+    #[tracing::instrument(name = "PerHostConcurrentMiddleware", skip(req, extensions, next))]
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let host = host_key(req.url());
+        let _permit = self
+            .semaphore(host.clone())
+            .acquire_owned()
+            .await
+            .expect("Could not acquire semaphore because it was closed. This is a bug."); // Permit released on drop.
+        debug!("Acquired semaphore permit for {}", host);
+
+        next.run(req, extensions).await
+    }
+}
+
+/// A DNS resolver that allows at most `max_concurrent` lookups at once, so a crawl spanning many
+/// hosts does not overwhelm the resolver. Set it with [reqwest::ClientBuilder::dns_resolver].
+///
+/// Lookups are throttled independently of the request limits of [MaxConcurrentMiddleware] and
+/// [PerHostConcurrentMiddleware], as connections to hosts already resolved need no lookup.
+pub struct LimitedResolver {
+    semaphore: Arc<Semaphore>,
+    resolver: Option<Arc<dyn Resolve>>,
+}
+
+impl LimitedResolver {
+    /// Limit lookups made with the system resolver. At least one lookup is allowed at once.
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            resolver: None,
+        }
+    }
+
+    /// Limit lookups made by `resolver` instead of the system resolver.
+    pub fn with_resolver(mut self, resolver: Arc<dyn Resolve>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+}
+
+impl Resolve for LimitedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let semaphore = self.semaphore.clone();
+        let resolver = self.resolver.clone();
+        Box::pin(async move {
+            let _permit = semaphore.acquire_owned().await?; // Permit released on drop.
+            debug!("Resolving {}", name.as_str());
+            match resolver {
+                Some(resolver) => resolver.resolve(name).await,
+                None => {
+                    let addrs: Vec<_> =
+                        tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+                    Ok(Box::new(addrs.into_iter()) as Addrs)
+                }
+            }
+        })
+    }
+}
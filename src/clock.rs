@@ -0,0 +1,72 @@
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    time::{Duration, SystemTime},
+};
+
+/// A source of time for the [Crawler](crate::Crawler) and client middleware, so time-based limits
+/// can be tested without waiting on the wall clock.
+pub trait Clock: Debug + Send + Sync {
+    /// The current time.
+    fn now(&self) -> SystemTime;
+
+    /// Wait for `duration` to pass.
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// The default [Clock], driven by Tokio's timer.
+///
+/// Time is measured from when the clock was created, so it follows the Tokio clock when it is
+/// paused and advanced with [tokio::time::pause] and [tokio::time::advance] in tests.
+#[derive(Debug, Clone)]
+pub struct TokioClock {
+    origin: SystemTime,
+    started: tokio::time::Instant,
+}
+
+impl TokioClock {
+    pub fn new() -> Self {
+        Self {
+            origin: SystemTime::now(),
+            started: tokio::time::Instant::now(),
+        }
+    }
+}
+
+impl Default for TokioClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for TokioClock {
+    fn now(&self) -> SystemTime {
+        self.origin + self.started.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Clock, TokioClock};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokio_clock_follows_paused_time() -> anyhow::Result<()> {
+        let clock = TokioClock::new();
+        let start = clock.now();
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert_eq!(clock.now().duration_since(start)?, Duration::from_secs(60));
+
+        clock.sleep(Duration::from_secs(30)).await;
+        assert_eq!(clock.now().duration_since(start)?, Duration::from_secs(90));
+
+        Ok(())
+    }
+}
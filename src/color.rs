@@ -0,0 +1,50 @@
+use owo_colors::Style;
+
+use crate::cli::ColorChoice;
+
+/// Styles for terminal output, which are plain when colour is disabled so no ANSI codes are emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colors {
+    enabled: bool,
+}
+
+impl Colors {
+    /// Colours for a stream, honouring `choice` and, with [ColorChoice::Auto], whether the stream
+    /// is a terminal.
+    pub fn new(choice: ColorChoice, is_terminal: bool) -> Self {
+        let enabled = match choice {
+            ColorChoice::Auto => is_terminal,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+        Self { enabled }
+    }
+
+    fn style(self, style: Style) -> Style {
+        if self.enabled {
+            style
+        } else {
+            Style::new()
+        }
+    }
+
+    pub fn bold(self) -> Style {
+        self.style(Style::new().bold())
+    }
+
+    pub fn green(self) -> Style {
+        self.style(Style::new().green())
+    }
+
+    pub fn cyan(self) -> Style {
+        self.style(Style::new().cyan())
+    }
+
+    pub fn yellow(self) -> Style {
+        self.style(Style::new().yellow())
+    }
+
+    pub fn red(self) -> Style {
+        self.style(Style::new().red())
+    }
+}
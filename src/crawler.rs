@@ -1,31 +1,161 @@
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     future::Future,
     sync::Arc,
     time::{Duration, SystemTime},
 };
 
-use http::HeaderValue;
+use http::{
+    header::{CONTENT_LENGTH, ETAG},
+    HeaderMap, HeaderValue, Version,
+};
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use reqwest::StatusCode;
+use scraper::Selector;
+use serde::Serialize;
 use texting_robots::Robot;
 use thiserror::Error;
-use tokio::{sync::broadcast, task::JoinSet};
-use tracing::{debug, error, info, Instrument};
+use tokio::{
+    sync::{broadcast, mpsc, Mutex},
+    task::{AbortHandle, Id, JoinSet},
+};
+use tracing::{debug, error, info, warn, Instrument};
 use url::Url;
 
-use crate::parser::{assume_html, parse_links, AllPages, Page};
+use crate::{
+    clock::{Clock, TokioClock},
+    etag_cache::EtagCache,
+    feed::is_feed_content_type,
+    live_stats::LiveStats,
+    memory::{MemoryReader, ProcessMemoryReader},
+    normalize::{
+        collapse_index_file, normalize_encoding, normalize_trailing_slash, sort_query, Normalize,
+    },
+    page_store::PageStore,
+    parse_pool::ParsePool,
+    parser::{
+        assume_feed, assume_html, fnv1a, is_html_content_type, parse_page, parse_timed_out_page,
+        AllPages, DownloadCheck, FailedVisit, FailureReason, LinkTable, Page, SkipReason,
+    },
+    scope::{Scope, ScopeMode},
+};
 
 /// An error from ths vistor. Assumes all recoverable errors have been handled and just reporting to caller.
 #[derive(Error, Debug)]
-#[error("failed to make a request")]
-pub struct VisitorError(pub anyhow::Error);
+pub enum VisitorError {
+    /// The request could not be completed.
+    #[error("failed to make a request")]
+    Request(#[source] anyhow::Error),
+    /// A chain of redirects looped back on itself or exceeded the redirect limit.
+    #[error("redirect loop at {url} after {hops} redirects")]
+    RedirectLoop { url: Url, hops: usize },
+    /// The host name could not be resolved.
+    #[error("failed to resolve host {host}")]
+    Dns {
+        host: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// The response was not HTML, so its body was not downloaded.
+    /// See [ClientWithMiddlewareVisitor::with_html_sniff](crate::ClientWithMiddlewareVisitor::with_html_sniff).
+    #[error("response from {url} is not HTML")]
+    NotHtml {
+        url: Url,
+        content_type: Option<HeaderValue>,
+    },
+}
+
+impl VisitorError {
+    /// Whether the visit might succeed if tried again: a failed DNS lookup, or a request that timed
+    /// out or could not connect. See [CrawlerBuilder::with_page_retry].
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            VisitorError::Request(e) => e.chain().any(is_transient),
+            VisitorError::RedirectLoop { .. } | VisitorError::NotHtml { .. } => false,
+            VisitorError::Dns { .. } => true,
+        }
+    }
+}
+
+/// Whether an error is a timeout or connection failure.
+fn is_transient(error: &(dyn std::error::Error + 'static)) -> bool {
+    if let Some(reqwest_middleware::Error::Reqwest(e)) = error.downcast_ref() {
+        return e.is_timeout() || e.is_connect();
+    }
+    if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+        return e.is_timeout() || e.is_connect();
+    }
+    if let Some(e) = error.downcast_ref::<std::io::Error>() {
+        return matches!(
+            e.kind(),
+            std::io::ErrorKind::TimedOut
+                | std::io::ErrorKind::ConnectionRefused
+                | std::io::ErrorKind::ConnectionReset
+                | std::io::ErrorKind::ConnectionAborted
+                | std::io::ErrorKind::NotConnected
+        );
+    }
+    error.is::<tokio::time::error::Elapsed>()
+}
+
+/// Something that happened during a crawl, sent to the channel given to
+/// [CrawlerBuilder::with_event_sender].
+#[derive(Debug, Clone)]
+pub enum CrawlEvent {
+    /// A page was crawled, as sent to [Crawler::subscribe].
+    Page(Arc<Page>),
+    /// An external URL was discovered for the first time, as sent to
+    /// [Crawler::subscribe_external_links].
+    ExternalLink(Url),
+}
+
+/// How failed visits are retried. See [CrawlerBuilder::with_page_retry].
+#[derive(Clone, Copy, Debug)]
+struct PageRetry {
+    max_retries: u32,
+    backoff: Duration,
+}
+
+/// The longest a retry waits, however many retries came before it.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(300);
+
+impl PageRetry {
+    /// How long to wait before retry number `attempt`, counting from one.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff
+            .checked_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .unwrap_or(Duration::MAX)
+            .min(MAX_RETRY_BACKOFF)
+    }
+}
+
+/// A fatal problem that stopped a crawl started with [Crawler::try_crawl].
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CrawlError {
+    /// The crawler was built with settings that cannot crawl anything.
+    #[error("invalid configuration: {0}")]
+    InvalidConfiguration(String),
+    /// No seed could be requested, because of its scheme, port or robots.txt rules.
+    #[error("seed URL {url} is disallowed")]
+    SeedDisallowed { url: Url },
+    /// No seed could be visited.
+    #[error("seed URL {url} could not be visited: {reason:?}")]
+    SeedUnreachable { url: Url, reason: FailureReason },
+}
 
 /// Contents of a page.
 pub struct PageContent {
+    /// The URL the content was served from. If the visitor followed redirects this is the final
+    /// URL, which relative links on the page are resolved against.
     pub url: Url,
     pub status_code: StatusCode,
     pub content: String,
     pub content_type: Option<HeaderValue>,
+    /// All response headers.
+    pub headers: HeaderMap,
+    /// The HTTP version of the response, if known.
+    pub http_version: Option<Version>,
 }
 
 /// A trait for visiting a URL and returning the contents of its page.
@@ -33,6 +163,150 @@ pub trait SiteVisitor: Clone + Send + 'static {
     /// Visit a URL and return the contents of the page as a [PageContent].
     fn visit(&mut self, url: Url)
         -> impl Future<Output = Result<PageContent, VisitorError>> + Send;
+
+    /// Visit a URL with an `If-None-Match: etag` header, so the server may respond
+    /// `304 Not Modified` instead of sending the page again.
+    /// Defaults to an unconditional [visit](SiteVisitor::visit).
+    fn visit_if_none_match(
+        &mut self,
+        url: Url,
+        etag: &str,
+    ) -> impl Future<Output = Result<PageContent, VisitorError>> + Send {
+        let _ = etag;
+        self.visit(url)
+    }
+
+    /// Request a URL's headers without its body, with a `HEAD` request.
+    /// Defaults to a full [visit](SiteVisitor::visit).
+    fn head(&mut self, url: Url) -> impl Future<Output = Result<PageContent, VisitorError>> + Send {
+        self.visit(url)
+    }
+}
+
+/// Decides whether the crawler may visit a URL. See [CrawlerBuilder::with_robots_matcher].
+type RobotsMatcher = Arc<dyn Fn(&Url) -> bool + Send + Sync>;
+
+/// Settings shared by every visit in a crawl.
+#[derive(Clone)]
+struct VisitSettings {
+    scope: Arc<Scope>,
+    request_spacer: Option<RequestSpacer>,
+    parse_timeout: Option<Duration>,
+    link_region: Option<Arc<Selector>>,
+    parse_noscript: bool,
+    max_anchor_text_len: Option<usize>,
+    parse_pool: Option<Arc<ParsePool>>,
+    retain_content: bool,
+    etag_cache: Option<Arc<EtagCache>>,
+    clock: Arc<dyn Clock>,
+}
+
+/// The order a [Crawler] visits the pages it discovers in. See [CrawlerBuilder::with_strategy].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum CrawlStrategy {
+    /// Visit pages in order of how many links they are from a seed. No page is visited until
+    /// every shallower page has been, so a crawl that stops early has visited the shallowest pages.
+    BreadthFirst,
+    /// Visit the most recently discovered pages first, following each chain of links as deep as
+    /// it goes before returning to the pages discovered earlier.
+    DepthFirst,
+}
+
+/// A running visit task.
+struct InFlight {
+    /// The URL being visited.
+    url: Url,
+    /// The scope links found on the page are checked against.
+    scope: Arc<Scope>,
+    /// The page the URL was first discovered on, or `None` for a seed.
+    parent: Option<Url>,
+    /// The number of earlier visits to the URL that failed and were retried.
+    attempt: u32,
+    /// The number of links followed from a seed to reach the URL.
+    depth: usize,
+}
+
+/// A visit waiting to be started.
+struct Queued {
+    url: Url,
+    scope: Arc<Scope>,
+    parent: Option<Url>,
+    /// The number of earlier visits to the URL that failed and were retried.
+    attempt: u32,
+    depth: usize,
+}
+
+/// Spaces out visits so that consecutive requests start at least `delay` apart.
+/// Clones share the same schedule.
+#[derive(Clone, Debug)]
+struct RequestSpacer {
+    delay: Duration,
+    clock: Arc<dyn Clock>,
+    next_slot: Arc<Mutex<SystemTime>>,
+}
+
+impl RequestSpacer {
+    fn new(delay: Duration, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            delay,
+            next_slot: Arc::new(Mutex::new(clock.now())),
+            clock,
+        }
+    }
+
+    /// Wait until it is this caller's turn to make a request.
+    async fn wait(&self) {
+        let (slot, now) = {
+            let mut next_slot = self.next_slot.lock().await;
+            let now = self.clock.now();
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + self.delay;
+            (slot, now)
+        };
+        if let Ok(wait) = slot.duration_since(now) {
+            self.clock.sleep(wait).await;
+        }
+    }
+}
+
+/// A handle for controlling a running crawl. Get one with [Crawler::control].
+#[derive(Clone, Debug, Default)]
+pub struct CrawlControl {
+    abort_handles: Arc<std::sync::Mutex<HashMap<Url, AbortHandle>>>,
+}
+
+impl CrawlControl {
+    /// Abort the in-flight visit to `url` without stopping the rest of the crawl.
+    /// The URL is recorded as failed with [FailureReason::Cancelled].
+    /// Returns `false` if no visit to `url` is in flight.
+    pub fn abort_url(&self, url: &Url) -> bool {
+        let abort_handles = self
+            .abort_handles
+            .lock()
+            .expect("Abort handles lock poisoned. This is a bug.");
+
+        match abort_handles.get(url) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&self, url: Url, handle: AbortHandle) {
+        self.abort_handles
+            .lock()
+            .expect("Abort handles lock poisoned. This is a bug.")
+            .insert(url, handle);
+    }
+
+    fn remove(&self, url: &Url) {
+        self.abort_handles
+            .lock()
+            .expect("Abort handles lock poisoned. This is a bug.")
+            .remove(url);
+    }
 }
 
 /// Web crawler.
@@ -43,135 +317,1194 @@ where
     V: SiteVisitor,
 {
     site_visitor: V,
-    robot: Option<Robot>,
-    tasks: JoinSet<Result<Page, VisitorError>>,
+    robots_matcher: Option<RobotsMatcher>,
+    schedule_seed: Option<u64>,
+    strategy: Option<CrawlStrategy>,
+    max_in_flight: usize,
+    ignore_robots_for_seed_host: bool,
+    /// Hosts of the crawl's seeds that bypass robots.txt. Only filled in when
+    /// `ignore_robots_for_seed_host` is set.
+    seed_hosts: HashSet<String>,
+    tasks: JoinSet<Result<Option<Page>, VisitorError>>,
+    in_flight: HashMap<Id, InFlight>,
+    control: CrawlControl,
+    live_stats: LiveStats,
     channel: broadcast::Sender<Arc<Page>>,
+    external_channel: broadcast::Sender<Url>,
+    event_sender: Option<mpsc::Sender<CrawlEvent>>,
     max_time: Option<std::time::Duration>,
+    deadline: Option<SystemTime>,
     max_pages: Option<u64>,
+    max_bytes: Option<u64>,
+    max_tracked_urls: Option<usize>,
+    memory_limit: Option<u64>,
+    host_failure_threshold: Option<usize>,
+    page_retry: Option<PageRetry>,
+    shutdown_drain: Option<Duration>,
+    scope_mode: ScopeMode,
+    allowed_schemes: HashSet<String>,
+    allowed_ports: Option<HashSet<u16>>,
+    allowed_path_prefixes: Option<Vec<String>>,
+    trailing_slash: Normalize,
+    normalize_encoding: bool,
+    sorted_query_dedup: bool,
+    index_files: Vec<String>,
+    verify_content_type: bool,
+    probe_content_types: bool,
+    check_downloads: bool,
+    preexcluded: HashSet<Url>,
+    prior_manifest: HashMap<Url, u64>,
+    skip_unchanged_subtrees: bool,
+    parse_timeout: Option<Duration>,
+    link_region: Option<(String, Arc<Selector>)>,
+    parse_noscript: bool,
+    max_anchor_text_len: Option<usize>,
+    parse_pool: Option<Arc<ParsePool>>,
+    retain_content: bool,
+    etag_cache: Option<Arc<EtagCache>>,
+    retain_links: bool,
+    intern_links: bool,
+    respect_nofollow: bool,
+    respect_meta_robots: bool,
+    request_spacer: Option<RequestSpacer>,
+    page_store: Option<Arc<dyn PageStore>>,
+    clock: Arc<dyn Clock>,
+    memory_reader: Arc<dyn MemoryReader>,
 }
 
 impl<V> Crawler<V>
 where
     V: SiteVisitor,
 {
-    /// Check if the crawler can visit a URL. If no [Robot] is provided assume we can visit any URL.
+    /// Check if the crawler can visit a URL. If no [Robot] is provided assume we can visit any URL
+    /// with an allowed scheme.
     fn can_visit(&self, url: &Url) -> bool {
-        assume_html(url)
-            && self
-                .robot
-                .as_ref()
-                .map_or(true, |robot| robot.allowed(url.as_str()))
+        (self.verify_content_type || assume_html(url) || assume_feed(url)) && self.can_follow(url)
+    }
+
+    /// Whether a visit to `url` must check the response's `Content-Type` before parsing it.
+    /// URLs that look like feeds are always checked, so other XML is not parsed as HTML.
+    fn checks_content_type(&self, url: &Url) -> bool {
+        (self.verify_content_type && !assume_html(url)) || assume_feed(url)
+    }
+
+    /// Check if the crawler may follow a link to a URL, ignoring whether it looks like an HTML page.
+    fn can_follow(&self, url: &Url) -> bool {
+        self.allowed_path_prefixes.as_ref().is_none_or(|prefixes| {
+            prefixes
+                .iter()
+                .any(|prefix| path_has_prefix(url.path(), prefix))
+        }) && self.can_request(url)
+    }
+
+    /// Check if the crawler may request a URL at all, ignoring whether it looks like an HTML page.
+    fn can_request(&self, url: &Url) -> bool {
+        self.allowed_schemes.contains(url.scheme())
+            && self.allowed_ports.as_ref().is_none_or(|allowed_ports| {
+                url.port_or_known_default()
+                    .is_some_and(|port| allowed_ports.contains(&port))
+            })
+            && self.robots_matcher.as_ref().is_none_or(|robots_matcher| {
+                url.host_str()
+                    .is_some_and(|host| self.seed_hosts.contains(host))
+                    || robots_matcher(url)
+            })
+    }
+
+    /// Normalize a URL so that equivalent URLs are only visited once.
+    fn normalize(&self, mut url: Url) -> Url {
+        if self.normalize_encoding {
+            normalize_encoding(&mut url);
+        }
+        url
+    }
+
+    /// The key a normalized URL is recorded under to decide if it has already been visited.
+    /// Unlike [Crawler::normalize], this does not change the URL that is visited and reported.
+    fn dedup_key(&self, url: &Url) -> Url {
+        let mut key = url.clone();
+        if self.sorted_query_dedup {
+            sort_query(&mut key);
+        }
+        collapse_index_file(&mut key, &self.index_files);
+        // After collapsing, which leaves a trailing slash, so it matches the directory's own key
+        normalize_trailing_slash(&mut key, self.trailing_slash);
+        key
+    }
+
+    /// Whether a collection of `tracked` URLs has reached the limit on tracked URLs.
+    fn tracking_full(&self, tracked: usize) -> bool {
+        self.max_tracked_urls
+            .is_some_and(|max_tracked_urls| tracked >= max_tracked_urls)
+    }
+
+    /// Record `key` in the `visited` set, unless it is new and the set has reached the limit on
+    /// tracked URLs. Returns `None` if the set was full, and otherwise whether `key` was new.
+    fn track_visited(&self, visited: &mut HashSet<Url>, key: Url) -> Option<bool> {
+        if !visited.contains(&key) && self.tracking_full(visited.len()) {
+            return None;
+        }
+        Some(visited.insert(key))
+    }
+
+    /// When the crawl must stop by, the sooner of the deadline and the end of the maximum time.
+    fn time_limit(&self, start_time: SystemTime) -> Option<SystemTime> {
+        let max_time_end = self.max_time.map(|max_time| start_time + max_time);
+        match (self.deadline, max_time_end) {
+            (Some(deadline), Some(max_time_end)) => Some(deadline.min(max_time_end)),
+            (deadline, max_time_end) => deadline.or(max_time_end),
+        }
+    }
+
+    /// Whether the process is using more memory than the memory limit allows.
+    fn over_memory_limit(&self) -> bool {
+        self.memory_limit.is_some_and(|memory_limit| {
+            self.memory_reader
+                .resident_bytes()
+                .is_some_and(|resident_bytes| resident_bytes > memory_limit)
+        })
+    }
+
+    /// Links on `page` that the crawler may follow, honouring any nofollow directives it respects.
+    fn followable_links(&self, page: &Page) -> Vec<Url> {
+        if self.respect_meta_robots && page.meta_robots.nofollow {
+            debug!("Robots nofollow - Ignoring links on {}", page.url);
+            return Vec::new();
+        }
+
+        let in_scope_redirect = page
+            .redirect
+            .iter()
+            .filter(|redirect| !redirect.external)
+            .map(|redirect| &redirect.location);
+
+        page.links
+            .iter()
+            .filter(|link| !(self.respect_nofollow && page.nofollow_links.contains(*link)))
+            .chain(in_scope_redirect)
+            .cloned()
+            .collect()
     }
 
-    async fn visit_and_parse(mut site_visitor: V, url: Url) -> Result<Page, VisitorError> {
+    /// Visit and parse a URL. If `verify_content_type` is set, pages without an HTML or feed
+    /// `Content-Type` are not parsed and `None` is returned.
+    async fn visit_and_parse(
+        mut site_visitor: V,
+        url: Url,
+        settings: VisitSettings,
+        verify_content_type: bool,
+    ) -> Result<Option<Page>, VisitorError> {
+        if let Some(request_spacer) = settings.request_spacer {
+            request_spacer.wait().await;
+        }
+
         debug!("Visiting and parsing {}", url);
-        let page_response = site_visitor.visit(url).await?;
+        let cached = settings
+            .etag_cache
+            .as_ref()
+            .and_then(|etag_cache| etag_cache.get(&url));
+        let page_response = match &cached {
+            Some((etag, _)) => site_visitor.visit_if_none_match(url.clone(), etag).await?,
+            None => site_visitor.visit(url.clone()).await?,
+        };
+        let fetched_at = settings.clock.now();
+
+        if page_response.status_code == StatusCode::NOT_MODIFIED {
+            if let Some((_, page)) = cached {
+                debug!("Not modified - Reusing cached page {}", url);
+                return Ok(Some(Page { fetched_at, ..page }));
+            }
+        }
+        let etag = page_response
+            .headers
+            .get(ETAG)
+            .filter(|_| page_response.status_code.is_success())
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+
+        if verify_content_type
+            && !is_html_content_type(page_response.content_type.as_ref())
+            && !is_feed_content_type(page_response.content_type.as_ref())
+        {
+            debug!(
+                "Content-Type is not HTML or a feed - Ignored {}",
+                page_response.url
+            );
+            return Ok(None);
+        }
+
+        let timed_out = settings
+            .parse_timeout
+            .map(|timeout| (timeout, parse_timed_out_page(&page_response)));
+        let scope = settings.scope;
+        let link_region = settings.link_region;
+        let parse_noscript = settings.parse_noscript;
+        let max_anchor_text_len = settings.max_anchor_text_len;
+        let parse_pool = settings.parse_pool;
+        let retain_content = settings.retain_content;
+        let parse = async move {
+            let parse = move || {
+                let mut page = parse_page(
+                    &page_response,
+                    &scope,
+                    link_region.as_deref(),
+                    parse_noscript,
+                    max_anchor_text_len,
+                );
+                if retain_content {
+                    page.content = Some(page_response.content);
+                }
+                page
+            };
+            match parse_pool {
+                Some(parse_pool) => parse_pool.run(parse).await,
+                None => tokio::task::spawn_blocking(parse)
+                    .await
+                    .expect("Task failed to execute to completion"),
+            }
+        };
+
+        // If parsing takes longer than the timeout it is abandoned and the page is returned without links.
+        let mut result = match timed_out {
+            Some((timeout, timed_out_page)) => match tokio::time::timeout(timeout, parse).await {
+                Ok(result) => result,
+                Err(_) => {
+                    warn!(
+                        "Parsing timed out after {:?}: {}",
+                        timeout, timed_out_page.url
+                    );
+                    timed_out_page
+                }
+            },
+            None => parse.await,
+        };
+        result.fetched_at = fetched_at;
+        if !result.malformed_links.is_empty() {
+            warn!(
+                "Malformed links on {}: {:?}",
+                result.url, result.malformed_links
+            );
+        }
+
+        if let (Some(etag_cache), Some(etag)) = (settings.etag_cache, etag) {
+            if !result.parse_timed_out {
+                etag_cache.insert(url, etag, result.clone());
+            }
+        }
+
+        Ok(Some(result))
+    }
+
+    /// Spawn a task sending a `HEAD` request to `url`, returning the MIME type of its `Content-Type`.
+    fn spawn_probe(&self, probes: &mut JoinSet<Option<String>>, url: Url) {
+        let mut visitor = self.site_visitor.clone();
+        let request_spacer = self.request_spacer.clone();
+        probes.spawn(
+            async move {
+                if let Some(request_spacer) = request_spacer {
+                    request_spacer.wait().await;
+                }
+                debug!("Probing Content-Type of {}", url);
+                match visitor.head(url.clone()).await {
+                    Ok(page_content) => page_content
+                        .content_type
+                        .as_ref()
+                        .and_then(|content_type| content_type.to_str().ok())
+                        .map(mime_type),
+                    Err(e) => {
+                        debug!("Failed to probe {}: {}", url, e);
+                        None
+                    }
+                }
+            }
+            .instrument(tracing::Span::current()),
+        );
+    }
+
+    /// Spawn a task to check a download link with a `HEAD` request.
+    fn spawn_download_check(&self, checks: &mut JoinSet<(Url, DownloadCheck)>, url: Url) {
+        let mut visitor = self.site_visitor.clone();
+        let request_spacer = self.request_spacer.clone();
+        checks.spawn(
+            async move {
+                if let Some(request_spacer) = request_spacer {
+                    request_spacer.wait().await;
+                }
+                debug!("Checking download {}", url);
+                let check = match visitor.head(url.clone()).await {
+                    Ok(response) => DownloadCheck::Responded {
+                        status_code: response.status_code,
+                        content_length: response
+                            .headers
+                            .get(CONTENT_LENGTH)
+                            .and_then(|content_length| content_length.to_str().ok())
+                            .and_then(|content_length| content_length.parse().ok()),
+                    },
+                    Err(e) => DownloadCheck::Failed(e.to_string()),
+                };
+                (url, check)
+            }
+            .instrument(tracing::Span::current()),
+        );
+    }
+
+    /// Spawn a task to visit and parse `url`, tracking it so it can be aborted. Retries, with a
+    /// non-zero `attempt`, wait for the backoff to pass before visiting.
+    fn spawn_visit(
+        &mut self,
+        url: Url,
+        scope: &Arc<Scope>,
+        parent: Option<Url>,
+        attempt: u32,
+        depth: usize,
+    ) {
+        let visitor = self.site_visitor.clone();
+        let settings = VisitSettings {
+            scope: scope.clone(),
+            request_spacer: self.request_spacer.clone(),
+            parse_timeout: self.parse_timeout,
+            link_region: self
+                .link_region
+                .as_ref()
+                .map(|(_, selector)| selector.clone()),
+            parse_noscript: self.parse_noscript,
+            max_anchor_text_len: self.max_anchor_text_len,
+            parse_pool: self.parse_pool.clone(),
+            retain_content: self.retain_content,
+            etag_cache: self.etag_cache.clone(),
+            clock: self.clock.clone(),
+        };
+        let backoff = self
+            .page_retry
+            .filter(|_| attempt > 0)
+            .map(|page_retry| page_retry.backoff(attempt));
+        let clock = self.clock.clone();
+        let visit = Self::visit_and_parse(
+            visitor,
+            url.clone(),
+            settings,
+            self.checks_content_type(&url),
+        );
+        let handle = self.tasks.spawn(
+            async move {
+                if let Some(backoff) = backoff {
+                    clock.sleep(backoff).await;
+                }
+                visit.await
+            }
+            .instrument(tracing::Span::current()),
+        );
+        self.in_flight.insert(
+            handle.id(),
+            InFlight {
+                url: url.clone(),
+                scope: scope.clone(),
+                parent,
+                attempt,
+                depth,
+            },
+        );
+        self.control.insert(url, handle);
+    }
 
-        let result = tokio::task::spawn_blocking(move || parse_links(&page_response))
-            .await
-            .expect("Task failed to execute to completion");
+    /// Visit a URL now, or if crawling with a [CrawlStrategy], add it to the `frontier` of visits
+    /// to start in the strategy's order. Breadth-first frontiers are kept in order of depth, so
+    /// retries and resumed visits go ahead of deeper pages.
+    fn schedule_visit(&mut self, frontier: &mut VecDeque<Queued>, queued: Queued) {
+        match self.strategy {
+            Some(CrawlStrategy::BreadthFirst) => {
+                let index = frontier.partition_point(|other| other.depth <= queued.depth);
+                frontier.insert(index, queued);
+            }
+            Some(CrawlStrategy::DepthFirst) => frontier.push_back(queued),
+            None => self.spawn_visit(
+                queued.url,
+                &queued.scope,
+                queued.parent,
+                queued.attempt,
+                queued.depth,
+            ),
+        }
+    }
+
+    /// Start visits from the `frontier` in the order of `strategy`, until the in-flight limit is reached.
+    fn start_queued_visits(&mut self, frontier: &mut VecDeque<Queued>, strategy: CrawlStrategy) {
+        while self.tasks.len() < self.max_in_flight {
+            let queued = match strategy {
+                CrawlStrategy::BreadthFirst => {
+                    // The frontier is in order of depth, but deeper pages must also wait for
+                    // shallower visits still in flight, which may discover more shallow pages.
+                    let min_in_flight_depth = self
+                        .in_flight
+                        .values()
+                        .map(|in_flight| in_flight.depth)
+                        .min();
+                    if frontier.front().is_none_or(|queued| {
+                        min_in_flight_depth.is_some_and(|depth| queued.depth > depth)
+                    }) {
+                        break;
+                    }
+                    frontier.pop_front()
+                }
+                CrawlStrategy::DepthFirst => frontier.pop_back(),
+            };
+            let Some(queued) = queued else {
+                break;
+            };
+            self.spawn_visit(
+                queued.url,
+                &queued.scope,
+                queued.parent,
+                queued.attempt,
+                queued.depth,
+            );
+        }
+    }
 
-        Ok(result)
+    /// Get a [CrawlControl] for controlling the crawl while it runs.
+    pub fn control(&self) -> CrawlControl {
+        self.control.clone()
+    }
+
+    /// Get a [LiveStats] for reading the crawl's progress while it runs.
+    pub fn live_stats(&self) -> LiveStats {
+        self.live_stats.clone()
     }
 
     /// Subscribe to receive pages as they are crawled.
+    /// Receivers that fall too far behind will miss pages. Use [CrawlerBuilder::with_event_sender]
+    /// to receive every page.
     pub fn subscribe(&self) -> broadcast::Receiver<Arc<Page>> {
         self.channel.subscribe()
     }
 
+    /// Subscribe to receive each unique external URL as soon as it is first discovered.
+    /// Receivers that fall too far behind will miss URLs (see [broadcast::error::RecvError::Lagged]).
+    pub fn subscribe_external_links(&self) -> broadcast::Receiver<Url> {
+        self.external_channel.subscribe()
+    }
+
     /// Start crawling from a given URL.
     /// Consumes the [Crawler] and returns a collection of all pages visited.
-    #[tracing::instrument(skip(self))]
-    pub async fn crawl(mut self, url: Url) -> AllPages {
+    pub async fn crawl(self, url: Url) -> AllPages {
+        self.crawl_seeds([url]).await
+    }
+
+    /// Start crawling from a given URL, failing if the crawl could not start. See [Crawler::try_crawl_seeds].
+    pub async fn try_crawl(self, url: Url) -> Result<AllPages, CrawlError> {
+        self.try_crawl_seeds([url]).await
+    }
+
+    /// Start crawling from several URLs at once, like [Crawler::crawl_seeds], but return an error
+    /// if the crawler's configuration cannot crawl anything, or if none of the seeds could be
+    /// visited. Failures of other pages are reported in [AllPages::failed] as usual.
+    pub async fn try_crawl_seeds(
+        self,
+        seeds: impl IntoIterator<Item = Url>,
+    ) -> Result<AllPages, CrawlError> {
+        let seeds: Vec<Url> = seeds.into_iter().collect();
+        if seeds.is_empty() {
+            return Err(CrawlError::InvalidConfiguration(
+                "no seed URLs were given".to_string(),
+            ));
+        }
+        if self.max_pages == Some(0) {
+            return Err(CrawlError::InvalidConfiguration(
+                "the page limit is zero".to_string(),
+            ));
+        }
+        if self.max_bytes == Some(0) {
+            return Err(CrawlError::InvalidConfiguration(
+                "the byte limit is zero".to_string(),
+            ));
+        }
+        if self.allowed_schemes.is_empty() {
+            return Err(CrawlError::InvalidConfiguration(
+                "no URL schemes are allowed".to_string(),
+            ));
+        }
+
+        let normalized_seeds: HashSet<Url> = seeds
+            .iter()
+            .map(|seed| self.normalize(seed.clone()))
+            .collect();
+        let all_pages = self.crawl_seeds(seeds).await;
+
+        let seed_failures: Vec<&FailedVisit> = all_pages
+            .failed
+            .iter()
+            .filter(|failed| normalized_seeds.contains(&failed.url))
+            .collect();
+        match seed_failures.first() {
+            Some(failed) if seed_failures.len() == normalized_seeds.len() => {
+                Err(match &failed.reason {
+                    FailureReason::Disallowed => CrawlError::SeedDisallowed {
+                        url: failed.url.clone(),
+                    },
+                    reason => CrawlError::SeedUnreachable {
+                        url: failed.url.clone(),
+                        reason: reason.clone(),
+                    },
+                })
+            }
+            _ => Ok(all_pages),
+        }
+    }
+
+    /// Start crawling from several URLs at once. Links found from each seed are checked against
+    /// that seed's scope, and a page reachable from more than one seed is only visited once.
+    /// Limits such as [max pages](CrawlerBuilder::with_max_pages), [max time](CrawlerBuilder::with_max_time)
+    /// and [max bytes](CrawlerBuilder::with_max_bytes) apply to the crawl as a whole, not to each seed.
+    /// Consumes the [Crawler] and returns a collection of all pages visited.
+    #[tracing::instrument(skip_all)]
+    pub async fn crawl_seeds(mut self, seeds: impl IntoIterator<Item = Url>) -> AllPages {
         let mut pages: Vec<Page> = Vec::new();
-        let mut visited: HashSet<Url> = HashSet::new();
+        let mut failed: Vec<FailedVisit> = Vec::new();
+        let mut aliases: HashMap<Url, Url> = HashMap::new();
+        let mut skipped: HashMap<SkipReason, usize> = HashMap::new();
+        let mut probes: JoinSet<Option<String>> = JoinSet::new();
+        let mut probed: HashSet<Url> = HashSet::new();
+        let mut download_checks: JoinSet<(Url, DownloadCheck)> = JoinSet::new();
+        let mut checked_downloads: HashSet<Url> = HashSet::new();
+        let mut link_table = LinkTable::default();
+
+        let mut visited: HashSet<Url> = std::mem::take(&mut self.preexcluded)
+            .into_iter()
+            .map(|url| self.dedup_key(&self.normalize(url)))
+            .collect();
+        let mut seen_external: HashSet<Url> = HashSet::new();
+        // Consecutive failures to reach each host, and the hosts given up on
+        let mut host_failures: HashMap<String, usize> = HashMap::new();
+        let mut down_hosts: HashSet<String> = HashSet::new();
         let mut page_count: u64 = 0;
-        let start_time = SystemTime::now();
+        let mut byte_count: u64 = 0;
+        // Unlike `page_count`, includes pages visited while draining
+        let mut pages_visited: u64 = 0;
+        let start_time = self.clock.now();
+        self.live_stats.start();
 
         debug!("Starting crawl");
 
-        if self.can_visit(&url) {
-            visited.insert(url.clone());
-            let visitor = self.site_visitor.clone();
+        let seeds: Vec<Url> = seeds.into_iter().collect();
+        if self.ignore_robots_for_seed_host {
+            self.seed_hosts = seeds
+                .iter()
+                .filter_map(|seed| seed.host_str().map(str::to_string))
+                .collect();
+        }
+
+        // Visits waiting to start, if crawling with a strategy
+        let mut frontier: VecDeque<Queued> = VecDeque::new();
+        for seed in seeds {
+            let url = self.normalize(seed.clone());
+            if seed != url {
+                aliases.insert(seed, url.clone());
+            }
 
-            self.tasks
-                .spawn(Self::visit_and_parse(visitor, url).instrument(tracing::Span::current()));
+            // The seed was chosen explicitly, so visit it even if its path does not look like HTML.
+            if !self.can_request(&url) {
+                error!(
+                    "Seed URL has a disallowed scheme or is disallowed by robots.txt: {}",
+                    url
+                );
+                failed.push(FailedVisit {
+                    url,
+                    reason: FailureReason::Disallowed,
+                });
+            } else if visited.insert(self.dedup_key(&url)) {
+                if !assume_html(&url) {
+                    info!(
+                        "Seed URL does not look like an HTML page - Visiting anyway {}",
+                        url
+                    );
+                }
+                let scope = Arc::new(Scope::new(self.scope_mode.clone(), url.clone()));
+                self.schedule_visit(
+                    &mut frontier,
+                    Queued {
+                        url,
+                        scope,
+                        parent: None,
+                        attempt: 0,
+                        depth: 0,
+                    },
+                );
+            }
         }
 
-        while let Some(task_result) = self.tasks.join_next().await {
-            // If there are any failures log an error and continue.
-            let page = match task_result {
-                Ok(page_result) => match page_result {
-                    Ok(page) => page,
+        // Once a limit is reached, when to stop waiting for visits already in flight.
+        let mut drain_until: Option<SystemTime> = None;
+        let mut stopped_early = false;
+        // Visits held back while memory is over the limit
+        let mut paused: VecDeque<Queued> = VecDeque::new();
+        let mut over_memory_limit = false;
+
+        loop {
+            if !paused.is_empty() && drain_until.is_none() {
+                over_memory_limit = self.over_memory_limit();
+                if !over_memory_limit {
+                    info!("Memory below limit - Resuming {} visits", paused.len());
+                    for queued in std::mem::take(&mut paused) {
+                        self.schedule_visit(&mut frontier, queued);
+                    }
+                } else if self.tasks.is_empty() && frontier.is_empty() {
+                    if let Some(queued) = paused.pop_front() {
+                        self.schedule_visit(&mut frontier, queued);
+                    }
+                }
+            }
+            if let Some(strategy) = self.strategy.filter(|_| drain_until.is_none()) {
+                self.start_queued_visits(&mut frontier, strategy);
+            }
+
+            self.live_stats.update(
+                pages_visited,
+                self.tasks.len() + frontier.len() + paused.len(),
+                failed.len(),
+            );
+
+            let task_result = match drain_until {
+                Some(drain_until) => {
+                    let remaining = drain_until
+                        .duration_since(self.clock.now())
+                        .unwrap_or_default();
+                    tokio::select! {
+                        task_result = self.tasks.join_next_with_id() => task_result,
+                        _ = self.clock.sleep(remaining) => {
+                            info!("Shutdown drain timed out - Abandoning {} visits", self.tasks.len());
+                            None
+                        }
+                    }
+                }
+                // Stop at the time limit even if no visit finishes before it
+                None => match self.time_limit(start_time) {
+                    Some(time_limit) => {
+                        let remaining = time_limit
+                            .duration_since(self.clock.now())
+                            .unwrap_or_default();
+                        tokio::select! {
+                            task_result = self.tasks.join_next_with_id() => task_result,
+                            _ = self.clock.sleep(remaining) => {
+                                info!("Time limit reached with {} visits in flight", self.tasks.len());
+                                stopped_early = true;
+                                if let Some(shutdown_drain) = self.shutdown_drain {
+                                    drain_until = Some(self.clock.now() + shutdown_drain);
+                                    continue;
+                                }
+                                None
+                            }
+                        }
+                    }
+                    None => self.tasks.join_next_with_id().await,
+                },
+            };
+            let Some(task_result) = task_result else {
+                break;
+            };
+
+            let id = match &task_result {
+                Ok((id, _)) => *id,
+                Err(join_error) => join_error.id(),
+            };
+            let InFlight {
+                url: task_url,
+                scope,
+                parent,
+                attempt,
+                depth,
+            } = self
+                .in_flight
+                .remove(&id)
+                .expect("Task was not tracked. This is a bug.");
+            self.control.remove(&task_url);
+
+            // If there are any failures log an error, record it and continue.
+            let mut page = match task_result {
+                Ok((_, page_result)) => match page_result {
+                    Ok(Some(page)) => page,
+                    Ok(None) => continue,
+                    Err(VisitorError::NotHtml { .. }) => {
+                        debug!("Response is not HTML - Skipped {}", task_url);
+                        *skipped.entry(SkipReason::NotHtml).or_default() += 1;
+                        continue;
+                    }
                     Err(request_error) => {
+                        // Visits failing while draining are not retried
+                        if let Some(page_retry) = self.page_retry.filter(|page_retry| {
+                            attempt < page_retry.max_retries
+                                && drain_until.is_none()
+                                && request_error.is_retryable()
+                        }) {
+                            warn!(
+                                "Failed to reach site, retrying ({}/{}): {}",
+                                attempt + 1,
+                                page_retry.max_retries,
+                                request_error
+                            );
+                            self.schedule_visit(
+                                &mut frontier,
+                                Queued {
+                                    url: task_url,
+                                    scope,
+                                    parent,
+                                    attempt: attempt + 1,
+                                    depth,
+                                },
+                            );
+                            continue;
+                        }
                         error!("Failed to reach site: {}", request_error);
+                        if let (Some(threshold), Some(host)) =
+                            (self.host_failure_threshold, task_url.host_str())
+                        {
+                            // Only failures to reach the host count, not errors from a host that answered
+                            if request_error.is_retryable() {
+                                let failures = host_failures.entry(host.to_string()).or_default();
+                                *failures += 1;
+                                if *failures >= threshold && down_hosts.insert(host.to_string()) {
+                                    warn!(
+                                        "{} failures in a row - Not visiting any more of {}",
+                                        failures, host
+                                    );
+                                }
+                            }
+                        }
+                        let reason = match request_error {
+                            VisitorError::Request(e) => FailureReason::Visitor(format!("{:#}", e)),
+                            VisitorError::RedirectLoop { hops, .. } => {
+                                FailureReason::RedirectLoop { hops }
+                            }
+                            VisitorError::Dns { host, .. } => FailureReason::Dns { host },
+                            // Skipped before reaching here, rather than recorded as failed
+                            VisitorError::NotHtml { .. } => {
+                                unreachable!("NotHtml visit was not skipped. This is a bug.")
+                            }
+                        };
+                        failed.push(FailedVisit {
+                            url: task_url,
+                            reason,
+                        });
                         continue;
                     }
                 },
                 Err(join_error) => {
-                    error!("Failed to join task: {}", join_error);
+                    let reason = if join_error.is_cancelled() {
+                        info!("Cancelled visit to {}", task_url);
+                        FailureReason::Cancelled
+                    } else {
+                        error!("Failed to join task: {}", join_error);
+                        FailureReason::Panicked
+                    };
+                    failed.push(FailedVisit {
+                        url: task_url,
+                        reason,
+                    });
                     continue;
                 }
             };
 
-            // Broadcast the page
-            let _ = self.channel.send(Arc::new(page.clone())); // Ignore errors as we don't care if the receiver is gone
+            pages_visited += 1;
+            if let Some(host) = task_url.host_str() {
+                host_failures.remove(host);
+            }
+            page.parent = parent;
+            if page.url != task_url {
+                debug!("Redirected {} -> {}", task_url, page.url);
+                // Links straight to where the redirect landed need not be visited again
+                let key = self.dedup_key(&self.normalize(page.url.clone()));
+                if self.track_visited(&mut visited, key).is_none() {
+                    debug!("Tracking too many URLs - Not recording {}", page.url);
+                }
+                if !self.tracking_full(aliases.len()) {
+                    aliases.insert(task_url, page.url.clone());
+                }
+            }
+
+            for link in page.external_links.iter() {
+                if seen_external.contains(link) || self.tracking_full(seen_external.len()) {
+                    continue;
+                }
+                seen_external.insert(link.clone());
+                let _ = self.external_channel.send(link.clone()); // Ignore errors as we don't care if the receiver is gone
+                if let Some(event_sender) = &self.event_sender {
+                    let _ = event_sender
+                        .send(CrawlEvent::ExternalLink(link.clone()))
+                        .await;
+                }
+            }
+            if self.check_downloads {
+                for download in page.downloads.iter() {
+                    if self.can_request(download)
+                        && !self.tracking_full(checked_downloads.len())
+                        && checked_downloads.insert(download.clone())
+                    {
+                        self.spawn_download_check(&mut download_checks, download.clone());
+                    }
+                }
+            }
+
+            let mut recovered_links = self.followable_links(&page);
+            if self.skip_unchanged_subtrees
+                && self.prior_manifest.get(&page.url) == Some(&page.content_hash)
+            {
+                debug!(
+                    "Unchanged since the last crawl - Not following links of {}",
+                    page.url
+                );
+                recovered_links.clear();
+            }
+            if let Some(schedule_seed) = self.schedule_seed {
+                shuffle_links(&mut recovered_links, schedule_seed, &page.url);
+            }
+            let page_url = page.url.clone();
+            let content_length = page.content_length as u64;
 
-            let mut recovered_links = Vec::new();
-            for link in page.links.iter() {
-                recovered_links.push(link.clone());
+            if let Some(redirect) = page.redirect.as_ref().filter(|redirect| redirect.external) {
+                info!(
+                    "Redirect to external URL - Not following {} -> {}",
+                    page.url, redirect.location
+                );
+            }
+
+            if self.respect_meta_robots && page.meta_robots.noindex {
+                debug!("Robots noindex - Not reporting {}", page.url);
+            } else {
+                if let Some(page_store) = &self.page_store {
+                    if let Err(e) = page_store.store(&page).await {
+                        error!("Failed to store page {}: {:#}", page.url, e);
+                    }
+                }
+
+                // The page is only copied when subscribers also need it, and links are only needed
+                // for scheduling, so avoid copying them when not retained
+                let mut reported =
+                    if self.event_sender.is_none() && self.channel.receiver_count() == 0 {
+                        if !self.retain_links {
+                            page.clear_links();
+                        }
+                        page
+                    } else {
+                        let reported = if self.retain_links {
+                            page.clone()
+                        } else {
+                            page.without_links()
+                        };
+
+                        // Broadcast the page
+                        let page = Arc::new(page);
+                        if let Some(event_sender) = &self.event_sender {
+                            let _ = event_sender.send(CrawlEvent::Page(page.clone())).await;
+                        }
+                        let _ = self.channel.send(page); // Ignore errors as we don't care if the receiver is gone
+                        reported
+                    };
+                if self.intern_links && self.retain_links {
+                    link_table.insert(reported.url.clone(), std::mem::take(&mut reported.links));
+                }
+                pages.push(reported);
+            }
+
+            // Visits finishing while draining are reported, but their links are not followed
+            if drain_until.is_some() {
+                continue;
             }
-            pages.push(page);
 
             // Check if we have reached the max pages
+            let mut limit_reached = false;
             if Some(page_count + 1) == self.max_pages {
                 info!("Max pages reached");
-                break;
+                limit_reached = true;
             }
             page_count += 1;
 
+            // Check if we have reached the max bytes
+            byte_count += content_length;
+            if self
+                .max_bytes
+                .is_some_and(|max_bytes| byte_count >= max_bytes)
+            {
+                info!("Max bytes reached");
+                limit_reached = true;
+            }
+
             // Check if we have reached the max time
             if let Some(max_time) = self.max_time {
-                let now = SystemTime::now();
+                let now = self.clock.now();
                 if let Ok(duration) = now.duration_since(start_time) {
                     if duration > max_time {
                         info!("Max time reached");
-                        break;
+                        limit_reached = true;
+                    }
+                }
+            }
+
+            // Check if we have passed the deadline
+            if self
+                .deadline
+                .is_some_and(|deadline| self.clock.now() >= deadline)
+            {
+                info!("Deadline reached");
+                limit_reached = true;
+            }
+
+            if limit_reached {
+                stopped_early = true;
+                match self.shutdown_drain {
+                    Some(shutdown_drain) => {
+                        drain_until = Some(self.clock.now() + shutdown_drain);
+                        continue;
                     }
+                    None => break,
                 }
             }
 
+            let was_over_memory_limit = over_memory_limit;
+            over_memory_limit = self.over_memory_limit();
+            if over_memory_limit && !was_over_memory_limit {
+                warn!("Memory above limit - Pausing new visits");
+            }
+
             for link in recovered_links {
+                let original = link.clone();
+                let link = self.normalize(link);
                 if self.can_visit(&link) {
-                    let not_visited = visited.insert(link.clone());
-
-                    if not_visited {
-                        let visitor = self.site_visitor.clone();
+                    let key = self.dedup_key(&link);
+                    if !visited.contains(&key)
+                        && link
+                            .host_str()
+                            .is_some_and(|host| down_hosts.contains(host))
+                    {
+                        debug!("Host is down - Dropped {}", link);
+                        *skipped.entry(SkipReason::HostDown).or_default() += 1;
+                        continue;
+                    }
+                    let Some(not_visited) = self.track_visited(&mut visited, key) else {
+                        debug!("Tracking too many URLs - Dropped {}", link);
+                        *skipped.entry(SkipReason::FrontierFull).or_default() += 1;
+                        continue;
+                    };
+                    if original != link && !self.tracking_full(aliases.len()) {
+                        aliases.insert(original, link.clone());
+                    }
 
-                        self.tasks.spawn(
-                            Self::visit_and_parse(visitor, link)
-                                .instrument(tracing::Span::current()),
-                        );
+                    let queued = Queued {
+                        url: link,
+                        scope: scope.clone(),
+                        parent: Some(page_url.clone()),
+                        attempt: 0,
+                        depth: depth + 1,
+                    };
+                    if not_visited && over_memory_limit {
+                        paused.push_back(queued);
+                    } else if not_visited {
+                        self.schedule_visit(&mut frontier, queued);
                     }
+                } else if self.probe_content_types
+                    && self.can_follow(&link)
+                    && !self.tracking_full(probed.len())
+                    && probed.insert(link.clone())
+                {
+                    self.spawn_probe(&mut probes, link);
                 } else {
-                    debug!("Robots.txt - Ignored {} ", link);
+                    debug!("Cannot visit - Ignored {} ", link);
                 }
             }
         }
 
-        AllPages(pages)
+        // Visits that were never started or finished because the crawl stopped early
+        let unfinished = frontier.len() + paused.len() + self.tasks.len();
+        if unfinished > 0 {
+            *skipped.entry(SkipReason::LimitReached).or_default() += unfinished;
+        }
+        self.tasks.abort_all();
+
+        let mut content_type_histogram: BTreeMap<String, usize> = BTreeMap::new();
+        if self.probe_content_types {
+            if stopped_early {
+                probes.abort_all();
+            }
+            let mut content_types: Vec<String> = pages
+                .iter()
+                .filter_map(|page| {
+                    page.content_type
+                        .as_ref()
+                        .and_then(|content_type| content_type.to_str().ok())
+                        .map(mime_type)
+                })
+                .collect();
+            // Abandoned probes are cancelled and not counted
+            while let Some(probe) = probes.join_next().await {
+                content_types.extend(probe.ok().flatten());
+            }
+            for content_type in content_types {
+                *content_type_histogram.entry(content_type).or_default() += 1;
+            }
+        }
+
+        if stopped_early {
+            download_checks.abort_all();
+        }
+        // Abandoned checks are cancelled and not reported
+        let mut download_results = HashMap::new();
+        while let Some(check) = download_checks.join_next().await {
+            download_results.extend(check.ok());
+        }
+
+        self.live_stats.finish(pages_visited, failed.len());
+        AllPages {
+            pages,
+            failed,
+            aliases,
+            skipped,
+            content_type_histogram,
+            download_checks: download_results,
+            link_table,
+        }
     }
 }
 
+/// The MIME type of a `Content-Type` header, without parameters and in lowercase.
+fn mime_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Whether `path` is `prefix` or below it. `prefix` starts with `/` and has no trailing `/`.
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/') || prefix == "/")
+}
+
+/// Shuffle the links found on `page_url` into an order that only depends on `seed`, the page and
+/// its links, so that it is the same however the pages of a crawl complete.
+fn shuffle_links(links: &mut [Url], seed: u64, page_url: &Url) {
+    let page_hash = fnv1a(page_url.as_str().as_bytes());
+    links.sort();
+    links.shuffle(&mut ChaCha8Rng::seed_from_u64(seed ^ page_hash));
+}
+
+/// A snapshot of the configuration a [CrawlerBuilder] will build a [Crawler] with.
+/// See [CrawlerBuilder::config_summary].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrawlConfig {
+    pub max_time: Option<Duration>,
+    pub deadline: Option<SystemTime>,
+    pub max_pages: Option<u64>,
+    /// The total size of page bodies after which the crawl stops.
+    pub max_bytes: Option<u64>,
+    pub max_tracked_urls: Option<usize>,
+    /// The resident memory in bytes above which scheduling new visits pauses.
+    pub memory_limit: Option<u64>,
+    /// The number of consecutive failed visits to a host after which it is no longer visited.
+    pub host_failure_threshold: Option<usize>,
+    /// The number of times a failed visit is retried and the wait before the first retry.
+    pub page_retry: Option<(u32, Duration)>,
+    pub shutdown_drain: Option<Duration>,
+    /// Whether a robots.txt file or robots matcher was provided.
+    pub robots: bool,
+    pub ignore_robots_for_seed_host: bool,
+    pub schedule_seed: Option<u64>,
+    pub strategy: Option<CrawlStrategy>,
+    /// The number of visits run at once when crawling with a strategy.
+    pub max_in_flight: usize,
+    pub scope_mode: ScopeMode,
+    pub allowed_schemes: BTreeSet<String>,
+    /// Ports the crawler may visit. `None` allows every port.
+    pub allowed_ports: Option<BTreeSet<u16>>,
+    /// Path prefixes links must be under to be visited. `None` allows every path.
+    pub allowed_path_prefixes: Option<Vec<String>>,
+    pub trailing_slash: Normalize,
+    pub normalize_encoding: bool,
+    pub sorted_query_dedup: bool,
+    /// Index file names collapsed into their directory when deduplicating URLs.
+    pub index_files: Vec<String>,
+    pub verify_content_type: bool,
+    pub probe_content_types: bool,
+    pub check_downloads: bool,
+    /// The number of URLs excluded before the crawl starts.
+    pub preexcluded: usize,
+    /// The number of pages in the manifest of a previous crawl.
+    pub prior_manifest: usize,
+    pub skip_unchanged_subtrees: bool,
+    pub parse_timeout: Option<Duration>,
+    /// The CSS selector links are extracted within, if any.
+    pub link_region: Option<String>,
+    pub parse_noscript: bool,
+    pub max_anchor_text_len: Option<usize>,
+    /// The number of dedicated parse threads, if parsing does not use Tokio's blocking pool.
+    pub parse_threads: Option<usize>,
+    pub retain_content: bool,
+    /// Whether an [EtagCache] was provided.
+    pub etag_cache: bool,
+    pub retain_links: bool,
+    pub intern_links: bool,
+    pub respect_nofollow: bool,
+    pub respect_meta_robots: bool,
+    pub respect_crawl_delay: bool,
+    /// The crawl delay that will be applied between requests, if any.
+    pub crawl_delay: Option<Duration>,
+    /// Whether a [PageStore] was provided.
+    pub page_store: bool,
+    /// Whether a sender was provided with [CrawlerBuilder::with_event_sender].
+    pub event_sender: bool,
+}
+
 /// Builder for [Crawler].
 pub struct CrawlerBuilder<V>
 where
     V: SiteVisitor,
 {
     site_visitor: V,
-    robot: Option<Robot>,
+    robots_matcher: Option<RobotsMatcher>,
+    /// The `Crawl-delay` of the robots.txt file, in seconds.
+    robots_crawl_delay: Option<f32>,
+    schedule_seed: Option<u64>,
+    strategy: Option<CrawlStrategy>,
+    max_in_flight: usize,
+    ignore_robots_for_seed_host: bool,
     max_time: Option<std::time::Duration>,
+    deadline: Option<SystemTime>,
     max_pages: Option<u64>,
+    max_bytes: Option<u64>,
+    max_tracked_urls: Option<usize>,
+    memory_limit: Option<u64>,
+    host_failure_threshold: Option<usize>,
+    page_retry: Option<PageRetry>,
+    shutdown_drain: Option<Duration>,
+    scope_mode: ScopeMode,
+    allowed_schemes: HashSet<String>,
+    allowed_ports: Option<HashSet<u16>>,
+    allowed_path_prefixes: Option<Vec<String>>,
+    trailing_slash: Normalize,
+    normalize_encoding: bool,
+    sorted_query_dedup: bool,
+    index_files: Vec<String>,
+    verify_content_type: bool,
+    probe_content_types: bool,
+    check_downloads: bool,
+    preexcluded: HashSet<Url>,
+    prior_manifest: HashMap<Url, u64>,
+    skip_unchanged_subtrees: bool,
+    parse_timeout: Option<Duration>,
+    link_region: Option<(String, Arc<Selector>)>,
+    parse_noscript: bool,
+    max_anchor_text_len: Option<usize>,
+    parse_pool: Option<Arc<ParsePool>>,
+    retain_content: bool,
+    etag_cache: Option<Arc<EtagCache>>,
+    retain_links: bool,
+    intern_links: bool,
+    respect_nofollow: bool,
+    respect_meta_robots: bool,
+    respect_crawl_delay: bool,
+    page_store: Option<Arc<dyn PageStore>>,
+    event_sender: Option<mpsc::Sender<CrawlEvent>>,
+    clock: Arc<dyn Clock>,
+    memory_reader: Arc<dyn MemoryReader>,
 }
 
 impl<V> CrawlerBuilder<V>
@@ -182,16 +1515,137 @@ where
     pub fn new(site_visitor: V) -> Self {
         Self {
             site_visitor,
-            robot: None,
+            robots_matcher: None,
+            robots_crawl_delay: None,
+            schedule_seed: None,
+            strategy: None,
+            max_in_flight: 32,
+            ignore_robots_for_seed_host: false,
             max_time: None,
+            deadline: None,
             max_pages: None,
+            max_bytes: None,
+            max_tracked_urls: None,
+            memory_limit: None,
+            host_failure_threshold: None,
+            page_retry: None,
+            shutdown_drain: None,
+            scope_mode: ScopeMode::default(),
+            allowed_schemes: HashSet::from(["http".to_string(), "https".to_string()]),
+            allowed_ports: None,
+            allowed_path_prefixes: None,
+            trailing_slash: Normalize::default(),
+            normalize_encoding: false,
+            sorted_query_dedup: false,
+            index_files: Vec::new(),
+            verify_content_type: false,
+            probe_content_types: false,
+            check_downloads: false,
+            preexcluded: HashSet::new(),
+            prior_manifest: HashMap::new(),
+            skip_unchanged_subtrees: false,
+            parse_timeout: None,
+            link_region: None,
+            parse_noscript: false,
+            max_anchor_text_len: None,
+            parse_pool: None,
+            retain_content: false,
+            etag_cache: None,
+            retain_links: true,
+            intern_links: false,
+            respect_nofollow: false,
+            respect_meta_robots: false,
+            respect_crawl_delay: false,
+            page_store: None,
+            event_sender: None,
+            clock: Arc::new(TokioClock::new()),
+            memory_reader: Arc::new(ProcessMemoryReader::new()),
         }
     }
 
     /// Provide a robot_txt file for the crawler. The crawler will not visit pages denied in the robot_txt file.
+    /// Replaces any matcher set with [CrawlerBuilder::with_robots_matcher].
     pub fn with_robot(mut self, robot_txt: &str, crawler_agent: &str) -> anyhow::Result<Self> {
-        self.robot = Some(Robot::new(crawler_agent, robot_txt.as_bytes())?);
-        Ok(self)
+        let robot = Robot::new(crawler_agent, robot_txt.as_bytes())?;
+        self.robots_crawl_delay = robot.delay;
+        Ok(self.with_robots_matcher(move |url| robot.allowed(url.as_str())))
+    }
+
+    /// Provide a robots.txt file for each host, keyed by host name, for crawls with seeds on several
+    /// hosts. Each URL is only checked against the file of its own host, and URLs on hosts without
+    /// a file are allowed. The longest `Crawl-delay` of the files applies to the whole crawl.
+    /// Replaces any matcher set with [CrawlerBuilder::with_robots_matcher].
+    pub fn with_host_robots<'a>(
+        self,
+        robots: impl IntoIterator<Item = (&'a str, &'a str)>,
+        crawler_agent: &str,
+    ) -> anyhow::Result<Self> {
+        let robots = robots
+            .into_iter()
+            .map(|(host, robot_txt)| {
+                Ok((
+                    host.to_string(),
+                    Robot::new(crawler_agent, robot_txt.as_bytes())?,
+                ))
+            })
+            .collect::<anyhow::Result<HashMap<String, Robot>>>()?;
+        let crawl_delay = robots
+            .values()
+            .filter_map(|robot| robot.delay)
+            .reduce(f32::max);
+        let mut builder = self.with_robots_matcher(move |url| {
+            url.host_str()
+                .and_then(|host| robots.get(host))
+                .is_none_or(|robot| robot.allowed(url.as_str()))
+        });
+        builder.robots_crawl_delay = crawl_delay;
+        Ok(builder)
+    }
+
+    /// Decide which URLs the crawler may visit with a custom matcher instead of a robots.txt file,
+    /// for example to combine robots.txt rules with another policy. URLs are visited only if
+    /// `robots_matcher` returns `true`. Replaces the rules of any robots.txt file provided with
+    /// [CrawlerBuilder::with_robot], though its crawl delay is kept.
+    pub fn with_robots_matcher(
+        mut self,
+        robots_matcher: impl Fn(&Url) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.robots_matcher = Some(Arc::new(robots_matcher));
+        self
+    }
+
+    /// Visit discovered pages in the order of `strategy`, running at most
+    /// [max in flight](CrawlerBuilder::with_max_in_flight) visits at once and queueing the rest.
+    /// By default every page is visited as soon as it is discovered, so pages complete in no
+    /// particular order.
+    ///
+    /// With [CrawlStrategy::BreadthFirst] and [CrawlerBuilder::with_max_pages], the pages visited
+    /// are the ones closest to the seeds, which are usually the most important.
+    pub fn with_strategy(mut self, strategy: CrawlStrategy) -> Self {
+        self.strategy = Some(strategy);
+        self
+    }
+
+    /// Set how many visits run at once when crawling with a [strategy](CrawlerBuilder::with_strategy).
+    /// Defaults to 32, and is at least one. Fewer visits at once follow the strategy's order more closely.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Schedule the links found on each page in a shuffled order, spreading requests across the site
+    /// rather than working through it section by section. The order is reproducible for a given
+    /// `schedule_seed`. By default links are scheduled in no particular order.
+    pub fn with_schedule_seed(mut self, schedule_seed: u64) -> Self {
+        self.schedule_seed = Some(schedule_seed);
+        self
+    }
+
+    /// Visit the seed's host whatever its robots.txt says, while still honouring it for any other
+    /// in-scope hosts. Useful when crawling a site you own.
+    pub fn with_ignore_robots_for_seed_host(mut self, ignore_robots_for_seed_host: bool) -> Self {
+        self.ignore_robots_for_seed_host = ignore_robots_for_seed_host;
+        self
     }
 
     /// Set the maximum time the crawler will run for.
@@ -200,22 +1654,478 @@ where
         self
     }
 
+    /// Store each reported page in `page_store` as soon as it is completed.
+    pub fn with_page_store(mut self, page_store: Arc<dyn PageStore>) -> Self {
+        self.page_store = Some(page_store);
+        self
+    }
+
+    /// Send every [CrawlEvent] to `event_sender`. Unlike [Crawler::subscribe], no events are
+    /// dropped: the crawl waits for room in the channel, so a slow receiver slows the crawl down.
+    /// Events are no longer sent once the receiver is dropped.
+    pub fn with_event_sender(mut self, event_sender: mpsc::Sender<CrawlEvent>) -> Self {
+        self.event_sender = Some(event_sender);
+        self
+    }
+
+    /// Set the [Clock] used for time limits, deadlines and crawl delays. Defaults to [TokioClock].
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Set an absolute time after which the crawler stops, whenever the crawl was started.
+    /// If a maximum time is also set, whichever is reached first stops the crawl.
+    pub fn with_deadline(mut self, deadline: SystemTime) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// When a page limit, time limit or deadline stops the crawl, wait up to `shutdown_drain` for
+    /// visits already in flight to finish so their pages are included, then abandon the rest.
+    /// Pages finishing in this window are reported even if that exceeds the page limit, but their
+    /// links are not followed. By default in-flight visits are abandoned immediately.
+    pub fn with_shutdown_drain(mut self, shutdown_drain: Duration) -> Self {
+        self.shutdown_drain = Some(shutdown_drain);
+        self
+    }
+
     /// Set the maximum number of pages the crawler will visit.
     pub fn with_max_pages(mut self, max_pages: u64) -> Self {
         self.max_pages = Some(max_pages);
         self
     }
 
+    /// Stop the crawl once the bodies of the pages visited total at least `max_bytes`, measured
+    /// after decoding them as text. Like [CrawlerBuilder::with_max_pages], this bounds the work of
+    /// a crawl, but in proportion to what was downloaded, so a few huge pages count for more than
+    /// many small ones.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Set the maximum number of distinct URLs the crawler tracks, counting every URL it has
+    /// visited, is visiting or has queued, and any preexcluded URLs. Once reached, newly discovered
+    /// URLs are dropped and counted in [AllPages::skipped] under [SkipReason::FrontierFull].
+    ///
+    /// Unlike [CrawlerBuilder::with_max_pages], which stops the crawl once enough pages have been
+    /// visited, this bounds the memory used to remember URLs on sites with huge numbers of links.
+    /// The crawl continues until the tracked URLs have been visited.
+    ///
+    /// The same limit applies separately to the other URLs the crawler remembers: the aliases in
+    /// [AllPages::aliases], the external URLs already sent to
+    /// [Crawler::subscribe_external_links], and the URLs probed for their content type. Once one
+    /// is full, new external URLs are no longer sent and new URLs are no longer probed or aliased.
+    pub fn with_max_tracked_urls(mut self, max_tracked_urls: usize) -> Self {
+        self.max_tracked_urls = Some(max_tracked_urls);
+        self
+    }
+
+    /// Pause scheduling new visits while the process's resident memory is above `memory_limit`
+    /// bytes, resuming once it drops. Visits in flight continue, so the memory they hold can be
+    /// freed. If none are in flight, new visits are scheduled one at a time so the crawl still
+    /// progresses. Unlike the page and URL limits, this reacts to actual memory pressure, to avoid
+    /// the process being killed on long unattended crawls.
+    pub fn with_memory_limit(mut self, memory_limit: u64) -> Self {
+        self.memory_limit = Some(memory_limit);
+        self
+    }
+
+    /// Set the [MemoryReader] used for the memory limit. Defaults to [ProcessMemoryReader].
+    pub fn with_memory_reader(mut self, memory_reader: Arc<dyn MemoryReader>) -> Self {
+        self.memory_reader = memory_reader;
+        self
+    }
+
+    /// Stop visiting a host once `host_failure_threshold` visits to it in a row have failed to
+    /// reach it with the errors [VisitorError::is_retryable] accepts: failed DNS lookups, timeouts
+    /// and connection failures. Other errors do not count. For the rest of the crawl, newly
+    /// discovered links to the host are dropped and counted in [AllPages::skipped] under
+    /// [SkipReason::HostDown]. Any page the host responds with resets its count.
+    ///
+    /// Unlike circuit breaker middleware, which rejects requests for a while and then tries the
+    /// host again, this gives up on the host, so a crawl does not spend its time on a host that is down.
+    /// A threshold of zero behaves like one.
+    pub fn with_host_failure_threshold(mut self, host_failure_threshold: usize) -> Self {
+        self.host_failure_threshold = Some(host_failure_threshold);
+        self
+    }
+
+    /// Retry visits that fail with a [retryable](VisitorError::is_retryable) error, such as a
+    /// timeout or connection error, up to `max_retries` times before recording them as failed.
+    /// The first retry waits for `backoff`, and each later retry waits twice as long as the last,
+    /// up to five minutes.
+    ///
+    /// This is in addition to any retries made by the [SiteVisitor], such as by retry middleware,
+    /// for failures that only surface once it has given up.
+    pub fn with_page_retry(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.page_retry = Some(PageRetry {
+            max_retries,
+            backoff,
+        });
+        self
+    }
+
+    /// Set how the crawler decides whether a link is in scope. Defaults to [ScopeMode::SameDomain].
+    pub fn with_scope_mode(mut self, scope_mode: ScopeMode) -> Self {
+        self.scope_mode = scope_mode;
+        self
+    }
+
+    /// Set the URL schemes the crawler will visit. Defaults to `http` and `https`.
+    /// Use this with a [SiteVisitor] that speaks another protocol.
+    pub fn with_allowed_schemes(mut self, allowed_schemes: HashSet<String>) -> Self {
+        self.allowed_schemes = allowed_schemes;
+        self
+    }
+
+    /// Only visit URLs on these ports, using the scheme's default port when none is given.
+    /// Links to other ports are still recorded on each [Page]. Defaults to allowing every port.
+    pub fn with_allowed_ports(mut self, allowed_ports: HashSet<u16>) -> Self {
+        self.allowed_ports = Some(allowed_ports);
+        self
+    }
+
+    /// Only follow links whose path is under one of these prefixes, such as `["/docs", "/blog"]`.
+    /// Prefixes match whole path segments, so `/docs` allows `/docs` and `/docs/intro` but not
+    /// `/docsearch`. Seeds are visited whatever their path, and links to other paths are still
+    /// recorded on each [Page]. Defaults to allowing every path.
+    pub fn with_allowed_path_prefixes(mut self, allowed_path_prefixes: &[&str]) -> Self {
+        let allowed_path_prefixes = allowed_path_prefixes
+            .iter()
+            .map(|prefix| format!("/{}", prefix.trim_matches('/')))
+            .collect();
+        self.allowed_path_prefixes = Some(allowed_path_prefixes);
+        self
+    }
+
+    /// Set how trailing slashes are normalized when deciding if two URLs are the same page.
+    /// Pages are still requested at the URL they were linked with. Defaults to [Normalize::Off].
+    pub fn with_trailing_slash_normalization(mut self, trailing_slash: Normalize) -> Self {
+        self.trailing_slash = trailing_slash;
+        self
+    }
+
+    /// Lowercase hosts and decode percent-encoded unreserved characters, so `/%7Euser` and `/~user`
+    /// are the same page. Defaults to `false`.
+    pub fn with_encoding_normalization(mut self, normalize_encoding: bool) -> Self {
+        self.normalize_encoding = normalize_encoding;
+        self
+    }
+
+    /// Treat URLs whose query parameters only differ in order, such as `?a=1&b=2` and `?b=2&a=1`,
+    /// as the same page. Only the first form found is visited, and it is reported as found.
+    /// Defaults to `false`.
+    pub fn with_sorted_query_dedup(mut self, sorted_query_dedup: bool) -> Self {
+        self.sorted_query_dedup = sorted_query_dedup;
+        self
+    }
+
+    /// Treat a directory and its index file, such as `/dir/` and `/dir/index.html`, as the same page
+    /// if the index file's name is one of `index_files`. Only the first form found is visited, and
+    /// it is reported as found.
+    pub fn with_index_file_collapse(mut self, index_files: &[&str]) -> Self {
+        self.index_files = index_files
+            .iter()
+            .map(|index_file| index_file.to_string())
+            .collect();
+        self
+    }
+
+    /// Visit URLs that do not look like HTML pages from their path, such as `/report.aspx`,
+    /// and crawl them only if the response has an HTML, RSS or Atom `Content-Type`. Defaults to `false`.
+    pub fn with_verify_content_type(mut self, verify_content_type: bool) -> Self {
+        self.verify_content_type = verify_content_type;
+        self
+    }
+
+    /// Send a `HEAD` request to each in-scope link that is not visited because its path does not
+    /// look like an HTML page, such as `/logo.png`, and count its `Content-Type` along with those of
+    /// visited pages in [AllPages::content_type_histogram]. Each URL is probed once, and probes
+    /// still running when a limit stops the crawl are abandoned. Defaults to `false`.
+    pub fn with_content_type_probe(mut self, probe_content_types: bool) -> Self {
+        self.probe_content_types = probe_content_types;
+        self
+    }
+
+    /// Send a `HEAD` request to each [download](Page::downloads) link found on visited pages, to
+    /// check that the file exists and find its size, without downloading it. Results are recorded
+    /// in [AllPages::download_checks]. Each URL is checked once, and checks still running when a
+    /// limit stops the crawl are abandoned. Defaults to `false`.
+    pub fn with_download_check(mut self, check_downloads: bool) -> Self {
+        self.check_downloads = check_downloads;
+        self
+    }
+
+    /// Treat these URLs as already visited, so they are never scheduled even when linked to.
+    /// URLs are normalized in the same way as discovered links before they are compared.
+    pub fn with_preexcluded(mut self, preexcluded: HashSet<Url>) -> Self {
+        self.preexcluded = preexcluded;
+        self
+    }
+
+    /// Compare pages against the [content manifest](AllPages::content_manifest) of a previous
+    /// crawl. See [CrawlerBuilder::with_skip_unchanged_subtrees].
+    pub fn with_prior_manifest(mut self, prior_manifest: HashMap<Url, u64>) -> Self {
+        self.prior_manifest = prior_manifest;
+        self
+    }
+
+    /// Do not follow the links of pages whose content is unchanged since the crawl that produced
+    /// the [prior manifest](CrawlerBuilder::with_prior_manifest), assuming the pages below them
+    /// are unchanged too. Unchanged pages are still reported, and pages they link to are still
+    /// visited if a changed page also links to them. Defaults to `false`.
+    pub fn with_skip_unchanged_subtrees(mut self, skip_unchanged_subtrees: bool) -> Self {
+        self.skip_unchanged_subtrees = skip_unchanged_subtrees;
+        self
+    }
+
+    /// Abandon parsing a page if it takes longer than `parse_timeout`. The page is still reported,
+    /// flagged with [Page::parse_timed_out] and without links. Defaults to no timeout.
+    pub fn with_parse_timeout(mut self, parse_timeout: Duration) -> Self {
+        self.parse_timeout = Some(parse_timeout);
+        self
+    }
+
+    /// Only follow links found inside elements matching a CSS selector, such as `"main, nav"`,
+    /// ignoring boilerplate links elsewhere on the page. Defaults to the whole document.
+    pub fn with_link_scope_selector(mut self, selector: &str) -> anyhow::Result<Self> {
+        let parsed = Selector::parse(selector)
+            .map_err(|e| anyhow::anyhow!("Invalid CSS selector {:?}: {}", selector, e))?;
+        self.link_region = Some((selector.to_string(), Arc::new(parsed)));
+        Ok(self)
+    }
+
+    /// Also follow links inside `<noscript>` elements, which often hold fallback navigation on
+    /// sites that build their menus with JavaScript. Defaults to `false`.
+    pub fn with_parse_noscript(mut self, parse_noscript: bool) -> Self {
+        self.parse_noscript = parse_noscript;
+        self
+    }
+
+    /// Cut the text recorded for each anchor in [Page::anchors] to `max_anchor_text_len`
+    /// characters, followed by `…`, so pages linking whole paragraphs do not bloat memory.
+    /// Defaults to unlimited.
+    pub fn with_max_anchor_text_len(mut self, max_anchor_text_len: usize) -> Self {
+        self.max_anchor_text_len = Some(max_anchor_text_len);
+        self
+    }
+
+    /// Parse pages on a dedicated pool of `threads` threads instead of Tokio's shared blocking pool,
+    /// so parse-heavy crawls neither starve nor are starved by other blocking work.
+    pub fn with_parse_threads(mut self, threads: usize) -> anyhow::Result<Self> {
+        self.parse_pool = Some(Arc::new(ParsePool::new(threads)?));
+        Ok(self)
+    }
+
+    /// Send conditional requests for pages with an `ETag` in `etag_cache`, reusing the cached page
+    /// and its links when the server responds `304 Not Modified`. Pages fetched with an `ETag` are
+    /// added to the cache, so sharing it between crawls skips parsing unchanged pages.
+    /// The [SiteVisitor] must implement [SiteVisitor::visit_if_none_match] for requests to be conditional.
+    pub fn with_etag_cache(mut self, etag_cache: Arc<EtagCache>) -> Self {
+        self.etag_cache = Some(etag_cache);
+        self
+    }
+
+    /// Keep the fetched body of each page in [Page::content]. Defaults to `false`.
+    /// Every page's body is held in memory until the crawl finishes, so large crawls can use a lot of memory.
+    pub fn with_retain_content(mut self, retain_content: bool) -> Self {
+        self.retain_content = retain_content;
+        self
+    }
+
+    /// Keep each page's links in the returned [AllPages]. Defaults to `true`.
+    /// When `false`, link sets are dropped once they have been scheduled, keeping only each page's
+    /// URL and status to reduce memory on large crawls. Subscribers still receive pages with their links.
+    pub fn with_retain_links(mut self, retain_links: bool) -> Self {
+        self.retain_links = retain_links;
+        self
+    }
+
+    /// Move each page's [Page::links] into [AllPages::link_table], which stores each distinct URL
+    /// once and shares it between every page linking to it. Defaults to `false`. Saves memory on
+    /// large sites whose pages link to the same URLs, but leaves [Page::links] of the returned
+    /// pages empty. Has no effect if links are not retained.
+    pub fn with_link_interning(mut self, intern_links: bool) -> Self {
+        self.intern_links = intern_links;
+        self
+    }
+
+    /// Do not follow links whose anchor is marked `rel="nofollow"`.
+    pub fn with_respect_nofollow(mut self, respect_nofollow: bool) -> Self {
+        self.respect_nofollow = respect_nofollow;
+        self
+    }
+
+    /// Honour `<meta name="robots">` and `X-Robots-Tag` directives. Pages marked `noindex` are
+    /// visited but not reported, and links on pages marked `nofollow` are not followed.
+    pub fn with_respect_meta_robots(mut self, respect_meta_robots: bool) -> Self {
+        self.respect_meta_robots = respect_meta_robots;
+        self
+    }
+
+    /// Honour the `Crawl-delay` directive of the robots.txt file provided with [CrawlerBuilder::with_robot],
+    /// waiting at least that long between the start of consecutive requests.
+    pub fn with_respect_crawl_delay(mut self, respect_crawl_delay: bool) -> Self {
+        self.respect_crawl_delay = respect_crawl_delay;
+        self
+    }
+
+    /// Enable every politeness behaviour at once. This is equivalent to calling
+    /// [with_respect_nofollow](CrawlerBuilder::with_respect_nofollow),
+    /// [with_respect_meta_robots](CrawlerBuilder::with_respect_meta_robots) and
+    /// [with_respect_crawl_delay](CrawlerBuilder::with_respect_crawl_delay) with `true`.
+    ///
+    /// Disallow rules and the crawl delay come from the robots.txt file, which must still be
+    /// provided with [CrawlerBuilder::with_robot].
+    pub fn with_strict_politeness(self) -> Self {
+        self.with_respect_nofollow(true)
+            .with_respect_meta_robots(true)
+            .with_respect_crawl_delay(true)
+    }
+
+    /// The crawl delay that will be applied between requests, if any.
+    fn crawl_delay(&self) -> Option<Duration> {
+        self.robots_crawl_delay
+            .filter(|_| self.respect_crawl_delay)
+            .and_then(|delay| Duration::try_from_secs_f32(delay).ok())
+    }
+
+    /// Get a snapshot of the configuration the crawler will be built with.
+    pub fn config_summary(&self) -> CrawlConfig {
+        CrawlConfig {
+            max_time: self.max_time,
+            deadline: self.deadline,
+            max_pages: self.max_pages,
+            max_bytes: self.max_bytes,
+            max_tracked_urls: self.max_tracked_urls,
+            memory_limit: self.memory_limit,
+            host_failure_threshold: self.host_failure_threshold,
+            page_retry: self
+                .page_retry
+                .map(|page_retry| (page_retry.max_retries, page_retry.backoff)),
+            shutdown_drain: self.shutdown_drain,
+            robots: self.robots_matcher.is_some(),
+            ignore_robots_for_seed_host: self.ignore_robots_for_seed_host,
+            schedule_seed: self.schedule_seed,
+            strategy: self.strategy,
+            max_in_flight: self.max_in_flight,
+            scope_mode: self.scope_mode.clone(),
+            allowed_schemes: self.allowed_schemes.iter().cloned().collect(),
+            allowed_ports: self
+                .allowed_ports
+                .as_ref()
+                .map(|allowed_ports| allowed_ports.iter().copied().collect()),
+            allowed_path_prefixes: self.allowed_path_prefixes.clone(),
+            trailing_slash: self.trailing_slash,
+            normalize_encoding: self.normalize_encoding,
+            sorted_query_dedup: self.sorted_query_dedup,
+            index_files: self.index_files.clone(),
+            verify_content_type: self.verify_content_type,
+            probe_content_types: self.probe_content_types,
+            check_downloads: self.check_downloads,
+            preexcluded: self.preexcluded.len(),
+            prior_manifest: self.prior_manifest.len(),
+            skip_unchanged_subtrees: self.skip_unchanged_subtrees,
+            parse_timeout: self.parse_timeout,
+            link_region: self.link_region.as_ref().map(|(source, _)| source.clone()),
+            parse_noscript: self.parse_noscript,
+            max_anchor_text_len: self.max_anchor_text_len,
+            parse_threads: self.parse_pool.as_ref().map(|pool| pool.threads()),
+            retain_content: self.retain_content,
+            etag_cache: self.etag_cache.is_some(),
+            retain_links: self.retain_links,
+            intern_links: self.intern_links,
+            respect_nofollow: self.respect_nofollow,
+            respect_meta_robots: self.respect_meta_robots,
+            respect_crawl_delay: self.respect_crawl_delay,
+            crawl_delay: self.crawl_delay(),
+            page_store: self.page_store.is_some(),
+            event_sender: self.event_sender.is_some(),
+        }
+    }
+
     /// Build the crawler.
     pub fn build(self) -> Crawler<V> {
         let (tx, _) = broadcast::channel(100);
+        let (external_tx, _) = broadcast::channel(1000);
+        let request_spacer = self
+            .crawl_delay()
+            .map(|delay| RequestSpacer::new(delay, self.clock.clone()));
         Crawler {
             site_visitor: self.site_visitor,
-            robot: self.robot,
+            robots_matcher: self.robots_matcher,
+            schedule_seed: self.schedule_seed,
+            strategy: self.strategy,
+            max_in_flight: self.max_in_flight,
+            ignore_robots_for_seed_host: self.ignore_robots_for_seed_host,
+            seed_hosts: HashSet::new(),
             tasks: JoinSet::new(),
+            in_flight: HashMap::new(),
+            control: CrawlControl::default(),
+            live_stats: LiveStats::new(self.clock.clone()),
             channel: tx,
+            external_channel: external_tx,
+            event_sender: self.event_sender,
             max_time: self.max_time,
+            deadline: self.deadline,
             max_pages: self.max_pages,
+            max_bytes: self.max_bytes,
+            max_tracked_urls: self.max_tracked_urls,
+            memory_limit: self.memory_limit,
+            host_failure_threshold: self.host_failure_threshold,
+            page_retry: self.page_retry,
+            shutdown_drain: self.shutdown_drain,
+            scope_mode: self.scope_mode,
+            allowed_schemes: self.allowed_schemes,
+            allowed_ports: self.allowed_ports,
+            allowed_path_prefixes: self.allowed_path_prefixes,
+            trailing_slash: self.trailing_slash,
+            normalize_encoding: self.normalize_encoding,
+            sorted_query_dedup: self.sorted_query_dedup,
+            index_files: self.index_files,
+            verify_content_type: self.verify_content_type,
+            probe_content_types: self.probe_content_types,
+            check_downloads: self.check_downloads,
+            preexcluded: self.preexcluded,
+            prior_manifest: self.prior_manifest,
+            skip_unchanged_subtrees: self.skip_unchanged_subtrees,
+            parse_timeout: self.parse_timeout,
+            link_region: self.link_region,
+            parse_noscript: self.parse_noscript,
+            max_anchor_text_len: self.max_anchor_text_len,
+            parse_pool: self.parse_pool,
+            retain_content: self.retain_content,
+            etag_cache: self.etag_cache,
+            retain_links: self.retain_links,
+            intern_links: self.intern_links,
+            respect_nofollow: self.respect_nofollow,
+            respect_meta_robots: self.respect_meta_robots,
+            request_spacer,
+            page_store: self.page_store,
+            clock: self.clock,
+            memory_reader: self.memory_reader,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{PageRetry, MAX_RETRY_BACKOFF};
+
+    #[test]
+    fn test_retry_backoff() {
+        let page_retry = PageRetry {
+            max_retries: u32::MAX,
+            backoff: Duration::from_secs(1),
+        };
+
+        assert_eq!(page_retry.backoff(1), Duration::from_secs(1));
+        assert_eq!(page_retry.backoff(3), Duration::from_secs(4));
+        assert_eq!(page_retry.backoff(40), MAX_RETRY_BACKOFF);
+        assert_eq!(page_retry.backoff(u32::MAX), MAX_RETRY_BACKOFF);
+    }
+}
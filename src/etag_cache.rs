@@ -0,0 +1,45 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use url::Url;
+
+use crate::parser::Page;
+
+/// The `ETag` and parsed page of each URL seen by a crawl, so a later crawl sharing the cache can
+/// make conditional requests and reuse the page when the server responds `304 Not Modified`.
+/// See [CrawlerBuilder::with_etag_cache](crate::CrawlerBuilder::with_etag_cache).
+#[derive(Debug, Default)]
+pub struct EtagCache {
+    entries: Mutex<HashMap<Url, (String, Page)>>,
+}
+
+impl EtagCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of cached pages.
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("Could not acquire lock").len()
+    }
+
+    /// Whether no pages are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `ETag` and page cached for `url`.
+    pub(crate) fn get(&self, url: &Url) -> Option<(String, Page)> {
+        self.entries
+            .lock()
+            .expect("Could not acquire lock")
+            .get(url)
+            .cloned()
+    }
+
+    pub(crate) fn insert(&self, url: Url, etag: String, page: Page) {
+        self.entries
+            .lock()
+            .expect("Could not acquire lock")
+            .insert(url, (etag, page));
+    }
+}
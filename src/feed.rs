@@ -0,0 +1,104 @@
+use http::HeaderValue;
+use roxmltree::{Document, Node, ParsingOptions};
+
+/// Whether a `Content-Type` header names an RSS or Atom feed.
+pub(crate) fn is_feed_content_type(content_type: Option<&HeaderValue>) -> bool {
+    content_type
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| {
+            let mime = content_type.split(';').next().unwrap_or_default().trim();
+            mime.eq_ignore_ascii_case("application/rss+xml")
+                || mime.eq_ignore_ascii_case("application/atom+xml")
+        })
+}
+
+/// The unresolved link of each entry in an RSS `<item>` or Atom `<entry>`, in document order.
+/// RSS items use the text of their `<link>`, and Atom entries the `href` of their `alternate` links.
+/// Returns `None` if the content is not well-formed XML.
+pub(crate) fn feed_entry_links(content: &str) -> Option<Vec<String>> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document = Document::parse_with_options(content, options).ok()?;
+
+    let mut links = Vec::new();
+    for node in document.descendants() {
+        match node.tag_name().name() {
+            "item" => links.extend(
+                children(node, "link")
+                    .filter_map(|link| link.text())
+                    .map(|link| link.trim().to_string()),
+            ),
+            "entry" => links.extend(
+                children(node, "link")
+                    .filter(|link| link.attribute("rel").is_none_or(|rel| rel == "alternate"))
+                    .filter_map(|link| link.attribute("href"))
+                    .map(str::to_string),
+            ),
+            _ => {}
+        }
+    }
+    Some(links)
+}
+
+/// The child elements of `node` with the local name `name`, ignoring namespaces.
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.tag_name().name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::{feed_entry_links, is_feed_content_type};
+
+    #[test]
+    fn test_feed_entry_links() {
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+              <link>https://monzo.com/blog</link>
+              <item><title>One</title><link> https://monzo.com/blog/one </link></item>
+              <item><title>Two</title><link>/blog/two</link></item>
+            </channel></rss>"#;
+        assert_eq!(
+            feed_entry_links(rss),
+            Some(vec![
+                "https://monzo.com/blog/one".to_string(),
+                "/blog/two".to_string()
+            ])
+        );
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+              <link rel="self" href="https://monzo.com/feed.atom"/>
+              <entry>
+                <link href="https://monzo.com/blog/one"/>
+                <link rel="edit" href="https://monzo.com/edit/one"/>
+              </entry>
+              <entry><link rel="alternate" href="/blog/two"/></entry>
+            </feed>"#;
+        assert_eq!(
+            feed_entry_links(atom),
+            Some(vec![
+                "https://monzo.com/blog/one".to_string(),
+                "/blog/two".to_string()
+            ])
+        );
+
+        assert_eq!(feed_entry_links("<rss><channel>"), None);
+    }
+
+    #[test]
+    fn test_is_feed_content_type() {
+        let feed =
+            |content_type| is_feed_content_type(Some(&HeaderValue::from_static(content_type)));
+        assert!(feed("application/rss+xml"));
+        assert!(feed("application/atom+xml; charset=utf-8"));
+        assert!(!feed("text/html"));
+        assert!(!is_feed_content_type(None));
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    io::{self, Write},
+};
+
+use serde::Serialize;
+use url::Url;
+
+use crate::parser::AllPages;
+
+/// A line of the JSON Lines graph export.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum GraphRecord<'a> {
+    Node { url: &'a str, status: Option<u16> },
+    Edge { from: &'a str, to: &'a str },
+}
+
+impl AllPages {
+    /// Every unique link between a visited page and another URL, as `(from, to)` pairs in URL order.
+    /// If `exclude_seed_backlinks` is set, links from a page to itself and links to a seed are left
+    /// out, as nearly every page links back to the home page. The links are kept on each [Page](crate::Page).
+    /// Only includes links if the crawler retained them (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn to_edges(&self, exclude_seed_backlinks: bool) -> BTreeSet<(&Url, &Url)> {
+        let seeds: HashSet<&Url> = self
+            .pages
+            .iter()
+            .filter(|page| page.parent.is_none())
+            .map(|page| &page.url)
+            .collect();
+
+        self.pages
+            .iter()
+            .flat_map(|page| {
+                page.links
+                    .iter()
+                    .chain(page.external_links.iter())
+                    .map(move |link| (&page.url, link))
+            })
+            .filter(|&(from, to)| {
+                let target = self.aliases.get(to).unwrap_or(to);
+                !exclude_seed_backlinks || (target != from && !seeds.contains(target))
+            })
+            .collect()
+    }
+
+    /// Write the link graph as [JSON Lines](https://jsonlines.org) for loading into a graph database.
+    /// Each line is either `{"type":"node","url":...,"status":...}`, with a `null` status for URLs
+    /// that were not visited, or `{"type":"edge","from":...,"to":...}` for a link between two URLs.
+    /// Nodes are written before edges, each once and in URL order. Edges are those of
+    /// [AllPages::to_edges], so `exclude_seed_backlinks` leaves out self-loops and links to a seed.
+    /// Only includes links if the crawler retained them (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn write_graph_jsonl(
+        &self,
+        mut writer: impl Write,
+        exclude_seed_backlinks: bool,
+    ) -> io::Result<()> {
+        let mut nodes: BTreeMap<_, _> =
+            self.all_urls().into_iter().map(|url| (url, None)).collect();
+        for page in self.pages.iter() {
+            nodes.insert(page.url.clone(), Some(page.status_code.as_u16()));
+        }
+
+        let edges = self.to_edges(exclude_seed_backlinks);
+
+        let nodes = nodes.iter().map(|(url, status)| GraphRecord::Node {
+            url: url.as_str(),
+            status: *status,
+        });
+        let edges = edges.iter().map(|(from, to)| GraphRecord::Edge {
+            from: from.as_str(),
+            to: to.as_str(),
+        });
+        for record in nodes.chain(edges) {
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderMap;
+    use reqwest::StatusCode;
+    use url::Url;
+
+    use crate::{parse_links, AllPages, PageContent};
+
+    fn all_pages(pages: &[(&str, &str)]) -> anyhow::Result<AllPages> {
+        let mut all_pages = AllPages {
+            pages: pages
+                .iter()
+                .map(|&(url, content)| {
+                    Ok(parse_links(&PageContent {
+                        url: Url::parse(url)?,
+                        status_code: StatusCode::OK,
+                        content: content.to_string(),
+                        content_type: None,
+                        headers: HeaderMap::new(),
+                        http_version: None,
+                    }))
+                })
+                .collect::<anyhow::Result<_>>()?,
+            ..Default::default()
+        };
+        // Every page after the first was found on the first
+        let seed = all_pages.pages.first().map(|page| page.url.clone());
+        for page in all_pages.pages.iter_mut().skip(1) {
+            page.parent = seed.clone();
+        }
+        Ok(all_pages)
+    }
+
+    #[test]
+    fn test_to_edges_exclude_seed_backlinks() -> anyhow::Result<()> {
+        let all_pages = all_pages(&[
+            (
+                "https://monzo.com/",
+                r#"<a href="/"></a> <a href="/about"></a>"#,
+            ),
+            (
+                "https://monzo.com/about",
+                r#"<a href="/"></a> <a href="/about"></a> <a href="/careers"></a>"#,
+            ),
+        ])?;
+        let edge = |from: &str, to: &str| -> anyhow::Result<(Url, Url)> {
+            Ok((Url::parse(from)?, Url::parse(to)?))
+        };
+        let edges = |exclude_seed_backlinks: bool| -> Vec<(Url, Url)> {
+            all_pages
+                .to_edges(exclude_seed_backlinks)
+                .into_iter()
+                .map(|(from, to)| (from.clone(), to.clone()))
+                .collect()
+        };
+
+        assert_eq!(edges(false).len(), 5);
+        assert_eq!(
+            edges(true),
+            [
+                edge("https://monzo.com/", "https://monzo.com/about")?,
+                edge("https://monzo.com/about", "https://monzo.com/careers")?,
+            ]
+        );
+        // The links are kept on the pages
+        assert_eq!(all_pages.pages[1].links.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_graph_jsonl() -> anyhow::Result<()> {
+        let all_pages = all_pages(&[
+            (
+                "https://monzo.com/",
+                r#"<a href="/about"></a> <a href="/about"></a> <a href="https://github.com/monzo"></a>"#,
+            ),
+            (
+                "https://monzo.com/about",
+                r#"<a href="/"></a> <a href="/careers"></a> <a href="https://github.com/monzo"></a>"#,
+            ),
+        ])?;
+
+        let mut jsonl = Vec::new();
+        all_pages.write_graph_jsonl(&mut jsonl, false)?;
+        let lines: Vec<&str> = std::str::from_utf8(&jsonl)?.lines().collect();
+
+        let count = |record_type: &str| {
+            lines
+                .iter()
+                .filter(|line| line.starts_with(&format!(r#"{{"type":"{record_type}""#)))
+                .count()
+        };
+        assert_eq!(count("node"), all_pages.all_urls().len());
+        assert_eq!(count("node"), 4);
+        assert_eq!(count("edge"), 5);
+        assert_eq!(lines.len(), 9);
+        assert!(lines.contains(&r#"{"type":"node","url":"https://monzo.com/about","status":200}"#));
+        assert!(
+            lines.contains(&r#"{"type":"node","url":"https://github.com/monzo","status":null}"#)
+        );
+        assert!(lines.contains(
+            &r#"{"type":"edge","from":"https://monzo.com/","to":"https://monzo.com/about"}"#
+        ));
+
+        Ok(())
+    }
+}
@@ -1,8 +1,44 @@
 //! spider_crab is a library for crawling websites.
 //!
+mod analysis;
 pub mod client_middleware;
+mod clock;
 mod crawler;
+mod etag_cache;
+mod feed;
+mod graph;
+mod live_stats;
+mod manifest;
+mod memory;
+mod normalize;
+mod page_store;
+mod parity;
+mod parse_pool;
 mod parser;
+mod report;
+mod scope;
+mod sitemap;
+mod tower_visitor;
 pub use client_middleware::ClientWithMiddlewareVisitor;
-pub use crawler::{Crawler, CrawlerBuilder, PageContent, SiteVisitor, VisitorError};
-pub use parser::{parse_links, AllPages, Page};
+pub use clock::{Clock, TokioClock};
+pub use crawler::{
+    CrawlConfig, CrawlControl, CrawlError, CrawlEvent, CrawlStrategy, Crawler, CrawlerBuilder,
+    PageContent, SiteVisitor, VisitorError,
+};
+pub use etag_cache::EtagCache;
+pub use live_stats::{LiveStats, StatsSnapshot};
+pub use manifest::{CrawlManifest, ManifestEntry};
+pub use memory::{MemoryReader, ProcessMemoryReader};
+pub use normalize::Normalize;
+pub use page_store::PageStore;
+#[cfg(feature = "sqlite")]
+pub use page_store::SqlitePageStore;
+pub use parity::ParityReport;
+pub use parser::{
+    parse_links, AllPages, ContentKind, DownloadCheck, FailedVisit, FailureReason, LinkTable,
+    MetaRobots, Page, Redirect, SkipReason,
+};
+pub use report::{BrokenLink, CrawlReport, CrawlStats, ReportOptions, SitemapCoverageReport};
+pub use scope::ScopeMode;
+pub use sitemap::SitemapCoverage;
+pub use tower_visitor::TowerVisitor;
@@ -0,0 +1,91 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use crate::clock::Clock;
+
+/// Counts for a crawl at a moment in time. See [LiveStats::snapshot].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// Pages visited so far, including pages excluded from the results by `noindex`.
+    pub pages_visited: u64,
+    /// URLs being visited or waiting to be visited.
+    pub frontier: usize,
+    /// URLs that could not be visited.
+    pub failed: usize,
+    /// Time since the crawl started, or how long it took once it has finished.
+    pub elapsed: Duration,
+    pub finished: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    pages_visited: u64,
+    frontier: usize,
+    failed: usize,
+    started: Option<SystemTime>,
+    finished: Option<SystemTime>,
+}
+
+/// A handle for reading the progress of a running crawl, such as to report it from another task.
+/// Get one with [Crawler::live_stats](crate::Crawler::live_stats).
+#[derive(Clone, Debug)]
+pub struct LiveStats {
+    state: Arc<Mutex<State>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl LiveStats {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            state: Arc::default(),
+            clock,
+        }
+    }
+
+    /// The crawl's counts as of now. Before the crawl starts, every count is zero.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let state = self.lock();
+        let elapsed = state
+            .started
+            .map(|started| {
+                state
+                    .finished
+                    .unwrap_or_else(|| self.clock.now())
+                    .duration_since(started)
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default();
+
+        StatsSnapshot {
+            pages_visited: state.pages_visited,
+            frontier: state.frontier,
+            failed: state.failed,
+            elapsed,
+            finished: state.finished.is_some(),
+        }
+    }
+
+    pub(crate) fn start(&self) {
+        self.lock().started = Some(self.clock.now());
+    }
+
+    pub(crate) fn update(&self, pages_visited: u64, frontier: usize, failed: usize) {
+        let mut state = self.lock();
+        state.pages_visited = pages_visited;
+        state.frontier = frontier;
+        state.failed = failed;
+    }
+
+    pub(crate) fn finish(&self, pages_visited: u64, failed: usize) {
+        self.update(pages_visited, 0, failed);
+        self.lock().finished = Some(self.clock.now());
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("Live stats lock poisoned. This is a bug.")
+    }
+}
@@ -1,9 +1,21 @@
 mod cli;
-use std::{path::Path, time::Duration};
+mod color;
+mod output;
+mod progress;
+mod robots;
+mod status;
+mod summary;
+use std::{
+    collections::HashSet,
+    io::{IsTerminal, Write},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
 
 use clap::Parser;
-use cli::Cli;
-use indicatif::{MultiProgress, ProgressBar};
+use cli::{Cli, Command, OutputFormat, RobotsCheckArgs, SeedList, Seeds, Strategy};
+use color::Colors;
 use opentelemetry::{trace::TracerProvider as _, KeyValue};
 use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::{
@@ -12,82 +24,97 @@ use opentelemetry_sdk::{
     Resource,
 };
 use spider_crab::{
-    client_middleware::{MaxConcurrentMiddleware, RetryTooManyRequestsMiddleware},
-    AllPages, ClientWithMiddlewareVisitor, CrawlerBuilder,
+    client_middleware::{
+        login, seeds_redirect_policy, LimitedResolver, MaxConcurrentMiddleware,
+        PerHostConcurrentMiddleware, RetryTooManyRequestsMiddleware,
+    },
+    AllPages, ClientWithMiddlewareVisitor, CrawlStrategy, CrawlerBuilder, ReportOptions, ScopeMode,
 };
 
+use output::{write_atomically, write_file_atomically};
 use owo_colors::{self, OwoColorize};
 use reqwest::redirect;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use reqwest_tracing::TracingMiddleware;
-use texting_robots::get_robots_url;
 use tokio::{io::AsyncWriteExt, time::Instant};
 use url::Url;
 
+use tracing::{info, warn};
 use tracing_subscriber::{prelude::*, EnvFilter};
 
 static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
 
-/// A client with middleware for obtaining Robots.txt files.
-/// Roughly follows https://github.com/Smerity/texting_robots?tab=readme-ov-file#crawling-considerations
-fn robots_client() -> anyhow::Result<ClientWithMiddleware> {
-    let retry_policy = ExponentialBackoff::builder().build_with_max_retries(5);
-
-    Ok(ClientBuilder::new(
-        reqwest::Client::builder()
-            .user_agent(APP_USER_AGENT)
-            .redirect(redirect::Policy::limited(10))
-            .build()?,
-    )
-    .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-    .with(RetryTooManyRequestsMiddleware::new(Duration::from_secs(5)))
-    .with(TracingMiddleware::default())
-    .build())
+/// Build the HTTP client requests are sent with. If `accept_invalid_hostnames` is set, TLS
+/// certificates are still verified, but a certificate for any host is accepted.
+fn http_client(
+    connect_timeout: Duration,
+    redirect_policy: redirect::Policy,
+    cookie_store: bool,
+    accept_invalid_hostnames: bool,
+    max_concurrent_dns: Option<usize>,
+) -> reqwest::Result<reqwest::Client> {
+    let mut client = reqwest::Client::builder()
+        .user_agent(APP_USER_AGENT)
+        .redirect(redirect_policy)
+        .connect_timeout(connect_timeout)
+        .cookie_store(cookie_store)
+        .danger_accept_invalid_hostnames(accept_invalid_hostnames);
+    if let Some(max_concurrent_dns) = max_concurrent_dns {
+        client = client.dns_resolver(Arc::new(LimitedResolver::new(max_concurrent_dns)));
+    }
+    client.build()
 }
 
 fn crawler_client(
+    client: reqwest::Client,
     max_retries: u32,
     too_many_requests_delay: Duration,
     max_concurrent_connections: usize,
-) -> anyhow::Result<ClientWithMiddleware> {
+    max_concurrent_per_host: Option<usize>,
+    slow_start_recovery: Option<Duration>,
+) -> ClientWithMiddleware {
     let retry_policy = ExponentialBackoff::builder()
         .jitter(reqwest_retry::Jitter::Bounded)
         .build_with_max_retries(max_retries);
 
-    Ok(ClientBuilder::new(
-        reqwest::Client::builder()
-            .user_agent(APP_USER_AGENT)
-            .redirect(redirect::Policy::limited(10))
-            .build()?,
-    )
-    .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-    .with(RetryTooManyRequestsMiddleware::new(too_many_requests_delay))
-    .with(MaxConcurrentMiddleware::new(max_concurrent_connections))
-    .with(TracingMiddleware::default())
-    .build())
-}
+    let mut client = ClientBuilder::new(client)
+        .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+        .with(RetryTooManyRequestsMiddleware::new(too_many_requests_delay));
+    // Wait for the host's limit before taking a global permit, so requests queued for a busy host
+    // don't hold permits that requests to other hosts could use.
+    if let Some(max_concurrent_per_host) = max_concurrent_per_host {
+        client = client.with(PerHostConcurrentMiddleware::new(max_concurrent_per_host));
+    }
 
-/// Try to get a robots.txt file for a given URL, returning None if it doesn't exist.
-async fn get_robots(root_url: &Url) -> anyhow::Result<String> {
-    let rclient = robots_client()?;
-    let robots_url = get_robots_url(root_url.as_str())?;
+    let mut max_concurrent = MaxConcurrentMiddleware::new(max_concurrent_connections);
+    if let Some(recovery_interval) = slow_start_recovery {
+        max_concurrent = max_concurrent.with_slow_start(0.5, recovery_interval);
+    }
 
-    let res = rclient.get(robots_url.as_str()).send().await?;
-    let robots = res.text().await;
-    robots.map_err(Into::into)
+    client
+        .with(max_concurrent)
+        .with(TracingMiddleware::default())
+        .build()
 }
 
-fn print_links(all_pages: &AllPages, hide_links: bool) {
-    for page in all_pages.0.iter() {
-        println!("{}", page.url.green());
+/// Write each page followed by its links, in `colors`.
+fn write_links(
+    out: &mut impl Write,
+    all_pages: &AllPages,
+    hide_links: bool,
+    colors: Colors,
+) -> std::io::Result<()> {
+    for page in all_pages.pages.iter() {
+        writeln!(out, "{}", page.url.style(colors.green()))?;
 
         if !hide_links {
             for link in page.links.iter() {
-                println!("  --> {}", link.cyan());
+                writeln!(out, "  --> {}", link.style(colors.cyan()))?;
             }
         }
     }
+    Ok(())
 }
 
 async fn write_links_to_file(
@@ -95,16 +122,71 @@ async fn write_links_to_file(
     file: &Path,
     hide_links: bool,
 ) -> anyhow::Result<()> {
-    let mut file = tokio::fs::File::create(file).await?;
-    for page in all_pages.0.iter() {
-        file.write_all(format!("{}\n", page.url).as_bytes()).await?;
-        if !hide_links {
-            for link in page.links.iter() {
-                file.write_all(format!("  --> {}\n", link).as_bytes())
-                    .await?;
+    write_atomically(file, async |file| {
+        for page in all_pages.pages.iter() {
+            file.write_all(format!("{}\n", page.url).as_bytes()).await?;
+            if !hide_links {
+                for link in page.links.iter() {
+                    file.write_all(format!("  --> {}\n", link).as_bytes())
+                        .await?;
+                }
             }
         }
+        Ok(())
+    })
+    .await
+}
+
+/// Each broken page with its status, followed by the pages that link to it.
+fn broken_links(all_pages: &AllPages) -> String {
+    let mut report: Vec<_> = all_pages.broken_links_report().into_iter().collect();
+    report.sort();
+
+    let mut output = String::new();
+    for (url, (status_code, referrers)) in report {
+        output.push_str(&format!("{} ({})\n", url, status_code.as_u16()));
+        for referrer in referrers {
+            output.push_str(&format!("  <-- {}\n", referrer));
+        }
     }
+    output
+}
+
+/// Every URL discovered, one per line.
+fn all_urls(all_pages: &AllPages) -> String {
+    all_pages
+        .all_urls()
+        .iter()
+        .map(|url| format!("{}\n", url))
+        .collect()
+}
+
+/// The URLs of a seed list read from `source`, warning about each line that was not a URL.
+fn valid_seeds(seeds: SeedList, source: &str, colors: Colors) -> Vec<Url> {
+    for (line, content) in seeds.invalid.iter() {
+        eprintln!(
+            "{} {} line {}: {}",
+            "Skipping invalid seed URL on".style(colors.yellow()),
+            source,
+            line,
+            content
+        );
+    }
+    seeds.urls
+}
+
+/// A [CrawlReport](spider_crab::CrawlReport) of the crawl as pretty-printed JSON.
+fn report(all_pages: &AllPages) -> anyhow::Result<String> {
+    let report = all_pages.report(&ReportOptions::default());
+    Ok(format!("{}\n", serde_json::to_string_pretty(&report)?))
+}
+
+/// Run the `robots-check` subcommand.
+async fn robots_check(args: RobotsCheckArgs) -> anyhow::Result<()> {
+    let robots_txt = robots::load_robots(&args.robots).await?;
+    let agent = args.agent.as_deref().unwrap_or(APP_USER_AGENT);
+
+    println!("{}", robots::check_robots(&robots_txt, &args.url, agent)?);
     Ok(())
 }
 
@@ -143,6 +225,33 @@ fn configure_tracing(otl_endpoint: Url) -> anyhow::Result<TracerProvider> {
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
+    if let Some(Command::RobotsCheck(args)) = cli.command {
+        return robots_check(args).await;
+    }
+    let stderr_colors = Colors::new(cli.color, std::io::stderr().is_terminal());
+    let mut seeds = Vec::new();
+    for seed in cli.url.iter() {
+        match seed {
+            Seeds::Url(url) => seeds.push(url.clone()),
+            Seeds::Stdin => seeds.extend(valid_seeds(
+                cli::read_seeds(std::io::stdin().lock())?,
+                "stdin",
+                stderr_colors,
+            )),
+        }
+    }
+    if let Some(seeds_file) = &cli.seeds_file {
+        let file = std::io::BufReader::new(std::fs::File::open(seeds_file)?);
+        seeds.extend(valid_seeds(
+            cli::read_seeds(file)?,
+            &seeds_file.display().to_string(),
+            stderr_colors,
+        ));
+    }
+    let Some(root_url) = seeds.first().cloned() else {
+        anyhow::bail!("No valid seed URLs were given");
+    };
+
     let trace_provider = cli.otl_endpoint.map(configure_tracing);
     let trace_provider = if let Some(provider) = trace_provider {
         Some(provider?)
@@ -150,73 +259,202 @@ async fn main() -> anyhow::Result<()> {
         None
     };
 
-    let client = crawler_client(5, Duration::from_secs(5), cli.max_concurrent_connections)?;
+    let scope_mode = match &cli.public_suffix_list {
+        Some(public_suffix_list) => {
+            let list = tokio::fs::read_to_string(public_suffix_list)
+                .await?
+                .parse()?;
+            ScopeMode::RegistrableDomain(Arc::new(list))
+        }
+        None => ScopeMode::default(),
+    };
+
+    if cli.accept_invalid_hostnames {
+        warn!("Accepting TLS certificates that do not match the host name");
+    }
+    let client = crawler_client(
+        http_client(
+            Duration::from_secs(cli.connect_timeout),
+            seeds_redirect_policy(scope_mode.clone(), seeds.clone(), cli.max_redirects),
+            cli.login_url.is_some(),
+            cli.accept_invalid_hostnames,
+            cli.max_concurrent_dns,
+        )?,
+        5,
+        Duration::from_secs(5),
+        cli.max_concurrent_connections,
+        cli.max_concurrent_per_host,
+        cli.slow_start.map(Duration::from_secs),
+    );
+    if let Some(login_url) = &cli.login_url {
+        login(&client, login_url, &cli.login_field).await?;
+    }
     let reqwest_visitor = ClientWithMiddlewareVisitor::new(client);
 
     // Build a crawler
-    let mut crawler_builder = CrawlerBuilder::new(reqwest_visitor);
-    if let Ok(robots_txt) = get_robots(&cli.url).await {
-        if !cli.ignore_robots {
-            crawler_builder = crawler_builder.with_robot(&robots_txt, APP_USER_AGENT)?;
+    let mut crawler_builder = CrawlerBuilder::new(reqwest_visitor).with_scope_mode(scope_mode);
+    if !cli.ignore_robots {
+        // Each seed host's URLs are checked against that host's own robots.txt
+        let mut fetched_hosts = HashSet::new();
+        let mut host_robots = Vec::new();
+        for seed in seeds.iter() {
+            let Some(host) = seed.host_str().filter(|host| fetched_hosts.insert(*host)) else {
+                continue;
+            };
+            if let Ok(robots_txt) = robots::get_robots(seed).await {
+                host_robots.push((host, robots_txt));
+            }
+        }
+        if !host_robots.is_empty() {
+            let host_robots = host_robots
+                .iter()
+                .map(|(host, robots_txt)| (*host, robots_txt.as_str()));
+            crawler_builder = crawler_builder
+                .with_host_robots(host_robots, APP_USER_AGENT)?
+                .with_ignore_robots_for_seed_host(cli.ignore_robots_for_seed_host);
         }
     }
     if let Some(max_pages) = cli.max_pages {
         crawler_builder = crawler_builder.with_max_pages(max_pages);
     }
+    if let Some(max_bytes) = cli.max_bytes {
+        crawler_builder = crawler_builder.with_max_bytes(max_bytes);
+    }
+    if let Some(strategy) = cli.strategy {
+        let strategy = match strategy {
+            Strategy::BreadthFirst => CrawlStrategy::BreadthFirst,
+            Strategy::DepthFirst => CrawlStrategy::DepthFirst,
+        };
+        crawler_builder = crawler_builder
+            .with_strategy(strategy)
+            .with_max_in_flight(cli.max_concurrent_connections);
+    }
+    if !cli.path_prefix.is_empty() {
+        let prefixes: Vec<&str> = cli.path_prefix.iter().map(String::as_str).collect();
+        crawler_builder = crawler_builder.with_allowed_path_prefixes(&prefixes);
+    }
     if let Some(max_time_seconds) = cli.max_time {
         crawler_builder = crawler_builder.with_max_time(max_time_seconds);
     }
+    // The broken links report, URL list and crawl report are built from links, so they are kept even when hidden
+    let needs_links = match cli.format {
+        OutputFormat::Text => !cli.hide_links,
+        OutputFormat::Sitemap => false,
+        OutputFormat::BrokenLinks | OutputFormat::Urls | OutputFormat::Report => true,
+    };
+    if !needs_links {
+        crawler_builder = crawler_builder.with_retain_links(false);
+    }
+    if cli.strict_politeness {
+        crawler_builder = crawler_builder.with_strict_politeness();
+    }
 
+    info!(config = ?crawler_builder.config_summary(), "Crawler configuration");
     let crawler = crawler_builder.build();
 
     // Subscribe to the crawler's broadcast channel. This will allow us to receive progress updates
-    let mut rx = crawler.subscribe();
-    let url_string = cli.url.clone();
+    let rx = crawler.subscribe();
+    let url_string = root_url.clone();
+    // Progress is drawn to stderr, so its colours depend on whether stderr is a terminal
+    let colors = Colors::new(cli.color, std::io::stdout().is_terminal());
     // Spawn a task to manage progress bar updates
-    let progress_handle = tokio::task::spawn_blocking(move || {
-        let start = Instant::now();
-        let mut count = 0;
-
-        let multi_progress = MultiProgress::new();
-        let header = multi_progress.add(ProgressBar::new_spinner());
-        let current_url = multi_progress.add(ProgressBar::new_spinner());
-        let visit_stats = multi_progress.add(ProgressBar::new_spinner());
-
-        header.enable_steady_tick(Duration::from_millis(120));
-        current_url.enable_steady_tick(Duration::from_millis(120));
-        visit_stats.enable_steady_tick(Duration::from_millis(120));
-
-        header.set_message(format!("Crawling: {}", url_string.as_str().green()));
-
-        while let Ok(page) = rx.blocking_recv() {
-            count += 1;
-            let duration = start.elapsed();
-            let seconds = duration.as_secs() % 60;
-            let minutes = (duration.as_secs() / 60) % 60;
-            visit_stats.set_message(format!(
-                "  Visited {} pages in {:0>2}:{:0>2}",
-                count.cyan(),
-                minutes.to_string().cyan(),
-                seconds.to_string().cyan()
-            ));
-            current_url.set_message(format!("  Current url: {}", page.url.as_str().green()));
+    let progress_handle =
+        tokio::task::spawn_blocking(move || progress::show_progress(rx, url_string, stderr_colors));
+
+    let live_stats = crawler.live_stats();
+    let crawl_start = Instant::now();
+    let crawl = crawler.crawl_seeds(seeds);
+    let res = match &cli.status_file {
+        Some(status_file) => {
+            let interval = Duration::from_secs(cli.status_interval);
+            status::with_status_file(crawl, status_file, &live_stats, interval).await?
         }
-        header.finish_and_clear();
-        current_url.finish_and_clear();
-        visit_stats.finish_and_clear();
-    });
-
-    let res = crawler.crawl(cli.url).await;
+        None => crawl.await,
+    };
+    let elapsed = crawl_start.elapsed();
     progress_handle.await?;
 
-    match &cli.output {
-        Some(path) => write_links_to_file(&res, path, cli.hide_links).await?,
-        None => print_links(&res, cli.hide_links),
+    match (cli.format, &cli.output) {
+        (OutputFormat::Text, Some(path)) => write_links_to_file(&res, path, cli.hide_links).await?,
+        (OutputFormat::Text, None) => {
+            write_links(&mut std::io::stdout().lock(), &res, cli.hide_links, colors)?
+        }
+        (OutputFormat::Sitemap, Some(path)) => {
+            write_file_atomically(path, res.to_sitemap_xml()).await?
+        }
+        (OutputFormat::Sitemap, None) => print!("{}", res.to_sitemap_xml()),
+        (OutputFormat::BrokenLinks, Some(path)) => {
+            write_file_atomically(path, broken_links(&res)).await?
+        }
+        (OutputFormat::BrokenLinks, None) => print!("{}", broken_links(&res)),
+        (OutputFormat::Urls, Some(path)) => write_file_atomically(path, all_urls(&res)).await?,
+        (OutputFormat::Urls, None) => print!("{}", all_urls(&res)),
+        (OutputFormat::Report, Some(path)) => write_file_atomically(path, report(&res)?).await?,
+        (OutputFormat::Report, None) => print!("{}", report(&res)?),
     };
 
+    if cli.summary {
+        println!("\n{}", summary::CrawlSummary::new(&res, elapsed, colors));
+    }
+
     // Shutdown tracing
     if let Some(provider_builder) = trace_provider {
         provider_builder.shutdown()?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::HeaderMap;
+    use reqwest::{redirect, StatusCode};
+    use spider_crab::{parse_links, AllPages, PageContent};
+    use url::Url;
+
+    use super::{crawler_client, http_client, write_links};
+    use crate::{cli::ColorChoice, color::Colors};
+
+    #[test]
+    fn test_client_accepting_invalid_hostnames() -> anyhow::Result<()> {
+        let client = http_client(
+            Duration::from_secs(10),
+            redirect::Policy::none(),
+            false,
+            true,
+            Some(10),
+        )?;
+        crawler_client(client, 5, Duration::from_secs(5), 10, None, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_links_color() -> anyhow::Result<()> {
+        let all_pages = AllPages {
+            pages: vec![parse_links(&PageContent {
+                url: Url::parse("https://monzo.com/")?,
+                status_code: StatusCode::OK,
+                content: r#"<a href="/about"></a>"#.to_string(),
+                content_type: None,
+                headers: HeaderMap::new(),
+                http_version: None,
+            })],
+            ..Default::default()
+        };
+        let write = |choice: ColorChoice| -> anyhow::Result<String> {
+            let mut out = Vec::new();
+            write_links(&mut out, &all_pages, false, Colors::new(choice, true))?;
+            Ok(String::from_utf8(out)?)
+        };
+
+        assert_eq!(
+            write(ColorChoice::Never)?,
+            "https://monzo.com/\n  --> https://monzo.com/about\n"
+        );
+        assert!(write(ColorChoice::Always)?.contains('\x1b'));
+        assert!(write(ColorChoice::Auto)?.contains('\x1b'));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+
+use crate::parser::AllPages;
+
+/// A summary of every page visited by a crawl, with the metadata needed to check an archive of it
+/// later. Serialize it with [serde], such as with `serde_json::to_string`.
+/// See [AllPages::to_manifest].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrawlManifest {
+    /// One entry for each visited page, in URL order.
+    pub pages: Vec<ManifestEntry>,
+}
+
+/// A visited page in a [CrawlManifest].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    pub url: String,
+    pub status: u16,
+    /// The [content hash](crate::Page::content_hash) of the page, as 16 hex digits.
+    pub content_hash: String,
+    /// The [size](crate::Page::content_length) of the page's body in bytes.
+    pub size: usize,
+    /// When the page was fetched, in seconds since the Unix epoch.
+    pub fetched_at: u64,
+}
+
+impl AllPages {
+    /// A [CrawlManifest] of every visited page, including its content hash, size and fetch time.
+    pub fn to_manifest(&self) -> CrawlManifest {
+        let mut pages: Vec<ManifestEntry> = self
+            .pages
+            .iter()
+            .map(|page| ManifestEntry {
+                url: page.url.to_string(),
+                status: page.status_code.as_u16(),
+                content_hash: format!("{:016x}", page.content_hash),
+                size: page.content_length,
+                fetched_at: page
+                    .fetched_at
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            })
+            .collect();
+        pages.sort_by(|a, b| a.url.cmp(&b.url));
+        CrawlManifest { pages }
+    }
+}
@@ -0,0 +1,57 @@
+use std::{fmt::Debug, sync::Mutex};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// A source of the process's memory usage for the [Crawler](crate::Crawler)'s memory limit, so
+/// the limit can be tested without allocating. See
+/// [CrawlerBuilder::with_memory_limit](crate::CrawlerBuilder::with_memory_limit).
+pub trait MemoryReader: Debug + Send + Sync {
+    /// The resident set size of the process in bytes, or `None` if it could not be read.
+    fn resident_bytes(&self) -> Option<u64>;
+}
+
+/// The default [MemoryReader], reading the current process's memory usage from the operating system.
+#[derive(Debug)]
+pub struct ProcessMemoryReader {
+    system: Mutex<System>,
+    pid: Option<Pid>,
+}
+
+impl ProcessMemoryReader {
+    pub fn new() -> Self {
+        Self {
+            system: Mutex::new(System::new()),
+            pid: sysinfo::get_current_pid().ok(),
+        }
+    }
+}
+
+impl Default for ProcessMemoryReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryReader for ProcessMemoryReader {
+    fn resident_bytes(&self) -> Option<u64> {
+        let pid = self.pid?;
+        let mut system = self.system.lock().expect("Could not acquire lock");
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            false,
+            ProcessRefreshKind::nothing().with_memory(),
+        );
+        system.process(pid).map(|process| process.memory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryReader, ProcessMemoryReader};
+
+    #[test]
+    fn test_process_memory_reader() {
+        let resident_bytes = ProcessMemoryReader::new().resident_bytes();
+        assert!(resident_bytes.is_some_and(|bytes| bytes > 0));
+    }
+}
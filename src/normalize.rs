@@ -0,0 +1,281 @@
+use serde::Serialize;
+use url::Url;
+
+/// How the [Crawler](crate::crawler::Crawler) treats trailing slashes when deciding if two URLs are the same page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum Normalize {
+    /// Add a trailing slash, so `/page` is treated as `/page/`.
+    /// Paths whose last segment looks like a file, such as `/page.html`, are left alone.
+    Add,
+    /// Remove a trailing slash, so `/page/` is treated as `/page`. The root path `/` is left alone.
+    Remove,
+    /// Treat `/page` and `/page/` as different pages.
+    #[default]
+    Off,
+}
+
+/// Apply a trailing slash [Normalize] policy to a URL.
+pub(crate) fn normalize_trailing_slash(url: &mut Url, normalize: Normalize) {
+    if url.cannot_be_a_base() {
+        return;
+    }
+
+    let path = url.path();
+    match normalize {
+        Normalize::Add => {
+            let last_segment = path.rsplit('/').next().unwrap_or_default();
+            if !last_segment.is_empty() && !last_segment.contains('.') {
+                let path = format!("{}/", path);
+                url.set_path(&path);
+            }
+        }
+        Normalize::Remove => {
+            if path.len() > 1 && path.ends_with('/') {
+                let path = path.trim_end_matches('/').to_string();
+                url.set_path(if path.is_empty() { "/" } else { &path });
+            }
+        }
+        Normalize::Off => {}
+    }
+}
+
+/// Lowercase a URL's host and put its path and query's percent-encoding in canonical form:
+/// unreserved characters are decoded, so `%7E` becomes `~`, and other escapes use uppercase hex.
+pub(crate) fn normalize_encoding(url: &mut Url) {
+    if let Some(host) = url
+        .host_str()
+        .filter(|host| host.bytes().any(|byte| byte.is_ascii_uppercase()))
+    {
+        let host = host.to_ascii_lowercase();
+        let _ = url.set_host(Some(&host)); // Lowercasing a valid host leaves it valid
+    }
+
+    if !url.cannot_be_a_base() {
+        let path = canonical_percent_encoding(url.path());
+        if path != url.path() {
+            url.set_path(&path);
+        }
+    }
+    if let Some(query) = url.query() {
+        let query = canonical_percent_encoding(query);
+        if Some(query.as_str()) != url.query() {
+            url.set_query(Some(&query));
+        }
+    }
+}
+
+/// Sort the parameters of a URL's query by name, so `?b=2&a=1` becomes `?a=1&b=2`. Parameters
+/// sharing a name keep their order, as it may be significant. Parameters are not re-encoded.
+pub(crate) fn sort_query(url: &mut Url) {
+    if let Some(query) = url.query() {
+        let mut params: Vec<&str> = query.split('&').collect();
+        params.sort_by_key(|param| param.split('=').next().unwrap_or_default());
+        let sorted = params.join("&");
+        url.set_query(Some(&sorted));
+    }
+}
+
+/// Remove a trailing index file name from a URL's path, so `/dir/index.html` becomes `/dir/` if
+/// `index.html` is one of `index_files`. The query and fragment are left alone.
+pub(crate) fn collapse_index_file(url: &mut Url, index_files: &[String]) {
+    if url.cannot_be_a_base() {
+        return;
+    }
+    let path = url.path();
+    let Some((directory, file)) = path.rsplit_once('/') else {
+        return;
+    };
+    if index_files.iter().any(|index_file| index_file == file) {
+        let path = format!("{}/", directory);
+        url.set_path(&path);
+    }
+}
+
+/// Decode percent-encoded unreserved characters (RFC 3986 section 2.3) and uppercase the hex of
+/// every other escape.
+fn canonical_percent_encoding(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut canonical = String::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) => {
+                canonical.push(char::from(byte));
+                i += 3;
+            }
+            Some(byte) => {
+                canonical.push_str(&format!("%{:02X}", byte));
+                i += 3;
+            }
+            None => {
+                // Escapes are ASCII, so `i` is always on a character boundary here
+                let c = encoded[i..].chars().next().unwrap_or_default();
+                canonical.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    canonical
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::{
+        collapse_index_file, normalize_encoding, normalize_trailing_slash, sort_query, Normalize,
+    };
+
+    fn encoding_normalized(url: &str) -> anyhow::Result<String> {
+        let mut url = Url::parse(url)?;
+        normalize_encoding(&mut url);
+        Ok(url.to_string())
+    }
+
+    #[test]
+    fn test_sort_query() -> anyhow::Result<()> {
+        let sorted = |url: &str| -> anyhow::Result<String> {
+            let mut url = Url::parse(url)?;
+            sort_query(&mut url);
+            Ok(url.to_string())
+        };
+        assert_eq!(
+            sorted("https://monzo.com/?b=2&a=1")?,
+            "https://monzo.com/?a=1&b=2"
+        );
+        assert_eq!(
+            sorted("https://monzo.com/?tag=b&page=2&tag=a&q")?,
+            "https://monzo.com/?page=2&q&tag=b&tag=a"
+        );
+        assert_eq!(sorted("https://monzo.com/")?, "https://monzo.com/");
+        Ok(())
+    }
+
+    #[test]
+    fn test_collapse_index_file() -> anyhow::Result<()> {
+        let collapsed = |url: &str| -> anyhow::Result<String> {
+            let mut url = Url::parse(url)?;
+            collapse_index_file(&mut url, &["index.html".to_string()]);
+            Ok(url.to_string())
+        };
+        assert_eq!(
+            collapsed("https://monzo.com/dir/index.html?a=1")?,
+            "https://monzo.com/dir/?a=1"
+        );
+        assert_eq!(
+            collapsed("https://monzo.com/index.html")?,
+            "https://monzo.com/"
+        );
+        assert_eq!(
+            collapsed("https://monzo.com/dir/index.htm")?,
+            "https://monzo.com/dir/index.htm"
+        );
+        assert_eq!(
+            collapsed("https://monzo.com/index.html/page")?,
+            "https://monzo.com/index.html/page"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_encoding() -> anyhow::Result<()> {
+        assert_eq!(
+            encoding_normalized("https://monzo.com/%7Euser")?,
+            encoding_normalized("https://monzo.com/~user")?
+        );
+        assert_eq!(
+            encoding_normalized("https://Example.COM/")?,
+            encoding_normalized("https://example.com/")?
+        );
+        assert_eq!(
+            encoding_normalized("https://monzo.com/%41%2d%5f?q=%7e%2f")?,
+            "https://monzo.com/A-_?q=~%2F"
+        );
+        assert_eq!(
+            encoding_normalized("https://monzo.com/caf%c3%a9%20menu")?,
+            "https://monzo.com/caf%C3%A9%20menu"
+        );
+        assert_eq!(
+            encoding_normalized("https://monzo.com/100%/%zz")?,
+            "https://monzo.com/100%/%zz"
+        );
+        assert_eq!(
+            encoding_normalized("custom://Monzo.COM/%7e")?,
+            "custom://monzo.com/~"
+        );
+        Ok(())
+    }
+
+    fn normalized(url: &str, normalize: Normalize) -> anyhow::Result<String> {
+        let mut url = Url::parse(url)?;
+        normalize_trailing_slash(&mut url, normalize);
+        Ok(url.to_string())
+    }
+
+    #[test]
+    fn test_trailing_slash_add() -> anyhow::Result<()> {
+        let normalize = Normalize::Add;
+        assert_eq!(
+            normalized("https://monzo.com/page", normalize)?,
+            "https://monzo.com/page/"
+        );
+        assert_eq!(
+            normalized("https://monzo.com/page/", normalize)?,
+            "https://monzo.com/page/"
+        );
+        assert_eq!(
+            normalized("https://monzo.com/page?q=1", normalize)?,
+            "https://monzo.com/page/?q=1"
+        );
+        assert_eq!(
+            normalized("https://monzo.com/page.html", normalize)?,
+            "https://monzo.com/page.html"
+        );
+        assert_eq!(
+            normalized("https://monzo.com", normalize)?,
+            "https://monzo.com/"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_trailing_slash_remove() -> anyhow::Result<()> {
+        let normalize = Normalize::Remove;
+        assert_eq!(
+            normalized("https://monzo.com/page/", normalize)?,
+            "https://monzo.com/page"
+        );
+        assert_eq!(
+            normalized("https://monzo.com/page//", normalize)?,
+            "https://monzo.com/page"
+        );
+        assert_eq!(
+            normalized("https://monzo.com/page", normalize)?,
+            "https://monzo.com/page"
+        );
+        assert_eq!(
+            normalized("https://monzo.com/", normalize)?,
+            "https://monzo.com/"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_trailing_slash_off() -> anyhow::Result<()> {
+        let normalize = Normalize::Off;
+        assert_eq!(
+            normalized("https://monzo.com/page/", normalize)?,
+            "https://monzo.com/page/"
+        );
+        assert_eq!(
+            normalized("https://monzo.com/page", normalize)?,
+            "https://monzo.com/page"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use async_compression::tokio::write::GzipEncoder;
+use tokio::{
+    fs::File,
+    io::{AsyncWrite, AsyncWriteExt},
+};
+
+/// Whether output to `path` is gzipped, because its name ends in `.gz`.
+fn is_gzip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"))
+}
+
+/// Write a file by calling `write` with a temporary file in the same directory, then renaming it
+/// over `path` once `write` succeeds. If writing fails or the process is killed part way through,
+/// `path` is left as it was rather than holding a partial file.
+///
+/// If `path` ends in `.gz`, everything `write` writes is gzipped as it is written.
+pub async fn write_atomically<F>(path: &Path, write: F) -> anyhow::Result<()>
+where
+    F: AsyncFnOnce(&mut (dyn AsyncWrite + Unpin + Send)) -> anyhow::Result<()>,
+{
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Output path is not a file: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = async {
+        let mut file = File::create(&temp_path).await?;
+        if is_gzip(path) {
+            let mut encoder = GzipEncoder::new(file);
+            write(&mut encoder).await?;
+            encoder.shutdown().await?;
+            file = encoder.into_inner();
+        } else {
+            write(&mut file).await?;
+            file.flush().await?;
+        }
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&temp_path, path).await?;
+        Ok(())
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await; // The file may never have been created
+    }
+    result
+}
+
+/// Write `contents` to `path` with [write_atomically].
+pub async fn write_file_atomically(path: &Path, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    write_atomically(path, async |file| {
+        file.write_all(contents.as_ref()).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use async_compression::tokio::write::GzipDecoder;
+    use spider_crab::{AllPages, ReportOptions};
+    use tokio::io::AsyncWriteExt;
+
+    use super::{write_atomically, write_file_atomically};
+
+    /// An empty directory for a test to write to.
+    fn test_dir(name: &str) -> anyhow::Result<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("spider_crab-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    #[tokio::test]
+    async fn test_write_atomically() -> anyhow::Result<()> {
+        let dir = test_dir("write-atomically")?;
+        let path = dir.join("links.txt");
+
+        write_file_atomically(&path, "https://monzo.com/\n").await?;
+
+        assert_eq!(std::fs::read_to_string(&path)?, "https://monzo.com/\n");
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_gzipped() -> anyhow::Result<()> {
+        let dir = test_dir("write-gzipped")?;
+        let path = dir.join("report.json.gz");
+        let report = serde_json::to_string(&AllPages::default().report(&ReportOptions::default()))?;
+
+        write_file_atomically(&path, &report).await?;
+
+        // The file is gzipped, and decompresses to the report
+        let compressed = std::fs::read(&path)?;
+        assert_eq!(compressed[..2], [0x1f, 0x8b]);
+        let mut decoder = GzipDecoder::new(Vec::new());
+        decoder.write_all(&compressed).await?;
+        decoder.shutdown().await?;
+        let json: serde_json::Value = serde_json::from_slice(&decoder.into_inner())?;
+        assert_eq!(json["stats"]["pages"], 0);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_write_leaves_no_partial_file() -> anyhow::Result<()> {
+        let dir = test_dir("interrupted-write")?;
+        let path = dir.join("links.txt");
+
+        // Given: A write that fails after writing part of the output
+        let result = write_atomically(&path, async |file| {
+            file.write_all(b"https://monzo.com/\n").await?;
+            anyhow::bail!("Interrupted");
+        })
+        .await;
+
+        // Then: The write fails without creating the target or leaving the temporary file behind
+        assert!(result.is_err());
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
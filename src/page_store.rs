@@ -0,0 +1,157 @@
+use crate::parser::Page;
+
+/// A destination for pages as the [Crawler](crate::Crawler) completes them, such as a database.
+/// Set with [CrawlerBuilder::with_page_store](crate::CrawlerBuilder::with_page_store).
+///
+/// Errors are logged and do not stop the crawl.
+#[async_trait::async_trait]
+pub trait PageStore: Send + Sync {
+    /// Store a completed page. Its links are included even if the crawler does not retain them.
+    async fn store(&self, page: &Page) -> anyhow::Result<()>;
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqlitePageStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+
+    use rusqlite::{params, Connection};
+
+    use super::PageStore;
+    use crate::parser::Page;
+
+    /// A [PageStore] writing pages to a `pages` table and the links between them to a `links` table
+    /// of a SQLite database.
+    ///
+    /// ```sql
+    /// CREATE TABLE pages (url TEXT PRIMARY KEY, status_code INTEGER, content_type TEXT, parent TEXT);
+    /// CREATE TABLE links (source TEXT, target TEXT, external INTEGER);
+    /// ```
+    ///
+    /// Writes are made one at a time on Tokio's blocking thread pool, so they do not stall the
+    /// crawler's other tasks.
+    #[derive(Debug)]
+    pub struct SqlitePageStore {
+        connection: Arc<Mutex<Connection>>,
+    }
+
+    impl SqlitePageStore {
+        /// Open or create a database file, creating the tables if they do not exist.
+        pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+            Self::new(Connection::open(path)?)
+        }
+
+        /// Create a database held in memory.
+        pub fn open_in_memory() -> anyhow::Result<Self> {
+            Self::new(Connection::open_in_memory()?)
+        }
+
+        fn new(connection: Connection) -> anyhow::Result<Self> {
+            connection.execute_batch(
+                "CREATE TABLE IF NOT EXISTS pages (
+                    url TEXT PRIMARY KEY,
+                    status_code INTEGER NOT NULL,
+                    content_type TEXT,
+                    parent TEXT
+                );
+                CREATE TABLE IF NOT EXISTS links (
+                    source TEXT NOT NULL,
+                    target TEXT NOT NULL,
+                    external INTEGER NOT NULL
+                );",
+            )?;
+            Ok(Self {
+                connection: Arc::new(Mutex::new(connection)),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl PageStore for SqlitePageStore {
+        async fn store(&self, page: &Page) -> anyhow::Result<()> {
+            let connection = self.connection.clone();
+            let url = page.url.to_string();
+            let status_code = page.status_code.as_u16();
+            let content_type = page
+                .content_type
+                .as_ref()
+                .and_then(|content_type| content_type.to_str().ok())
+                .map(str::to_string);
+            let parent = page.parent.as_ref().map(|parent| parent.to_string());
+            let links: Vec<(String, bool)> = page
+                .links
+                .iter()
+                .map(|link| (link.to_string(), false))
+                .chain(
+                    page.external_links
+                        .iter()
+                        .map(|link| (link.to_string(), true)),
+                )
+                .collect();
+
+            tokio::task::spawn_blocking(move || {
+                let mut connection = connection
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("SQLite connection lock was poisoned"))?;
+                let transaction = connection.transaction()?;
+                transaction.execute(
+                    "INSERT OR REPLACE INTO pages (url, status_code, content_type, parent) VALUES (?1, ?2, ?3, ?4)",
+                    params![url, status_code, content_type, parent],
+                )?;
+                {
+                    let mut insert_link = transaction.prepare(
+                        "INSERT INTO links (source, target, external) VALUES (?1, ?2, ?3)",
+                    )?;
+                    for (link, external) in links {
+                        insert_link.execute(params![url, link, external])?;
+                    }
+                }
+                transaction.commit()?;
+                Ok(())
+            })
+            .await?
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::{convert::Infallible, sync::Arc};
+
+        use url::Url;
+
+        use super::SqlitePageStore;
+        use crate::{CrawlerBuilder, TowerVisitor};
+
+        #[tokio::test]
+        async fn test_sqlite_page_store() -> anyhow::Result<()> {
+            let service = tower::service_fn(|request: http::Request<String>| async move {
+                let body = match request.uri().path() {
+                    "/" => r#"<a href="/about"></a> <a href="https://github.com/monzo"></a>"#,
+                    "/about" => r#"<a href="/"></a> <a href="/careers"></a>"#,
+                    _ => "",
+                };
+                Ok::<_, Infallible>(http::Response::new(body.to_string()))
+            });
+            let store = Arc::new(SqlitePageStore::open_in_memory()?);
+
+            CrawlerBuilder::new(TowerVisitor::new(service))
+                .with_page_store(store.clone())
+                .build()
+                .crawl(Url::parse("http://localhost")?)
+                .await;
+
+            let connection = store.connection.lock().expect("Could not acquire lock");
+            let count = |sql: &str| connection.query_row(sql, [], |row| row.get::<_, i64>(0));
+            assert_eq!(count("SELECT COUNT(*) FROM pages")?, 3);
+            assert_eq!(count("SELECT COUNT(*) FROM links")?, 4);
+            assert_eq!(count("SELECT COUNT(*) FROM links WHERE external")?, 1);
+
+            Ok(())
+        }
+    }
+}
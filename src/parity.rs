@@ -0,0 +1,63 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::parser::AllPages;
+
+/// How the pages of two crawls of different hosts compare, such as a staging site and the live
+/// site it will replace. Pages are matched by path and query, ignoring the scheme and host.
+/// See [AllPages::parity_report].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParityReport {
+    /// Paths visited by the first crawl but not the second.
+    pub only_in_a: BTreeSet<String>,
+    /// Paths visited by the second crawl but not the first.
+    pub only_in_b: BTreeSet<String>,
+    /// Paths visited by both crawls that responded with different statuses, as `(a, b)`.
+    pub status_differences: BTreeMap<String, (StatusCode, StatusCode)>,
+}
+
+/// The path and query of a URL, which identify the same page on different hosts.
+fn path_key(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+impl AllPages {
+    /// Compare this crawl with a crawl of another host by path, reporting pages only one of them
+    /// visited and pages whose statuses differ. If a crawl visited the same path on several hosts,
+    /// the first page visited is used.
+    pub fn parity_report(&self, other: &AllPages) -> ParityReport {
+        let statuses = |all_pages: &AllPages| {
+            let mut statuses: HashMap<String, StatusCode> = HashMap::new();
+            for page in all_pages.pages.iter() {
+                statuses
+                    .entry(path_key(&page.url))
+                    .or_insert(page.status_code);
+            }
+            statuses
+        };
+        let a = statuses(self);
+        let b = statuses(other);
+
+        let mut report = ParityReport::default();
+        for (path, &status_a) in a.iter() {
+            match b.get(path) {
+                None => {
+                    report.only_in_a.insert(path.clone());
+                }
+                Some(&status_b) if status_b != status_a => {
+                    report
+                        .status_differences
+                        .insert(path.clone(), (status_a, status_b));
+                }
+                Some(_) => {}
+            }
+        }
+        report.only_in_b = b.into_keys().filter(|path| !a.contains_key(path)).collect();
+        report
+    }
+}
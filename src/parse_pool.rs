@@ -0,0 +1,59 @@
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tokio::sync::oneshot;
+use tracing::error;
+
+/// A dedicated pool of threads for parsing pages, isolated from Tokio's shared blocking pool.
+#[derive(Debug)]
+pub(crate) struct ParsePool {
+    pool: ThreadPool,
+}
+
+impl ParsePool {
+    pub(crate) fn new(threads: usize) -> anyhow::Result<Self> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("spider-crab-parse-{}", index))
+            .panic_handler(|_| error!("Parse worker panicked"))
+            .build()?;
+        Ok(Self { pool })
+    }
+
+    /// The number of threads in the pool.
+    pub(crate) fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Run `f` on the pool and wait for its result.
+    ///
+    /// # Panics
+    /// If `f` panics.
+    pub(crate) async fn run<T, F>(&self, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.pool.spawn(move || {
+            let _ = tx.send(f()); // Ignore errors as we don't care if the caller has gone
+        });
+        rx.await.expect("Parse worker panicked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParsePool;
+
+    #[tokio::test]
+    async fn test_runs_on_pool() -> anyhow::Result<()> {
+        let pool = ParsePool::new(2)?;
+        assert_eq!(pool.threads(), 2);
+
+        let thread_name = pool
+            .run(|| std::thread::current().name().map(str::to_string))
+            .await;
+        assert!(thread_name.is_some_and(|name| name.starts_with("spider-crab-parse-")));
+
+        Ok(())
+    }
+}
@@ -1,68 +1,853 @@
-use std::{collections::HashSet, fmt::Debug};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Debug,
+    sync::Arc,
+    time::SystemTime,
+};
 
-use reqwest::StatusCode;
-use scraper::{Html, Selector};
+use http::{HeaderMap, HeaderValue, Version};
+use indexmap::IndexMap;
+use reqwest::{
+    header::{HeaderName, ETAG, LAST_MODIFIED, LINK, LOCATION},
+    StatusCode,
+};
+use scraper::{ElementRef, Html, Selector};
 use url::Url;
 
-use crate::crawler::PageContent;
+use crate::{
+    crawler::PageContent,
+    feed::{feed_entry_links, is_feed_content_type},
+    scope::{Scope, ScopeMode},
+};
 
 /// A page that has been visited by the [Crawler](crate::crawler::Crawler).
 #[derive(Debug, Clone)]
 pub struct Page {
     pub url: Url,
     pub status_code: StatusCode,
+    pub content_type: Option<HeaderValue>,
+    /// The `Last-Modified` response header, if it was a valid HTTP date.
+    pub last_modified: Option<SystemTime>,
+    /// The `ETag` response header.
+    pub etag: Option<String>,
+    /// The HTTP version the page was served over, if the visitor reported it.
+    pub http_version: Option<Version>,
+    /// When the response was received, or when the page was parsed if it was not visited by the
+    /// [Crawler](crate::crawler::Crawler). Pages reused from an [EtagCache](crate::EtagCache) are
+    /// stamped when the server confirmed they were not modified.
+    pub fetched_at: SystemTime,
+    /// A hash of the response body, to tell whether the page changed between crawls.
+    /// See [AllPages::content_manifest].
+    pub content_hash: u64,
+    /// The size of the response body in bytes, after decoding it to text.
+    pub content_length: usize,
+    /// Links to URLs inside the crawl scope. These are the links the crawler follows.
     pub links: HashSet<Url>,
+    /// Each unique link in document order, with the text of the first anchor that linked to it.
+    pub anchors: IndexMap<Url, String>,
+    /// Links from [links](Page::links) whose anchor was marked `rel="nofollow"`.
+    pub nofollow_links: HashSet<Url>,
+    /// Links, in or out of the crawl scope, whose anchor was marked `rel="sponsored"`, such as
+    /// adverts and paid placements.
+    pub sponsored_links: HashSet<Url>,
+    /// Links, in or out of the crawl scope, whose anchor was marked `rel="ugc"`, for user-generated
+    /// content such as comments and forum posts.
+    pub ugc_links: HashSet<Url>,
+    /// Links to URLs outside the crawl scope. These are never followed.
+    pub external_links: HashSet<Url>,
+    /// Targets of `<a download>` links, in or out of scope. These are files, so are never followed
+    /// and are not included in [links](Page::links) or [external_links](Page::external_links).
+    pub downloads: HashSet<Url>,
+    /// The raw `href` of each anchor that could not be resolved to a URL, in document order. These
+    /// are never followed, and usually point to typos or broken markup.
+    pub malformed_links: Vec<String>,
+    /// Language versions of the page declared with `<link rel="alternate" hreflang="...">`, as
+    /// `(language, URL)` pairs in document order. Alternates in the crawl scope are also added to
+    /// [links](Page::links) so they are crawled. Others are only recorded here.
+    pub alternates: Vec<(String, Url)>,
+    /// Directives found in the page's `<meta name="robots">` tags and `X-Robots-Tag` response headers.
+    pub meta_robots: MetaRobots,
+    /// Where the page redirected to, if the response was a redirect that was not followed.
+    pub redirect: Option<Redirect>,
+    /// Number of words of visible text on the page.
+    pub word_count: usize,
+    /// What the content was parsed as. Binary or severely malformed content is
+    /// [Unrecognized](ContentKind::Unrecognized), separating it from a page that simply has no links.
+    pub content_kind: ContentKind,
+    /// Whether parsing was abandoned because it took longer than the crawl's parse timeout.
+    /// The page is reported with no links, and its other parsed fields hold their defaults.
+    pub parse_timed_out: bool,
+    /// The page the crawler first discovered this page's URL on, or `None` for a seed.
+    pub parent: Option<Url>,
+    /// The fetched body of the page, if the crawler was asked to retain it.
+    /// See [CrawlerBuilder::with_retain_content](crate::CrawlerBuilder::with_retain_content).
+    pub content: Option<String>,
+}
+
+/// A redirect response that was not followed by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    /// The URL from the `Location` header.
+    pub location: Url,
+    /// Whether the location is outside the crawl scope.
+    pub external: bool,
+}
+
+/// Directives parsed from `<meta name="robots">` tags and `X-Robots-Tag` response headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetaRobots {
+    /// The page asked not to be indexed (`noindex` or `none`).
+    pub noindex: bool,
+    /// The page asked for its links not to be followed (`nofollow` or `none`).
+    pub nofollow: bool,
+}
+
+/// Why the [Crawler](crate::crawler::Crawler) failed to visit a URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureReason {
+    /// The visit was cancelled with [CrawlControl::abort_url](crate::crawler::CrawlControl::abort_url).
+    Cancelled,
+    /// The [SiteVisitor](crate::crawler::SiteVisitor) returned an error.
+    Visitor(String),
+    /// A chain of redirects looped or exceeded the redirect limit.
+    RedirectLoop { hops: usize },
+    /// The host name could not be resolved.
+    Dns { host: String },
+    /// The visit task panicked.
+    Panicked,
+    /// The seed URL was not visited because its scheme or port is not allowed, or robots.txt disallows it.
+    Disallowed,
+}
+
+/// What a [Page]'s content was parsed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentKind {
+    /// An HTML page.
+    Html,
+    /// A well-formed RSS or Atom feed.
+    Feed,
+    /// Content that looked like neither, such as binary data or a feed that is not well-formed XML.
+    Unrecognized,
+}
+
+/// Why discovered URLs were dropped without being visited or reported. See [AllPages::skipped].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkipReason {
+    /// The crawler was already tracking as many URLs as
+    /// [CrawlerBuilder::with_max_tracked_urls](crate::CrawlerBuilder::with_max_tracked_urls) allows.
+    FrontierFull,
+    /// The response was not HTML, so the visitor abandoned its download. See
+    /// [ClientWithMiddlewareVisitor::with_html_sniff](crate::ClientWithMiddlewareVisitor::with_html_sniff).
+    NotHtml,
+    /// Too many visits in a row to the URL's host had failed. See
+    /// [CrawlerBuilder::with_host_failure_threshold](crate::CrawlerBuilder::with_host_failure_threshold).
+    HostDown,
+    /// The crawl stopped at a limit, such as [max pages](crate::CrawlerBuilder::with_max_pages),
+    /// before the URL's visit was started or finished.
+    LimitReached,
+}
+
+/// A URL the [Crawler](crate::crawler::Crawler) tried but failed to visit.
+#[derive(Debug, Clone)]
+pub struct FailedVisit {
+    pub url: Url,
+    pub reason: FailureReason,
+}
+
+/// The result of checking a [download](Page::downloads) link with a `HEAD` request.
+/// See [CrawlerBuilder::with_download_check](crate::CrawlerBuilder::with_download_check).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadCheck {
+    /// The server responded, with the file's size if it sent a `Content-Length` header.
+    Responded {
+        status_code: StatusCode,
+        content_length: Option<u64>,
+    },
+    /// The request failed, such as when the host could not be reached.
+    Failed(String),
 }
 
 /// A collection of all [Page]s visited by the [Crawler](crate::crawler::Crawler).
-#[derive(Debug)]
-pub struct AllPages(pub Vec<Page>);
+#[derive(Debug, Default)]
+pub struct AllPages {
+    pub pages: Vec<Page>,
+    /// URLs that could not be visited.
+    pub failed: Vec<FailedVisit>,
+    /// URLs that were treated as another URL, mapped to the canonical URL they were crawled as.
+    /// Recorded when normalization rewrites a URL or a followed redirect lands somewhere else.
+    pub aliases: HashMap<Url, Url>,
+    /// The number of discovered URLs dropped without being visited or reported, for each reason.
+    pub skipped: HashMap<SkipReason, usize>,
+    /// The number of visited pages and probed links with each MIME type, if the crawler probed
+    /// links it did not visit (see [CrawlerBuilder::with_content_type_probe](crate::CrawlerBuilder::with_content_type_probe)).
+    pub content_type_histogram: BTreeMap<String, usize>,
+    /// The result of checking each download link found on visited pages, if the crawler checked
+    /// them (see [CrawlerBuilder::with_download_check](crate::CrawlerBuilder::with_download_check)).
+    pub download_checks: HashMap<Url, DownloadCheck>,
+    /// Each page's links, if the crawler interned them (see
+    /// [CrawlerBuilder::with_link_interning](crate::CrawlerBuilder::with_link_interning)).
+    pub link_table: LinkTable,
+}
+
+/// The links of every page in a crawl, with each distinct URL stored once and shared by every page
+/// that links to it.
+#[derive(Debug, Default)]
+pub struct LinkTable {
+    urls: HashSet<Arc<Url>>,
+    page_links: HashMap<Url, Vec<Arc<Url>>>,
+}
+
+impl LinkTable {
+    /// The links found on the page at `page`, or `None` if its links are not in the table.
+    pub fn links(&self, page: &Url) -> Option<&[Arc<Url>]> {
+        self.page_links.get(page).map(Vec::as_slice)
+    }
+
+    /// The number of distinct URLs linked to.
+    pub fn len(&self) -> usize {
+        self.urls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
+
+    /// Add the links found on `page`, sharing each URL already in the table.
+    pub(crate) fn insert(&mut self, page: Url, links: HashSet<Url>) {
+        let links = links
+            .into_iter()
+            .map(|link| match self.urls.get(&link) {
+                Some(interned) => interned.clone(),
+                None => {
+                    let link = Arc::new(link);
+                    self.urls.insert(link.clone());
+                    link
+                }
+            })
+            .collect();
+        self.page_links.insert(page, links);
+    }
+}
+
+/// Resolve an href found on `page_url`, returning `None` if it should not be treated as a link.
+fn resolve_link(page_url: &Url, href: &str, scope: &Scope) -> Option<Url> {
+    resolve_href(page_url, href).filter(|url| scope.contains(page_url, url))
+}
+
+/// Whether an href points anywhere other than the page it is on. Empty hrefs and fragment-only
+/// hrefs such as `#top` resolve to the page itself, so are not links.
+fn is_link_href(href: &str) -> bool {
+    !href.is_empty() && !href.starts_with('#')
+}
+
+/// Whether an href looks like an absolute URL but its scheme is not valid, such as `ht!tp://`.
+/// These would otherwise be resolved as relative paths.
+fn has_invalid_scheme(href: &str) -> bool {
+    href.trim().split_once("://").is_some_and(|(scheme, _)| {
+        let is_valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        !is_valid_scheme && !scheme.contains(['/', '?', '#'])
+    })
+}
+
+/// Resolve an href found on `page_url` regardless of scope, returning `None` if it is not a link to
+/// another page or cannot be resolved.
+fn resolve_href(page_url: &Url, href: &str) -> Option<Url> {
+    if !is_link_href(href) || has_invalid_scheme(href) {
+        return None;
+    }
+
+    let mut url = page_url.join(href).ok()?;
+
+    url.set_fragment(None);
+    Some(url)
+}
+
+/// Parse the directives from all `<meta name="robots">` tags in a document.
+fn parse_meta_robots(document: &Html) -> MetaRobots {
+    let selector = Selector::parse("meta[name]").expect("Failed to parse selector. This is a bug.");
+
+    let mut meta_robots = MetaRobots::default();
+    for meta in document.select(&selector) {
+        let is_robots = meta
+            .value()
+            .attr("name")
+            .is_some_and(|name| name.eq_ignore_ascii_case("robots"));
+        if !is_robots {
+            continue;
+        }
+
+        let content = meta.value().attr("content").unwrap_or_default();
+        meta_robots.apply_directives(content);
+    }
+    meta_robots
+}
+
+const X_ROBOTS_TAG: HeaderName = HeaderName::from_static("x-robots-tag");
+
+/// Parse the directives from all `X-Robots-Tag` response headers. Headers naming a user agent,
+/// such as `X-Robots-Tag: googlebot: nofollow`, are ignored.
+fn parse_x_robots_tag(headers: &HeaderMap) -> MetaRobots {
+    let mut meta_robots = MetaRobots::default();
+    for value in headers
+        .get_all(X_ROBOTS_TAG)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+    {
+        let names_user_agent = value
+            .split(',')
+            .next()
+            .and_then(|directive| directive.split_once(':'))
+            .is_some_and(|(name, _)| {
+                let name = name.trim().to_ascii_lowercase();
+                name != "unavailable_after" && !name.starts_with("max-")
+            });
+        if !names_user_agent {
+            meta_robots.apply_directives(value);
+        }
+    }
+    meta_robots
+}
+
+impl MetaRobots {
+    /// Set the flags for a comma separated list of directives, such as `noindex, nofollow`.
+    fn apply_directives(&mut self, directives: &str) {
+        for directive in directives.split(',').map(str::trim) {
+            if directive.eq_ignore_ascii_case("noindex") {
+                self.noindex = true;
+            } else if directive.eq_ignore_ascii_case("nofollow") {
+                self.nofollow = true;
+            } else if directive.eq_ignore_ascii_case("none") {
+                self.noindex = true;
+                self.nofollow = true;
+            }
+        }
+    }
+}
+
+/// Split `text` on `separator`, ignoring separators inside `<...>` or double quotes.
+fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut in_brackets = false;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_brackets = true,
+            '>' if !in_quotes => in_brackets = false,
+            c if c == separator && !in_quotes && !in_brackets => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Parse `Link` headers ([RFC 8288](https://www.rfc-editor.org/rfc/rfc8288)) into each target and its
+/// lowercased relation types.
+fn parse_link_headers(headers: &HeaderMap) -> Vec<(String, Vec<String>)> {
+    headers
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| split_unquoted(value, ','))
+        .filter_map(|link| {
+            let mut parts = split_unquoted(link, ';').into_iter();
+            let target = parts
+                .next()?
+                .trim()
+                .strip_prefix('<')?
+                .strip_suffix('>')?
+                .to_string();
+
+            let rels = parts
+                .filter_map(|param| param.split_once('='))
+                .filter(|(name, _)| name.trim().eq_ignore_ascii_case("rel"))
+                .flat_map(|(_, value)| {
+                    value
+                        .trim()
+                        .trim_matches('"')
+                        .split_ascii_whitespace()
+                        .map(str::to_ascii_lowercase)
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+
+            Some((target, rels))
+        })
+        .collect()
+}
+
+/// Get the target of a redirect response from its `Location` header.
+fn parse_redirect(page_content: &PageContent, scope: &Scope) -> Option<Redirect> {
+    if !page_content.status_code.is_redirection() {
+        return None;
+    }
+
+    let location = page_content.headers.get(LOCATION)?.to_str().ok()?;
+    let mut location = page_content.url.join(location).ok()?;
+    location.set_fragment(None);
+
+    Some(Redirect {
+        external: !scope.contains(&page_content.url, &location),
+        location,
+    })
+}
+
+/// Elements the HTML parser creates even when they are missing from the content.
+const IMPLIED_ELEMENTS: [&str; 3] = ["html", "head", "body"];
+
+/// A heuristic for whether content is really HTML: it must not look binary and must contain
+/// at least one element.
+fn looks_like_html(content: &str, document: &Html) -> bool {
+    let suspicious_chars = content
+        .chars()
+        .filter(|&c| c == char::REPLACEMENT_CHARACTER || (c.is_control() && !c.is_whitespace()))
+        .count();
+    if content.contains('\0') || suspicious_chars * 10 > content.chars().count() {
+        return false;
+    }
+
+    let has_element = document
+        .tree
+        .root()
+        .descendants()
+        .filter_map(|node| node.value().as_element())
+        .any(|element| !IMPLIED_ELEMENTS.contains(&element.name()));
+    if has_element {
+        return true;
+    }
+
+    let lowercase = content.to_ascii_lowercase();
+    ["<!doctype html", "<html", "<head", "<body"]
+        .iter()
+        .any(|tag| lowercase.contains(tag))
+}
+
+/// Elements whose text is not visible on the page.
+const INVISIBLE_ELEMENTS: [&str; 6] = ["head", "script", "style", "noscript", "template", "svg"];
+
+/// Count the words of visible text in a document.
+fn word_count(document: &Html) -> usize {
+    document
+        .tree
+        .root()
+        .descendants()
+        .filter_map(|node| node.value().as_text().map(|text| (node, text)))
+        .filter(|(node, _)| {
+            node.ancestors().all(|ancestor| {
+                ancestor
+                    .value()
+                    .as_element()
+                    .is_none_or(|element| !INVISIBLE_ELEMENTS.contains(&element.name()))
+            })
+        })
+        .map(|(_, text)| text.split_whitespace().count())
+        .sum()
+}
+
+/// The `(hreflang, URL)` pairs of `<link rel="alternate">` elements with an `hreflang` attribute.
+fn parse_alternates(document: &Html, page_url: &Url) -> Vec<(String, Url)> {
+    let selector = Selector::parse("link[rel][hreflang][href]")
+        .expect("Failed to parse selector. This is a bug.");
+    document
+        .select(&selector)
+        .filter(|link| {
+            link.value().attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|token| token.eq_ignore_ascii_case("alternate"))
+            })
+        })
+        .filter_map(|link| {
+            let language = link.value().attr("hreflang")?.trim();
+            let url = resolve_href(page_url, link.value().attr("href")?)?;
+            (!language.is_empty()).then(|| (language.to_string(), url))
+        })
+        .collect()
+}
+
+/// Whether an anchor's `rel` attribute contains `token`, ignoring case.
+fn has_rel(a: &ElementRef, token: &str) -> bool {
+    a.value().attr("rel").is_some_and(|rel| {
+        rel.split_ascii_whitespace()
+            .any(|rel_token| rel_token.eq_ignore_ascii_case(token))
+    })
+}
+
+/// The text of an anchor with whitespace collapsed, cut to `max_len` characters followed by `…`
+/// if it is longer.
+fn anchor_text(a: &ElementRef, max_len: Option<usize>) -> String {
+    let text = a
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ");
+    match max_len.and_then(|max_len| text.char_indices().nth(max_len)) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
 
 /// Get all unique links that are from the same domain as the `page_url`.
-/// Excludes any links that do not use http or https scheme.
+/// Links of any scheme are returned; the [Crawler](crate::crawler::Crawler) decides which schemes it follows.
 /// Fragments are not treated as unique links.
+/// Empty hrefs and fragment-only hrefs such as `#` refer to the page itself, so are ignored rather
+/// than recorded as links or [malformed links](Page::malformed_links). Other relative hrefs are
+/// resolved against the page URL, so `.` is the page's directory and `?x=1` is the page with a new query.
+/// Targets of `next`, `prev` and `canonical` relations in `Link` response headers are included.
 pub fn parse_links(page_content: &PageContent) -> Page {
+    let scope = Scope::new(ScopeMode::SameDomain, page_content.url.clone());
+    parse_page(page_content, &scope, None, false, None)
+}
+
+/// Get all unique links that are in `scope`. See [parse_links].
+/// If `link_region` is given, only anchors inside elements matching it are used.
+/// If `parse_noscript` is set, anchors inside `<noscript>` elements are also used.
+/// If `max_anchor_text_len` is given, longer anchor text is cut to that many characters followed by `…`.
+/// RSS and Atom feeds are parsed for the links of their entries instead.
+pub(crate) fn parse_page(
+    page_content: &PageContent,
+    scope: &Scope,
+    link_region: Option<&Selector>,
+    parse_noscript: bool,
+    max_anchor_text_len: Option<usize>,
+) -> Page {
+    if is_feed_content_type(page_content.content_type.as_ref()) {
+        return parse_feed(page_content, scope);
+    }
+
     let document = Html::parse_document(&page_content.content);
     let selector = Selector::parse("a").expect("Failed to parse selector. This is a bug.");
+    let select_in_region = |selector: &Selector| -> Vec<ElementRef> {
+        match link_region {
+            Some(link_region) => document
+                .select(link_region)
+                .flat_map(|region| region.select(selector))
+                .collect(),
+            None => document.select(selector).collect(),
+        }
+    };
+
+    // Documents are parsed with scripting enabled, so `<noscript>` content is left as text.
+    // Re-parse it as HTML to find its anchors.
+    let noscript_fragments: Vec<Html> = if parse_noscript {
+        let noscript =
+            Selector::parse("noscript").expect("Failed to parse selector. This is a bug.");
+        select_in_region(&noscript)
+            .iter()
+            .map(|element| Html::parse_fragment(&element.text().collect::<String>()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let anchor_elements: Vec<ElementRef> = select_in_region(&selector)
+        .into_iter()
+        .chain(
+            noscript_fragments
+                .iter()
+                .flat_map(|fragment| fragment.select(&selector)),
+        )
+        .collect();
 
     let page_url = page_content.url.clone();
 
-    let links = document
-        .select(&selector)
-        .filter(|a| a.value().attr("href").is_some())
-        .map(|a| {
-            a.value()
-                .attr("href")
-                .expect("href not found. This is a bug. None's should be filtered out.")
-        })
-        .filter(|href| !href.starts_with('#'))
-        .flat_map(|href| {
-            if href.starts_with('/') {
-                page_url.join(href)
-            } else {
-                Url::parse(href)
+    // Most anchors on a page are usually unique in-scope links, so size for them up front rather
+    // than rehashing as the sets grow.
+    let mut links = HashSet::with_capacity(anchor_elements.len());
+    let mut anchors = IndexMap::with_capacity(anchor_elements.len());
+    let mut nofollow_links = HashSet::new();
+    let mut sponsored_links = HashSet::new();
+    let mut ugc_links = HashSet::new();
+    let mut external_links = HashSet::new();
+    let mut downloads = HashSet::new();
+    let mut malformed_links = Vec::new();
+
+    for a in anchor_elements {
+        let Some(href) = a.value().attr("href") else {
+            continue;
+        };
+        let Some(url) = resolve_href(&page_url, href) else {
+            if is_link_href(href) {
+                malformed_links.push(href.to_string());
             }
+            continue;
+        };
+
+        if a.value().attr("download").is_some() {
+            downloads.insert(url);
+            continue;
+        }
+
+        if has_rel(&a, "sponsored") {
+            sponsored_links.insert(url.clone());
+        }
+        if has_rel(&a, "ugc") {
+            ugc_links.insert(url.clone());
+        }
+
+        if !scope.contains(&page_url, &url) {
+            if url.has_host() {
+                external_links.insert(url);
+            }
+            continue;
+        }
+
+        if has_rel(&a, "nofollow") {
+            nofollow_links.insert(url.clone());
+        }
+        anchors
+            .entry(url.clone())
+            .or_insert_with(|| anchor_text(&a, max_anchor_text_len));
+        links.insert(url);
+    }
+
+    let alternates = parse_alternates(&document, &page_url);
+    for (_, url) in alternates.iter() {
+        if scope.contains(&page_url, url) {
+            links.insert(url.clone());
+        }
+    }
+
+    // Merge in the next/prev/canonical targets of any `Link` response headers.
+    for target in parse_link_headers(&page_content.headers)
+        .into_iter()
+        .filter(|(_, rels)| {
+            rels.iter()
+                .any(|rel| ["next", "prev", "canonical"].contains(&rel.as_str()))
         })
-        .filter(|url| url.domain() == page_url.domain())
-        .filter(|url| url.scheme() == "https" || url.scheme() == "http")
-        .map(|mut href| {
-            href.set_fragment(None);
-            href
-        })
-        .collect();
+        .filter_map(|(target, _)| resolve_link(&page_url, &target, scope))
+    {
+        links.insert(target);
+    }
+
+    let redirect = parse_redirect(page_content, scope);
+
+    let mut meta_robots = parse_meta_robots(&document);
+    let x_robots_tag = parse_x_robots_tag(&page_content.headers);
+    meta_robots.noindex |= x_robots_tag.noindex;
+    meta_robots.nofollow |= x_robots_tag.nofollow;
 
     Page {
         url: page_url,
         status_code: page_content.status_code,
+        content_type: page_content.content_type.clone(),
+        last_modified: last_modified(&page_content.headers),
+        etag: etag(&page_content.headers),
+        http_version: page_content.http_version,
+        fetched_at: SystemTime::now(),
+        content_hash: content_hash(&page_content.content),
+        content_length: page_content.content.len(),
         links,
+        anchors,
+        nofollow_links,
+        sponsored_links,
+        ugc_links,
+        external_links,
+        downloads,
+        malformed_links,
+        alternates,
+        meta_robots,
+        redirect,
+        word_count: word_count(&document),
+        content_kind: if looks_like_html(&page_content.content, &document) {
+            ContentKind::Html
+        } else {
+            ContentKind::Unrecognized
+        },
+        parse_timed_out: false,
+        parent: None,
+        content: None,
+    }
+}
+
+/// Get the links of each entry in an RSS or Atom feed. Links outside `scope` are external links.
+/// A feed that is not well-formed XML has no links, and its content is unrecognized.
+fn parse_feed(page_content: &PageContent, scope: &Scope) -> Page {
+    let page_url = page_content.url.clone();
+    let entry_links = feed_entry_links(&page_content.content);
+    let content_kind = if entry_links.is_some() {
+        ContentKind::Feed
+    } else {
+        ContentKind::Unrecognized
+    };
+
+    let mut links = HashSet::new();
+    let mut external_links = HashSet::new();
+    for url in entry_links
+        .into_iter()
+        .flatten()
+        .filter_map(|href| resolve_href(&page_url, &href))
+    {
+        if scope.contains(&page_url, &url) {
+            links.insert(url);
+        } else if url.has_host() {
+            external_links.insert(url);
+        }
+    }
+
+    Page {
+        links,
+        external_links,
+        content_kind,
+        parse_timed_out: false,
+        ..parse_timed_out_page(page_content)
+    }
+}
+
+/// FNV-1a, as the standard library's hashers are not stable between releases.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325_u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn content_hash(content: &str) -> u64 {
+    fnv1a(content.as_bytes())
+}
+
+/// Parse the `Last-Modified` header, ignoring it if it is not a valid HTTP date.
+fn last_modified(headers: &HeaderMap) -> Option<SystemTime> {
+    headers
+        .get(LAST_MODIFIED)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| httpdate::parse_http_date(value).ok())
+}
+
+fn etag(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// A [Page] for a response whose parsing took too long and was abandoned.
+pub(crate) fn parse_timed_out_page(page_content: &PageContent) -> Page {
+    Page {
+        url: page_content.url.clone(),
+        status_code: page_content.status_code,
+        content_type: page_content.content_type.clone(),
+        last_modified: last_modified(&page_content.headers),
+        etag: etag(&page_content.headers),
+        http_version: page_content.http_version,
+        fetched_at: SystemTime::now(),
+        content_hash: content_hash(&page_content.content),
+        content_length: page_content.content.len(),
+        links: HashSet::new(),
+        anchors: IndexMap::new(),
+        nofollow_links: HashSet::new(),
+        sponsored_links: HashSet::new(),
+        ugc_links: HashSet::new(),
+        external_links: HashSet::new(),
+        downloads: HashSet::new(),
+        malformed_links: Vec::new(),
+        alternates: Vec::new(),
+        meta_robots: parse_x_robots_tag(&page_content.headers),
+        redirect: None,
+        word_count: 0,
+        content_kind: if is_feed_content_type(page_content.content_type.as_ref()) {
+            ContentKind::Feed
+        } else {
+            ContentKind::Html
+        },
+        parse_timed_out: true,
+        parent: None,
+        content: None,
     }
 }
 
+impl Page {
+    /// Whether the page is HTML according to its `Content-Type`.
+    /// Pages without a `Content-Type` are assumed to be HTML.
+    pub fn is_html(&self) -> bool {
+        self.content_type.is_none() || is_html_content_type(self.content_type.as_ref())
+    }
+
+    /// Links to URLs inside the crawl scope. The same as [links](Page::links).
+    pub fn internal_links(&self) -> &HashSet<Url> {
+        &self.links
+    }
+
+    /// The number of internal and external links on the page, as `(internal, external)`.
+    pub fn link_summary(&self) -> (usize, usize) {
+        (self.links.len(), self.external_links.len())
+    }
+
+    /// A copy of the page without its links, anchors, nofollow, sponsored or ugc links, external
+    /// links, downloads, malformed links or alternates.
+    pub fn without_links(&self) -> Page {
+        Page {
+            url: self.url.clone(),
+            status_code: self.status_code,
+            content_type: self.content_type.clone(),
+            last_modified: self.last_modified,
+            etag: self.etag.clone(),
+            http_version: self.http_version,
+            fetched_at: self.fetched_at,
+            content_hash: self.content_hash,
+            content_length: self.content_length,
+            links: HashSet::new(),
+            anchors: IndexMap::new(),
+            nofollow_links: HashSet::new(),
+            sponsored_links: HashSet::new(),
+            ugc_links: HashSet::new(),
+            external_links: HashSet::new(),
+            downloads: HashSet::new(),
+            malformed_links: Vec::new(),
+            alternates: Vec::new(),
+            meta_robots: self.meta_robots,
+            redirect: self.redirect.clone(),
+            word_count: self.word_count,
+            content_kind: self.content_kind,
+            parse_timed_out: self.parse_timed_out,
+            parent: self.parent.clone(),
+            content: self.content.clone(),
+        }
+    }
+
+    /// Drop the page's links, as in [Page::without_links], without copying the rest of the page.
+    pub(crate) fn clear_links(&mut self) {
+        self.links.clear();
+        self.anchors.clear();
+        self.nofollow_links.clear();
+        self.sponsored_links.clear();
+        self.ugc_links.clear();
+        self.external_links.clear();
+        self.downloads.clear();
+        self.malformed_links.clear();
+        self.alternates.clear();
+    }
+}
+
+/// Whether a `Content-Type` header is present and names an HTML type.
+pub(crate) fn is_html_content_type(content_type: Option<&HeaderValue>) -> bool {
+    content_type
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| {
+            let mime = content_type.split(';').next().unwrap_or_default().trim();
+            mime.eq_ignore_ascii_case("text/html")
+                || mime.eq_ignore_ascii_case("application/xhtml+xml")
+        })
+}
+
+/// Whether a URL's extension suggests an RSS or Atom feed, such as `/feed.xml` or `/blog.atom`.
+pub(crate) fn assume_feed(url: &Url) -> bool {
+    let path = url.path();
+    path.rsplit_once('.')
+        .is_some_and(|(_, suffix)| matches!(suffix, "xml" | "rss" | "atom"))
+}
+
 pub(crate) fn assume_html(url: &Url) -> bool {
     let path = url.path();
 
     if path.contains('.') {
-        let suffix = path.split('.').last();
+        let suffix = path.split('.').next_back();
         suffix == Some("html")
     } else {
         true
@@ -71,10 +856,21 @@ pub(crate) fn assume_html(url: &Url) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use crate::{crawler::PageContent, parser::assume_html};
+    use crate::{
+        crawler::PageContent,
+        parser::{assume_feed, assume_html},
+    };
+    use http::HeaderMap;
+
+    use scraper::Selector;
 
-    use super::parse_links;
-    use std::{collections::HashSet, fs};
+    use super::{parse_links, parse_page, ContentKind, MetaRobots};
+    use crate::scope::{Scope, ScopeMode};
+    use std::{
+        collections::HashSet,
+        fs,
+        time::{Duration, SystemTime},
+    };
     use url::Url;
 
     #[test]
@@ -86,7 +882,7 @@ mod tests {
     <h1 class="foo">Hello, <i>world!</i></h1>
     <a href="https://monzo.com/hi">Monzo https</a>
     <a href="http://monzo.com/hi">Monzo http</a>
-    <a href="ftp://monzo.com/hi">Don't include any links that don't use http or https scheme</a>
+    <a href="ftp://monzo.com/hi">Include links of any scheme</a>
     <div>
         <p>foo</p>
         <p>bar</p>
@@ -103,6 +899,8 @@ mod tests {
             status_code: reqwest::StatusCode::OK,
             content: html.to_string(),
             content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
         };
 
         let links = parse_links(&page).links;
@@ -110,6 +908,7 @@ mod tests {
         let expected_links: HashSet<Url> = HashSet::from([
             "https://monzo.com/hi",
             "http://monzo.com/hi",
+            "ftp://monzo.com/hi",
             "https://monzo.com/nested-deeper",
             "https://monzo.com/fragments-not-unique",
         ])
@@ -121,6 +920,478 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_nofollow_parser() -> anyhow::Result<()> {
+        let html = r#"
+    <meta name="ROBOTS" content="noindex,NOFOLLOW">
+    <a href="/followed">followed</a>
+    <a href="/ads" rel="sponsored nofollow">nofollow</a>
+"#;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+
+        let page = parse_links(&page);
+
+        assert_eq!(page.links.len(), 2);
+        assert_eq!(
+            page.nofollow_links,
+            HashSet::from([Url::parse("https://monzo.com/ads")?])
+        );
+        assert_eq!(
+            page.meta_robots,
+            MetaRobots {
+                noindex: true,
+                nofollow: true
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rel_link_categories() -> anyhow::Result<()> {
+        let html = r#"
+    <a href="/plain">plain</a>
+    <a href="https://ads.example.com/offer" rel="sponsored">advert</a>
+    <a href="/partner" rel="noopener SPONSORED">partner</a>
+    <a href="https://example.com/spam" rel="ugc nofollow">comment</a>
+    <a href="/profile" rel="ugc nofollow">commenter</a>
+"#;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+
+        let page = parse_links(&page);
+
+        assert_eq!(
+            page.sponsored_links,
+            HashSet::from([
+                Url::parse("https://ads.example.com/offer")?,
+                Url::parse("https://monzo.com/partner")?,
+            ])
+        );
+        assert_eq!(
+            page.ugc_links,
+            HashSet::from([
+                Url::parse("https://example.com/spam")?,
+                Url::parse("https://monzo.com/profile")?,
+            ])
+        );
+        // Only links in the crawl scope are followed, so only those are recorded as nofollow
+        assert_eq!(
+            page.nofollow_links,
+            HashSet::from([Url::parse("https://monzo.com/profile")?])
+        );
+        assert_eq!(page.links.len(), 3);
+        assert_eq!(page.external_links.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_x_robots_tag() -> anyhow::Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.append("X-Robots-Tag", "max-snippet: 20, NOFOLLOW".parse()?);
+        headers.append("X-Robots-Tag", "googlebot: noindex".parse()?);
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: r#"<a href="/about">About</a>"#.to_string(),
+            content_type: None,
+            headers,
+            http_version: None,
+        };
+
+        let page = parse_links(&page);
+
+        assert_eq!(page.links.len(), 1);
+        assert_eq!(
+            page.meta_robots,
+            MetaRobots {
+                noindex: false,
+                nofollow: true
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_links() -> anyhow::Result<()> {
+        let html = r#"
+    <a href="/about">About</a>
+    <a href="/f.zip" download>Download</a>
+    <a href="https://cdn.example.com/report.pdf" download="report.pdf">Report</a>
+"#;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+
+        let page = parse_links(&page);
+
+        assert_eq!(
+            page.downloads,
+            HashSet::from([
+                Url::parse("https://monzo.com/f.zip")?,
+                Url::parse("https://cdn.example.com/report.pdf")?
+            ])
+        );
+        assert_eq!(
+            page.links,
+            HashSet::from([Url::parse("https://monzo.com/about")?])
+        );
+        assert!(page.external_links.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hreflang_alternates() -> anyhow::Result<()> {
+        let html = r#"
+    <head>
+        <link rel="alternate" hreflang="fr" href="/fr/">
+        <link rel="alternate" hreflang="de" href="https://monzo.de/">
+        <link rel="alternate" href="/feed.xml">
+        <link rel="stylesheet" hreflang="en" href="/style.css">
+    </head>
+"#;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+
+        let page = parse_links(&page);
+
+        assert_eq!(
+            page.alternates,
+            [
+                ("fr".to_string(), Url::parse("https://monzo.com/fr/")?),
+                ("de".to_string(), Url::parse("https://monzo.de/")?)
+            ]
+        );
+        // Only the in-scope alternate is crawled
+        assert_eq!(
+            page.links,
+            HashSet::from([Url::parse("https://monzo.com/fr/")?])
+        );
+        assert!(page.external_links.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_internal_and_external_links() -> anyhow::Result<()> {
+        let html = r#"
+    <a href="/about">About</a>
+    <a href="https://monzo.com/cost">Cost</a>
+    <a href="/about">About again</a>
+    <a href="https://github.com/monzo">GitHub</a>
+    <a href="https://twitter.com/monzo">Twitter</a>
+    <a href="https://github.com/monzo">GitHub again</a>
+    <a href="mailto:help@monzo.com">Email</a>
+"#;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+
+        let page = parse_links(&page);
+
+        assert_eq!(
+            page.internal_links(),
+            &HashSet::from([
+                Url::parse("https://monzo.com/about")?,
+                Url::parse("https://monzo.com/cost")?
+            ])
+        );
+        assert_eq!(
+            page.external_links,
+            HashSet::from([
+                Url::parse("https://github.com/monzo")?,
+                Url::parse("https://twitter.com/monzo")?
+            ])
+        );
+        assert_eq!(page.link_summary(), (2, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_noscript_links() -> anyhow::Result<()> {
+        let html = r#"
+    <a href="/about">About</a>
+    <noscript>
+        <nav><a href="/cost">Pricing</a> <a href="https://github.com/monzo">GitHub</a></nav>
+    </noscript>
+"#;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+        let scope = Scope::new(ScopeMode::SameDomain, page.url.clone());
+
+        let without_noscript = parse_page(&page, &scope, None, false, None);
+        let with_noscript = parse_page(&page, &scope, None, true, None);
+
+        assert_eq!(
+            without_noscript.links,
+            HashSet::from([Url::parse("https://monzo.com/about")?])
+        );
+        assert_eq!(
+            with_noscript.links,
+            HashSet::from([
+                Url::parse("https://monzo.com/about")?,
+                Url::parse("https://monzo.com/cost")?
+            ])
+        );
+        assert_eq!(
+            with_noscript.external_links,
+            HashSet::from([Url::parse("https://github.com/monzo")?])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_region() -> anyhow::Result<()> {
+        let html = r#"
+    <nav><a href="/about">About</a></nav>
+    <main><p><a href="/cost">Pricing</a></p></main>
+    <footer><a href="/careers">Careers</a></footer>
+"#;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+        let scope = Scope::new(ScopeMode::SameDomain, page.url.clone());
+        let link_region = Selector::parse("main, nav").expect("Failed to parse selector");
+
+        let page = parse_page(&page, &scope, Some(&link_region), false, None);
+
+        assert_eq!(
+            page.links,
+            HashSet::from([
+                Url::parse("https://monzo.com/about")?,
+                Url::parse("https://monzo.com/cost")?
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_anchor_text_len() -> anyhow::Result<()> {
+        let long_text = "word ".repeat(1000);
+        let html = format!(r#"<a href="/long">{long_text}</a> <a href="/short">Cost</a>"#);
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html,
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+        let scope = Scope::new(ScopeMode::SameDomain, page.url.clone());
+
+        let page = parse_page(&page, &scope, None, false, Some(8));
+
+        let anchor = |path: &str| -> anyhow::Result<Option<&String>> {
+            Ok(page
+                .anchors
+                .get(&Url::parse("https://monzo.com")?.join(path)?))
+        };
+        assert_eq!(anchor("/long")?.map(String::as_str), Some("word wor…"));
+        assert_eq!(anchor("/short")?.map(String::as_str), Some("Cost"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_anchor_text_first_seen() -> anyhow::Result<()> {
+        let html = r#"
+    <a href="/cost">Our   <b>pricing</b></a>
+    <a href="/about">About</a>
+    <a href="/cost#plans">Plans</a>
+"#;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+
+        let anchors: Vec<(String, String)> = parse_links(&page)
+            .anchors
+            .into_iter()
+            .map(|(url, text)| (url.to_string(), text))
+            .collect();
+
+        assert_eq!(
+            anchors,
+            vec![
+                (
+                    "https://monzo.com/cost".to_string(),
+                    "Our pricing".to_string()
+                ),
+                ("https://monzo.com/about".to_string(), "About".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_header() -> anyhow::Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "Link",
+            r#"</page/2>; rel="next", <https://monzo.com/style.css>; rel=preload; as=style"#
+                .parse()?,
+        );
+        headers.append(
+            "Link",
+            r#"<https://monzo.com/canonical>; title="a, b; c"; rel="Canonical""#.parse()?,
+        );
+        let page = PageContent {
+            url: Url::parse("https://monzo.com/page/1")?,
+            status_code: reqwest::StatusCode::OK,
+            content: String::new(),
+            content_type: None,
+            headers,
+            http_version: None,
+        };
+
+        let links = parse_links(&page).links;
+
+        let expected_links: HashSet<Url> = HashSet::from([
+            Url::parse("https://monzo.com/page/2")?,
+            Url::parse("https://monzo.com/canonical")?,
+        ]);
+        assert_eq!(links, expected_links);
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_modified_and_etag() -> anyhow::Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Last-Modified", "Wed, 21 Oct 2015 07:28:00 GMT".parse()?);
+        headers.insert("ETag", r#""33a64df5""#.parse()?);
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: String::new(),
+            content_type: None,
+            headers,
+            http_version: None,
+        };
+
+        let page = parse_links(&page);
+
+        assert_eq!(
+            page.last_modified,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_445_412_480))
+        );
+        assert_eq!(page.etag.as_deref(), Some(r#""33a64df5""#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_last_modified() -> anyhow::Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Last-Modified", "yesterday".parse()?);
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: String::new(),
+            content_type: None,
+            headers,
+            http_version: None,
+        };
+
+        let page = parse_links(&page);
+
+        assert_eq!(page.last_modified, None);
+        assert_eq!(page.etag, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_word_count() -> anyhow::Result<()> {
+        let html = r#"
+    <!DOCTYPE html>
+    <head><title>Not counted</title><style>body { color: red; }</style></head>
+    <body>
+        <h1>Hello, <i>world!</i></h1>
+        <p>Banking   made
+        easy.</p>
+        <script>var notCounted = "words in a script";</script>
+        <a href="/about">About us</a>
+    </body>
+"#;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+
+        assert_eq!(parse_links(&page).word_count, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_looks_like_html() -> anyhow::Result<()> {
+        let page = |content: String| -> anyhow::Result<PageContent> {
+            Ok(PageContent {
+                url: Url::parse("https://monzo.com")?,
+                status_code: reqwest::StatusCode::OK,
+                content,
+                content_type: None,
+                headers: HeaderMap::new(),
+                http_version: None,
+            })
+        };
+
+        let garbage: Vec<u8> = (0..512u32).map(|i| (i * 7919 % 256) as u8).collect();
+        let binary = parse_links(&page(String::from_utf8_lossy(&garbage).into_owned())?);
+        assert_eq!(binary.content_kind, ContentKind::Unrecognized);
+
+        let empty = parse_links(&page("<html><body></body></html>".to_string())?);
+        assert_eq!(empty.content_kind, ContentKind::Html);
+        assert!(empty.links.is_empty());
+
+        let no_links = parse_links(&page("<p>Nothing to see here</p>".to_string())?);
+        assert_eq!(no_links.content_kind, ContentKind::Html);
+
+        Ok(())
+    }
+
     #[test]
     fn test_parse_monzo() -> anyhow::Result<()> {
         let html = fs::read_to_string("./tests/test_data/monzo/home.html")?;
@@ -130,6 +1401,8 @@ mod tests {
             status_code: reqwest::StatusCode::OK,
             content: html,
             content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
         };
 
         let links = parse_links(&page).links;
@@ -141,6 +1414,111 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_relative_links() -> anyhow::Result<()> {
+        let html = r#"
+    <a href="child">Child</a>
+    <a href="../sibling">Sibling</a>
+    <a href="//monzo.com/protocol-relative">Protocol relative</a>
+    <a href="">Empty</a>
+"#;
+        // The page was requested at /old and redirected here
+        let page = PageContent {
+            url: Url::parse("https://monzo.com/new/")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+
+        assert_eq!(
+            parse_links(&page).links,
+            HashSet::from([
+                Url::parse("https://monzo.com/new/child")?,
+                Url::parse("https://monzo.com/sibling")?,
+                Url::parse("https://monzo.com/protocol-relative")?,
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_self_referential_hrefs() -> anyhow::Result<()> {
+        let html = r##"
+    <a href="">Empty</a>
+    <a href="#">Fragment</a>
+    <a href=".">Directory</a>
+    <a href="?x=1">Query</a>
+"##;
+        let page_content = |url: &str| -> anyhow::Result<PageContent> {
+            Ok(PageContent {
+                url: Url::parse(url)?,
+                status_code: reqwest::StatusCode::OK,
+                content: html.to_string(),
+                content_type: None,
+                headers: HeaderMap::new(),
+                http_version: None,
+            })
+        };
+
+        let page = parse_links(&page_content("https://monzo.com/docs/page")?);
+        assert_eq!(
+            page.links,
+            HashSet::from([
+                Url::parse("https://monzo.com/docs/")?,
+                Url::parse("https://monzo.com/docs/page?x=1")?,
+            ])
+        );
+        assert!(page.malformed_links.is_empty());
+
+        // On a directory, `.` is the page itself
+        let page = parse_links(&page_content("https://monzo.com/docs/")?);
+        assert_eq!(
+            page.links,
+            HashSet::from([
+                Url::parse("https://monzo.com/docs/")?,
+                Url::parse("https://monzo.com/docs/?x=1")?,
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_links() -> anyhow::Result<()> {
+        let html = r##"
+    <a href="/about">About</a>
+    <a href="ht!tp://bad">Typo</a>
+    <a href="http://exa mple.com/">Space in host</a>
+    <a href="/redirect?to=https://monzo.com">Redirect</a>
+    <a href="#top">Top</a>
+"##;
+        let page = PageContent {
+            url: Url::parse("https://monzo.com")?,
+            status_code: reqwest::StatusCode::OK,
+            content: html.to_string(),
+            content_type: None,
+            headers: HeaderMap::new(),
+            http_version: None,
+        };
+
+        let page = parse_links(&page);
+
+        assert_eq!(
+            page.malformed_links,
+            ["ht!tp://bad", "http://exa mple.com/"]
+        );
+        assert_eq!(
+            page.links,
+            HashSet::from([
+                Url::parse("https://monzo.com/about")?,
+                Url::parse("https://monzo.com/redirect?to=https://monzo.com")?,
+            ])
+        );
+        assert!(page.external_links.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_url_parser() -> anyhow::Result<()> {
         let not_html = Url::parse("https://monzo.com/home.pdf")?;
@@ -155,6 +1533,13 @@ mod tests {
         let not_html = Url::parse("https://monzo.com/home.html")?;
         assert!(assume_html(&not_html));
 
+        for feed in ["feed.xml", "blog.rss", "blog.atom"] {
+            let feed = Url::parse("https://monzo.com/")?.join(feed)?;
+            assert!(assume_feed(&feed));
+            assert!(!assume_html(&feed));
+        }
+        assert!(!assume_feed(&Url::parse("https://monzo.com/home.html")?));
+
         Ok(())
     }
 }
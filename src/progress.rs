@@ -0,0 +1,113 @@
+use std::{sync::Arc, time::Duration};
+
+use indicatif::{MultiProgress, ProgressBar};
+use owo_colors::OwoColorize;
+
+use crate::color::Colors;
+use spider_crab::Page;
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    time::Instant,
+};
+use tracing::debug;
+use url::Url;
+
+/// Show progress bars for a crawl of `url` until the crawler finishes. Blocks the current thread.
+pub fn show_progress(mut rx: broadcast::Receiver<Arc<Page>>, url: Url, colors: Colors) {
+    let start = Instant::now();
+
+    let multi_progress = MultiProgress::new();
+    let header = multi_progress.add(ProgressBar::new_spinner());
+    let current_url = multi_progress.add(ProgressBar::new_spinner());
+    let visit_stats = multi_progress.add(ProgressBar::new_spinner());
+
+    header.enable_steady_tick(Duration::from_millis(120));
+    current_url.enable_steady_tick(Duration::from_millis(120));
+    visit_stats.enable_steady_tick(Duration::from_millis(120));
+
+    header.set_message(format!("Crawling: {}", url.as_str().style(colors.green())));
+
+    receive_pages(&mut rx, |count, page| {
+        let duration = start.elapsed();
+        let seconds = duration.as_secs() % 60;
+        let minutes = (duration.as_secs() / 60) % 60;
+        visit_stats.set_message(format!(
+            "  Visited {} pages in {:0>2}:{:0>2}",
+            count.style(colors.cyan()),
+            minutes.to_string().style(colors.cyan()),
+            seconds.to_string().style(colors.cyan())
+        ));
+        current_url.set_message(format!(
+            "  Current url: {}",
+            page.url.as_str().style(colors.green())
+        ));
+    });
+    header.finish_and_clear();
+    current_url.finish_and_clear();
+    visit_stats.finish_and_clear();
+}
+
+/// Call `on_page` with each page the crawler reports and the number of pages reported so far,
+/// until the crawler finishes. Pages missed because the receiver fell behind are still counted.
+/// Returns the number of pages reported.
+fn receive_pages(
+    rx: &mut broadcast::Receiver<Arc<Page>>,
+    mut on_page: impl FnMut(u64, &Page),
+) -> u64 {
+    let mut count = 0;
+    loop {
+        match rx.blocking_recv() {
+            Ok(page) => {
+                count += 1;
+                on_page(count, &page);
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                debug!("Progress fell behind - Skipped {} pages", skipped);
+                count += skipped;
+            }
+            Err(RecvError::Closed) => return count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use http::HeaderMap;
+    use reqwest::StatusCode;
+    use spider_crab::{parse_links, PageContent};
+    use tokio::sync::broadcast;
+    use url::Url;
+
+    use super::receive_pages;
+
+    #[test]
+    fn test_receive_pages_after_lag() -> anyhow::Result<()> {
+        // Given: A channel with room for two pages, sent five before they are received
+        let (tx, mut rx) = broadcast::channel(2);
+        for i in 1..=5 {
+            tx.send(Arc::new(parse_links(&PageContent {
+                url: Url::parse(&format!("https://monzo.com/{i}"))?,
+                status_code: StatusCode::OK,
+                content: String::new(),
+                content_type: None,
+                headers: HeaderMap::new(),
+                http_version: None,
+            })))?;
+        }
+        drop(tx);
+
+        // When: We receive the pages
+        let mut received = Vec::new();
+        let count = receive_pages(&mut rx, |count, page| {
+            received.push((count, page.url.path().to_string()))
+        });
+
+        // Then: Receiving continues after the skipped pages, which are still counted
+        assert_eq!(count, 5);
+        assert_eq!(received, [(4, "/4".to_string()), (5, "/5".to_string())]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde::Serialize;
+use url::Url;
+
+use crate::parser::AllPages;
+
+/// What to include in a [CrawlReport] beyond what the crawl itself found.
+#[derive(Debug, Clone, Default)]
+pub struct ReportOptions {
+    /// The URLs of the site's sitemap, to report [sitemap coverage](AllPages::sitemap_coverage).
+    pub sitemap_urls: Option<HashSet<Url>>,
+}
+
+/// Everything a crawl found, for exporting in one go. Serialize it with [serde], such as with
+/// `serde_json::to_string`. URLs are sorted so reports of the same crawl are identical.
+/// See [AllPages::report].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrawlReport {
+    pub stats: CrawlStats,
+    /// Visited pages that did not respond with a 2xx status. See [AllPages::broken_links_report].
+    pub broken_links: Vec<BrokenLink>,
+    /// Every unique link to a URL outside the crawl scope.
+    pub external_links: Vec<String>,
+    /// Every unique link marked `rel="sponsored"`, in or out of the crawl scope.
+    pub sponsored_links: Vec<String>,
+    /// Every unique link marked `rel="ugc"`, in or out of the crawl scope.
+    pub ugc_links: Vec<String>,
+    /// Visited pages, other than seeds, that no reported page links to. These were reached through
+    /// redirects or from pages excluded by `noindex`.
+    pub orphans: Vec<String>,
+    /// Groups of successfully visited pages with identical bodies.
+    pub duplicate_content: Vec<Vec<String>>,
+    /// Set if [ReportOptions::sitemap_urls] was given.
+    pub sitemap_coverage: Option<SitemapCoverageReport>,
+}
+
+/// Totals for a crawl in a [CrawlReport].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrawlStats {
+    pub pages: usize,
+    /// Links to URLs inside the crawl scope, summed over every page.
+    pub links: usize,
+    /// Links to URLs outside the crawl scope, summed over every page.
+    pub external_links: usize,
+    pub failed: usize,
+    /// URLs dropped without being visited, for any [reason](crate::SkipReason).
+    pub skipped: usize,
+    /// The number of pages with each status code.
+    pub status_counts: BTreeMap<u16, usize>,
+}
+
+/// A page in [CrawlReport::broken_links].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BrokenLink {
+    pub url: String,
+    pub status: u16,
+    /// The pages linking to it.
+    pub referrers: Vec<String>,
+}
+
+/// The [SitemapCoverage](crate::SitemapCoverage) of a crawl in a [CrawlReport].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SitemapCoverageReport {
+    pub missing_from_crawl: Vec<String>,
+    pub missing_from_sitemap: Vec<String>,
+}
+
+/// The URLs as strings, in URL order.
+fn sorted_strings<'a>(urls: impl IntoIterator<Item = &'a Url>) -> Vec<String> {
+    urls.into_iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(Url::to_string)
+        .collect()
+}
+
+impl AllPages {
+    /// A [CrawlReport] of the crawl's totals, broken links, external links, sponsored and ugc links,
+    /// orphaned pages, duplicated content and, if sitemap URLs are given in `options`, sitemap coverage.
+    /// Links are only reported if the crawler retained them (see [CrawlerBuilder::with_retain_links](crate::CrawlerBuilder::with_retain_links)).
+    pub fn report(&self, options: &ReportOptions) -> CrawlReport {
+        let mut status_counts = BTreeMap::new();
+        for page in self.pages.iter() {
+            *status_counts.entry(page.status_code.as_u16()).or_insert(0) += 1;
+        }
+        let stats = CrawlStats {
+            pages: self.pages.len(),
+            links: self.pages.iter().map(|page| page.links.len()).sum(),
+            external_links: self
+                .pages
+                .iter()
+                .map(|page| page.external_links.len())
+                .sum(),
+            failed: self.failed.len(),
+            skipped: self.skipped.values().sum(),
+            status_counts,
+        };
+
+        let mut broken_links: Vec<BrokenLink> = self
+            .broken_links_report()
+            .into_iter()
+            .map(|(url, (status_code, referrers))| BrokenLink {
+                url: url.to_string(),
+                status: status_code.as_u16(),
+                referrers: referrers.iter().map(Url::to_string).collect(),
+            })
+            .collect();
+        broken_links.sort_by(|a, b| a.url.cmp(&b.url));
+
+        let external_links = sorted_strings(
+            self.pages
+                .iter()
+                .flat_map(|page| page.external_links.iter()),
+        );
+
+        let sponsored_links = sorted_strings(
+            self.pages
+                .iter()
+                .flat_map(|page| page.sponsored_links.iter()),
+        );
+        let ugc_links = sorted_strings(self.pages.iter().flat_map(|page| page.ugc_links.iter()));
+
+        let in_degrees = self.in_degrees();
+        let orphans = sorted_strings(
+            self.pages
+                .iter()
+                .filter(|page| page.parent.is_some() && in_degrees.get(&page.url) == Some(&0))
+                .map(|page| &page.url),
+        );
+
+        let mut by_content: HashMap<u64, Vec<&Url>> = HashMap::new();
+        for page in self
+            .pages
+            .iter()
+            .filter(|page| page.status_code.is_success())
+        {
+            by_content
+                .entry(page.content_hash)
+                .or_default()
+                .push(&page.url);
+        }
+        let mut duplicate_content: Vec<Vec<String>> = by_content
+            .into_values()
+            .filter(|urls| urls.len() > 1)
+            .map(sorted_strings)
+            .collect();
+        duplicate_content.sort();
+
+        let sitemap_coverage = options.sitemap_urls.as_ref().map(|sitemap_urls| {
+            let coverage = self.sitemap_coverage(sitemap_urls);
+            SitemapCoverageReport {
+                missing_from_crawl: sorted_strings(&coverage.missing_from_crawl),
+                missing_from_sitemap: sorted_strings(&coverage.missing_from_sitemap),
+            }
+        });
+
+        CrawlReport {
+            stats,
+            broken_links,
+            external_links,
+            sponsored_links,
+            ugc_links,
+            orphans,
+            duplicate_content,
+            sitemap_coverage,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::{fmt, path::Path, time::Duration};
+
+use owo_colors::OwoColorize;
+use reqwest::redirect;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use reqwest_tracing::TracingMiddleware;
+use spider_crab::client_middleware::RetryTooManyRequestsMiddleware;
+use texting_robots::{get_robots_url, Robot};
+use url::Url;
+
+use crate::APP_USER_AGENT;
+
+/// A client with middleware for obtaining Robots.txt files.
+/// Roughly follows https://github.com/Smerity/texting_robots?tab=readme-ov-file#crawling-considerations
+fn robots_client() -> anyhow::Result<ClientWithMiddleware> {
+    let retry_policy = ExponentialBackoff::builder().build_with_max_retries(5);
+
+    Ok(ClientBuilder::new(
+        reqwest::Client::builder()
+            .user_agent(APP_USER_AGENT)
+            .redirect(redirect::Policy::limited(10))
+            .build()?,
+    )
+    .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+    .with(RetryTooManyRequestsMiddleware::new(Duration::from_secs(5)))
+    .with(TracingMiddleware::default())
+    .build())
+}
+
+/// Fetch a robots.txt file from its URL.
+async fn fetch_robots(robots_url: &str) -> anyhow::Result<String> {
+    let rclient = robots_client()?;
+
+    let res = rclient.get(robots_url).send().await?;
+    let robots = res.text().await;
+    robots.map_err(Into::into)
+}
+
+/// Try to get a robots.txt file for a given URL, returning None if it doesn't exist.
+pub async fn get_robots(root_url: &Url) -> anyhow::Result<String> {
+    let robots_url = get_robots_url(root_url.as_str())?;
+    fetch_robots(&robots_url).await
+}
+
+/// Load a robots.txt file from an http(s) URL or a local file path.
+pub async fn load_robots(source: &str) -> anyhow::Result<String> {
+    match Url::parse(source) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+            fetch_robots(url.as_str()).await
+        }
+        _ => Ok(tokio::fs::read_to_string(Path::new(source)).await?),
+    }
+}
+
+/// The result of checking a URL against a robots.txt file.
+#[derive(Debug, PartialEq)]
+pub struct RobotsCheck {
+    pub url: Url,
+    pub agent: String,
+    pub allowed: bool,
+    pub crawl_delay: Option<f32>,
+}
+
+impl fmt::Display for RobotsCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.allowed {
+            "allowed".green().to_string()
+        } else {
+            "disallowed".red().to_string()
+        };
+        writeln!(f, "{} is {} for {}", self.url, verdict, self.agent.cyan())?;
+
+        match self.crawl_delay {
+            Some(delay) => write!(f, "Crawl-delay: {}s", delay),
+            None => write!(f, "Crawl-delay: none"),
+        }
+    }
+}
+
+/// Check whether `agent` may visit `url` according to `robots_txt`.
+pub fn check_robots(robots_txt: &str, url: &Url, agent: &str) -> anyhow::Result<RobotsCheck> {
+    let robot = Robot::new(agent, robots_txt.as_bytes())?;
+
+    Ok(RobotsCheck {
+        url: url.clone(),
+        agent: agent.to_string(),
+        allowed: robot.allowed(url.as_str()),
+        crawl_delay: robot.delay,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::{check_robots, load_robots};
+
+    #[tokio::test]
+    async fn test_robots_check() -> anyhow::Result<()> {
+        let robots_txt = load_robots("./tests/test_data/robots.txt").await?;
+
+        let check = check_robots(
+            &robots_txt,
+            &Url::parse("https://monzo.com/private/accounts")?,
+            "spider_crab",
+        )?;
+        assert!(!check.allowed);
+        assert_eq!(check.crawl_delay, Some(2.0));
+
+        let check = check_robots(
+            &robots_txt,
+            &Url::parse("https://monzo.com/private/public-page")?,
+            "spider_crab",
+        )?;
+        assert!(check.allowed);
+
+        let check = check_robots(
+            &robots_txt,
+            &Url::parse("https://monzo.com/about")?,
+            "spider_crab",
+        )?;
+        assert!(check.allowed);
+
+        let check = check_robots(
+            &robots_txt,
+            &Url::parse("https://monzo.com/about")?,
+            "BadBot",
+        )?;
+        assert!(!check.allowed);
+        assert_eq!(check.crawl_delay, None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use publicsuffix::{List, Psl};
+use serde::{Serialize, Serializer};
+use url::Url;
+
+/// How the [Crawler](crate::crawler::Crawler) decides whether a link is in scope.
+///
+/// Hosts are compared in their ASCII form, as internationalized domain names are converted to
+/// punycode when URLs are parsed, so `münchen.de` and `xn--mnchen-3ya.de` are the same host.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ScopeMode {
+    /// Links must have the same domain as the page they were found on.
+    #[default]
+    SameDomain,
+    /// Links must have the same host and port as the seed URL.
+    ExactHost,
+    /// Links must have the same registrable domain (eTLD+1) as the seed URL according to the
+    /// public suffix list, so `help.monzo.com` is in scope when crawling `monzo.com`.
+    /// Hosts without a registrable domain, such as IP addresses, must match the seed exactly.
+    RegistrableDomain(Arc<List>),
+}
+
+/// Serialized as the name of the mode. The public suffix list is not included.
+impl Serialize for ScopeMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (index, name) = match self {
+            ScopeMode::SameDomain => (0, "SameDomain"),
+            ScopeMode::ExactHost => (1, "ExactHost"),
+            ScopeMode::RegistrableDomain(_) => (2, "RegistrableDomain"),
+        };
+        serializer.serialize_unit_variant("ScopeMode", index, name)
+    }
+}
+
+/// A [ScopeMode] resolved against the seed URL of a crawl.
+#[derive(Clone, Debug)]
+pub(crate) struct Scope {
+    mode: ScopeMode,
+    seed: Url,
+}
+
+impl Scope {
+    pub(crate) fn new(mode: ScopeMode, seed: Url) -> Self {
+        Self { mode, seed }
+    }
+
+    /// Check if a `link` found on `page_url` is in scope.
+    pub(crate) fn contains(&self, page_url: &Url, link: &Url) -> bool {
+        match &self.mode {
+            ScopeMode::SameDomain => link.domain() == page_url.domain(),
+            ScopeMode::ExactHost => {
+                link.host() == self.seed.host()
+                    && link.port_or_known_default() == self.seed.port_or_known_default()
+            }
+            ScopeMode::RegistrableDomain(list) => {
+                match (
+                    registrable_domain(list, link),
+                    registrable_domain(list, &self.seed),
+                ) {
+                    (Some(link_domain), Some(seed_domain)) => link_domain == seed_domain,
+                    _ => link.host() == self.seed.host(),
+                }
+            }
+        }
+    }
+}
+
+/// The registrable domain of a URL, ignoring case and any trailing dot.
+fn registrable_domain(list: &List, url: &Url) -> Option<String> {
+    let host = url.domain()?.trim_end_matches('.').to_ascii_lowercase();
+    let domain = list.domain(host.as_bytes())?;
+    String::from_utf8(domain.as_bytes().to_vec()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use publicsuffix::List;
+    use url::Url;
+
+    use super::{Scope, ScopeMode};
+
+    fn registrable_scope(seed: &str) -> anyhow::Result<Scope> {
+        let list: List = fs::read_to_string("./tests/test_data/public_suffix_list.dat")?.parse()?;
+        Ok(Scope::new(
+            ScopeMode::RegistrableDomain(Arc::new(list)),
+            Url::parse(seed)?,
+        ))
+    }
+
+    fn in_scope(scope: &Scope, link: &str) -> anyhow::Result<bool> {
+        let page_url = Url::parse("https://monzo.com/")?;
+        Ok(scope.contains(&page_url, &Url::parse(link)?))
+    }
+
+    #[test]
+    fn test_registrable_domain_subdomains() -> anyhow::Result<()> {
+        let scope = registrable_scope("https://monzo.com")?;
+
+        assert!(in_scope(&scope, "https://monzo.com/about")?);
+        assert!(in_scope(&scope, "https://www.monzo.com/about")?);
+        assert!(in_scope(&scope, "https://help.monzo.com/about")?);
+        assert!(in_scope(&scope, "http://HELP.MONZO.COM/about")?);
+        assert!(!in_scope(&scope, "https://notmonzo.com/about")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_registrable_domain_multi_label_suffix() -> anyhow::Result<()> {
+        let scope = registrable_scope("https://www.monzo.co.uk")?;
+
+        assert!(in_scope(&scope, "https://monzo.co.uk/about")?);
+        assert!(in_scope(&scope, "https://help.monzo.co.uk/about")?);
+        assert!(!in_scope(&scope, "https://barclays.co.uk/about")?);
+        assert!(!in_scope(&scope, "https://co.uk/about")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_registrable_domain_spoofing() -> anyhow::Result<()> {
+        let scope = registrable_scope("https://monzo.com")?;
+
+        assert!(!in_scope(&scope, "https://monzo.com.evil.com/about")?);
+        assert!(!in_scope(&scope, "https://evilmonzo.com/about")?);
+        assert!(!in_scope(&scope, "https://monzo.com@evil.com/about")?);
+        assert!(!in_scope(&scope, "https://127.0.0.1/about")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exact_host() -> anyhow::Result<()> {
+        let scope = Scope::new(ScopeMode::ExactHost, Url::parse("https://monzo.com")?);
+
+        assert!(in_scope(&scope, "https://monzo.com:443/about")?);
+        assert!(!in_scope(&scope, "https://monzo.com:8443/about")?);
+        assert!(!in_scope(&scope, "https://www.monzo.com/about")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_internationalized_domain_names() -> anyhow::Result<()> {
+        let page_url = Url::parse("https://münchen.de/")?;
+        let in_scope = |scope: &Scope, link: &str| -> anyhow::Result<bool> {
+            Ok(scope.contains(&page_url, &Url::parse(link)?))
+        };
+
+        for mode in [ScopeMode::SameDomain, ScopeMode::ExactHost] {
+            let scope = Scope::new(mode, Url::parse("https://münchen.de")?);
+            assert!(in_scope(&scope, "https://xn--mnchen-3ya.de/about")?);
+            assert!(in_scope(&scope, "https://MÜNCHEN.de/about")?);
+            assert!(!in_scope(&scope, "https://munchen.de/about")?);
+        }
+
+        let scope = registrable_scope("https://xn--mnchen-3ya.de")?;
+        assert!(in_scope(&scope, "https://www.münchen.de/about")?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::HashSet,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use url::Url;
+
+use crate::parser::AllPages;
+
+/// How the URLs of a sitemap compare with the pages found by a crawl.
+/// See [AllPages::sitemap_coverage].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SitemapCoverage {
+    /// Sitemap URLs the crawl never reached, such as pages no longer linked from the site.
+    pub missing_from_crawl: HashSet<Url>,
+    /// Successfully visited HTML pages that are not in the sitemap.
+    pub missing_from_sitemap: HashSet<Url>,
+}
+
+/// Escape a string for use in XML text content.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Format a time as a [W3C datetime](https://www.w3.org/TR/NOTE-datetime) in UTC, such as
+/// `2015-10-21T07:28:00Z`. Times before 1970 are clamped to the epoch.
+fn w3c_datetime(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Convert days since the epoch to a proleptic Gregorian date, per Howard Hinnant's
+    // `civil_from_days`, counting in 400 year eras from 0000-03-01
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    )
+}
+
+impl AllPages {
+    /// Generate a [sitemap](https://www.sitemaps.org/protocol.html) containing every successfully visited HTML page.
+    /// Pages without a 2xx status or with a non-HTML `Content-Type` are excluded. Pages with a
+    /// `Last-Modified` header have it as their `<lastmod>`.
+    pub fn to_sitemap_xml(&self) -> String {
+        let mut xml = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+            "\n"
+        ));
+
+        for page in self
+            .pages
+            .iter()
+            .filter(|page| page.status_code.is_success() && page.is_html())
+        {
+            xml.push_str("  <url>\n");
+            xml.push_str(&format!(
+                "    <loc>{}</loc>\n",
+                escape_xml(page.url.as_str())
+            ));
+            if let Some(last_modified) = page.last_modified {
+                xml.push_str(&format!(
+                    "    <lastmod>{}</lastmod>\n",
+                    w3c_datetime(last_modified)
+                ));
+            }
+            xml.push_str("  </url>\n");
+        }
+
+        xml.push_str("</urlset>\n");
+        xml
+    }
+
+    /// Compare the crawl with the URLs of a sitemap. A sitemap URL counts as reached if it was
+    /// visited, or if it was normalized or redirected to a visited page. Pages that would be
+    /// excluded from [AllPages::to_sitemap_xml] are not reported as missing from the sitemap.
+    pub fn sitemap_coverage(&self, sitemap_urls: &HashSet<Url>) -> SitemapCoverage {
+        let visited: HashSet<&Url> = self.pages.iter().map(|page| &page.url).collect();
+        let missing_from_crawl = sitemap_urls
+            .iter()
+            .filter(|url| !visited.contains(self.aliases.get(*url).unwrap_or(url)))
+            .cloned()
+            .collect();
+
+        let reached: HashSet<&Url> = sitemap_urls
+            .iter()
+            .map(|url| self.aliases.get(url).unwrap_or(url))
+            .collect();
+        let missing_from_sitemap = self
+            .pages
+            .iter()
+            .filter(|page| page.status_code.is_success() && page.is_html())
+            .map(|page| &page.url)
+            .filter(|url| !reached.contains(url))
+            .cloned()
+            .collect();
+
+        SitemapCoverage {
+            missing_from_crawl,
+            missing_from_sitemap,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        time::{Duration, SystemTime},
+    };
+
+    use http::{HeaderValue, StatusCode};
+    use indexmap::IndexMap;
+    use url::Url;
+
+    use super::{w3c_datetime, SitemapCoverage};
+    use crate::{parser::MetaRobots, AllPages, ContentKind, Page};
+
+    fn page(url: &str, status_code: StatusCode, content_type: &str) -> anyhow::Result<Page> {
+        Ok(Page {
+            url: Url::parse(url)?,
+            status_code,
+            content_type: Some(HeaderValue::from_str(content_type)?),
+            last_modified: None,
+            etag: None,
+            http_version: None,
+            fetched_at: SystemTime::UNIX_EPOCH,
+            content_hash: 0,
+            content_length: 0,
+            links: HashSet::new(),
+            anchors: IndexMap::new(),
+            nofollow_links: HashSet::new(),
+            sponsored_links: HashSet::new(),
+            ugc_links: HashSet::new(),
+            external_links: HashSet::new(),
+            downloads: HashSet::new(),
+            malformed_links: Vec::new(),
+            alternates: Vec::new(),
+            meta_robots: MetaRobots::default(),
+            redirect: None,
+            word_count: 0,
+            content_kind: ContentKind::Html,
+            parse_timed_out: false,
+            parent: None,
+            content: None,
+        })
+    }
+
+    #[test]
+    fn test_sitemap_xml() -> anyhow::Result<()> {
+        let last_modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_445_412_480);
+        let all_pages = AllPages {
+            pages: vec![
+                Page {
+                    last_modified: Some(last_modified),
+                    ..page("https://monzo.com/", StatusCode::OK, "text/html")?
+                },
+                page(
+                    "https://monzo.com/search?q=a&page=2",
+                    StatusCode::OK,
+                    "text/html; charset=utf-8",
+                )?,
+                page(
+                    "https://monzo.com/missing",
+                    StatusCode::NOT_FOUND,
+                    "text/html",
+                )?,
+                page("https://monzo.com/data", StatusCode::OK, "application/json")?,
+            ],
+            ..Default::default()
+        };
+
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url>
+    <loc>https://monzo.com/</loc>
+    <lastmod>2015-10-21T07:28:00Z</lastmod>
+  </url>
+  <url>
+    <loc>https://monzo.com/search?q=a&amp;page=2</loc>
+  </url>
+</urlset>
+"#;
+        assert_eq!(all_pages.to_sitemap_xml(), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_w3c_datetime() {
+        let at = |secs: u64| w3c_datetime(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+
+        assert_eq!(at(0), "1970-01-01T00:00:00Z");
+        assert_eq!(at(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(at(1_709_208_000), "2024-02-29T12:00:00Z");
+        assert_eq!(at(1_735_689_599), "2024-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_sitemap_coverage() -> anyhow::Result<()> {
+        let all_pages = AllPages {
+            pages: vec![
+                page("https://monzo.com/", StatusCode::OK, "text/html")?,
+                page("https://monzo.com/about", StatusCode::OK, "text/html")?,
+                page("https://monzo.com/careers/", StatusCode::OK, "text/html")?,
+                page("https://monzo.com/new", StatusCode::OK, "text/html")?,
+                page(
+                    "https://monzo.com/missing",
+                    StatusCode::NOT_FOUND,
+                    "text/html",
+                )?,
+                page("https://monzo.com/data", StatusCode::OK, "application/json")?,
+            ],
+            aliases: HashMap::from([(
+                Url::parse("https://monzo.com/careers")?,
+                Url::parse("https://monzo.com/careers/")?,
+            )]),
+            ..Default::default()
+        };
+        let urls = |urls: &[&str]| -> anyhow::Result<HashSet<Url>> {
+            Ok(urls
+                .iter()
+                .map(|url| Url::parse(url))
+                .collect::<Result<_, _>>()?)
+        };
+        let sitemap_urls = urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/about",
+            "https://monzo.com/careers",
+            "https://monzo.com/orphaned",
+        ])?;
+
+        assert_eq!(
+            all_pages.sitemap_coverage(&sitemap_urls),
+            SitemapCoverage {
+                missing_from_crawl: urls(&["https://monzo.com/orphaned"])?,
+                missing_from_sitemap: urls(&["https://monzo.com/new"])?,
+            }
+        );
+
+        Ok(())
+    }
+}
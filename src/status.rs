@@ -0,0 +1,129 @@
+use std::{future::Future, path::Path, time::Duration};
+
+use spider_crab::LiveStats;
+use tracing::warn;
+
+use crate::output::write_file_atomically;
+
+/// The crawl's progress as JSON, for external processes to poll.
+fn status_json(live_stats: &LiveStats) -> String {
+    let snapshot = live_stats.snapshot();
+    let status = serde_json::json!({
+        "pages_visited": snapshot.pages_visited,
+        "frontier": snapshot.frontier,
+        "failed": snapshot.failed,
+        "elapsed_secs": snapshot.elapsed.as_secs_f64(),
+        "finished": snapshot.finished,
+    });
+    format!("{}\n", status)
+}
+
+/// Write the crawl's progress to `path`. The file is replaced atomically, so readers never see
+/// a partial status.
+pub async fn write_status(path: &Path, live_stats: &LiveStats) -> anyhow::Result<()> {
+    write_file_atomically(path, status_json(live_stats)).await
+}
+
+/// Run `crawl`, writing its progress to `path` every `interval` and once more when it finishes.
+/// Failed writes during the crawl are logged and retried on the next interval.
+pub async fn with_status_file<T>(
+    crawl: impl Future<Output = T>,
+    path: &Path,
+    live_stats: &LiveStats,
+    interval: Duration,
+) -> anyhow::Result<T> {
+    let mut crawl = std::pin::pin!(crawl);
+    let mut interval = tokio::time::interval(interval);
+    let output = loop {
+        tokio::select! {
+            output = &mut crawl => break output,
+            _ = interval.tick() => {
+                if let Err(e) = write_status(path, live_stats).await {
+                    warn!("Failed to write status file {}: {:#}", path.display(), e);
+                }
+            }
+        }
+    };
+    write_status(path, live_stats).await?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, time::Duration};
+
+    use spider_crab::{CrawlerBuilder, TowerVisitor};
+    use url::Url;
+
+    use super::with_status_file;
+
+    fn read_status(path: &Path) -> anyhow::Result<serde_json::Value> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    #[tokio::test]
+    async fn test_status_file() -> anyhow::Result<()> {
+        // Given: A site of three pages in a chain, where the linked pages take 150ms to respond
+        let service = tower::service_fn(|request: http::Request<String>| async move {
+            let content = match request.uri().path() {
+                "/" => r#"<a href="/1"></a>"#,
+                "/1" => r#"<a href="/2"></a>"#,
+                _ => "<p></p>",
+            };
+            if request.uri().path() != "/" {
+                tokio::time::sleep(Duration::from_millis(150)).await;
+            }
+            Ok::<_, std::io::Error>(http::Response::new(content.to_string()))
+        });
+        let crawler = CrawlerBuilder::new(TowerVisitor::new(service)).build();
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("status.json");
+
+        // When: We crawl it, writing the status file every 20ms
+        // And: Poll the status file while the crawl runs
+        let live_stats = crawler.live_stats();
+        let poller = {
+            let path = path.clone();
+            tokio::spawn(async move {
+                let mut pages_visited = Vec::new();
+                loop {
+                    tokio::time::sleep(Duration::from_millis(25)).await;
+                    let Ok(status) = read_status(&path) else {
+                        continue;
+                    };
+                    if status["finished"] == true {
+                        return pages_visited;
+                    }
+                    pages_visited.extend(status["pages_visited"].as_u64());
+                }
+            })
+        };
+        let all_pages = with_status_file(
+            crawler.crawl(Url::parse("https://monzo.com/")?),
+            &path,
+            &live_stats,
+            Duration::from_millis(20),
+        )
+        .await?;
+        let pages_visited = poller.await?;
+
+        // Then: The status file was written during the crawl and updated as pages were visited
+        assert_eq!(all_pages.pages.len(), 3);
+        assert!(pages_visited.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(pages_visited.contains(&1));
+        assert!(pages_visited.contains(&2));
+
+        // And: The final status shows the finished crawl
+        let status = read_status(&path)?;
+        assert_eq!(status["pages_visited"], 3);
+        assert_eq!(status["frontier"], 0);
+        assert_eq!(status["failed"], 0);
+        assert_eq!(status["finished"], true);
+        assert!(status["elapsed_secs"]
+            .as_f64()
+            .is_some_and(|secs| secs >= 0.3));
+
+        Ok(())
+    }
+}
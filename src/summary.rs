@@ -0,0 +1,129 @@
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use owo_colors::OwoColorize;
+use reqwest::StatusCode;
+use spider_crab::AllPages;
+
+use crate::color::Colors;
+
+/// Counts describing a finished crawl.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrawlSummary {
+    pub status_counts: BTreeMap<StatusCode, usize>,
+    pub pages: usize,
+    pub links: usize,
+    pub failures: usize,
+    pub elapsed: Duration,
+    pub colors: Colors,
+}
+
+impl CrawlSummary {
+    pub fn new(all_pages: &AllPages, elapsed: Duration, colors: Colors) -> Self {
+        let mut status_counts = BTreeMap::new();
+        for page in all_pages.pages.iter() {
+            *status_counts.entry(page.status_code).or_insert(0) += 1;
+        }
+
+        Self {
+            status_counts,
+            pages: all_pages.pages.len(),
+            links: all_pages.pages.iter().map(|page| page.links.len()).sum(),
+            failures: all_pages.failed.len(),
+            elapsed,
+            colors,
+        }
+    }
+}
+
+impl fmt::Display for CrawlSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let colors = self.colors;
+        writeln!(
+            f,
+            "{:<8} {:>8}",
+            "Status".style(colors.bold()),
+            "Pages".style(colors.bold())
+        )?;
+        for (status_code, count) in self.status_counts.iter() {
+            let status = format!("{:<8}", status_code.as_u16());
+            let status = if status_code.is_success() {
+                status.style(colors.green()).to_string()
+            } else if status_code.is_redirection() {
+                status.style(colors.yellow()).to_string()
+            } else {
+                status.style(colors.red()).to_string()
+            };
+            writeln!(f, "{} {:>8}", status, count)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:<10} {:>8}", "Pages", self.pages.style(colors.cyan()))?;
+        writeln!(f, "{:<10} {:>8}", "Links", self.links.style(colors.cyan()))?;
+        writeln!(
+            f,
+            "{:<10} {:>8}",
+            "Failures",
+            self.failures.style(colors.red())
+        )?;
+        write!(
+            f,
+            "{:<10} {:>8}",
+            "Elapsed",
+            format!("{:.2}s", self.elapsed.as_secs_f64()).style(colors.cyan())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, convert::Infallible, time::Duration};
+
+    use reqwest::StatusCode;
+    use spider_crab::{CrawlerBuilder, TowerVisitor};
+    use url::Url;
+
+    use super::CrawlSummary;
+    use crate::{cli::ColorChoice, color::Colors};
+
+    #[tokio::test]
+    async fn test_crawl_summary() -> anyhow::Result<()> {
+        let service = tower::service_fn(|request: http::Request<String>| async move {
+            let (status, body) = match request.uri().path() {
+                "/" => (200, r#"<a href="/about"></a> <a href="/missing"></a>"#),
+                "/about" => (200, r#"<a href="/"></a>"#),
+                _ => (404, ""),
+            };
+            Ok::<_, Infallible>(
+                http::Response::builder()
+                    .status(status)
+                    .body(body.to_string())
+                    .expect("Failed to build response"),
+            )
+        });
+        let all_pages = CrawlerBuilder::new(TowerVisitor::new(service))
+            .build()
+            .crawl(Url::parse("http://localhost")?)
+            .await;
+
+        let summary = CrawlSummary::new(
+            &all_pages,
+            Duration::from_millis(1500),
+            Colors::new(ColorChoice::Never, true),
+        );
+
+        assert_eq!(
+            summary.status_counts,
+            BTreeMap::from([(StatusCode::OK, 2), (StatusCode::NOT_FOUND, 1)])
+        );
+        assert_eq!(summary.pages, 3);
+        assert_eq!(summary.links, 3);
+        assert_eq!(summary.failures, 0);
+
+        let output = summary.to_string();
+        assert!(output.contains("404"));
+        assert!(output.contains("1.50s"));
+        assert!(!output.contains('\x1b'));
+
+        Ok(())
+    }
+}
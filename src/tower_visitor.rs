@@ -0,0 +1,107 @@
+use std::future::poll_fn;
+
+use http::{
+    header::{CONTENT_TYPE, IF_NONE_MATCH},
+    Method, Request, Response,
+};
+use http_body::Body;
+use http_body_util::BodyExt;
+use tower_service::Service;
+use url::Url;
+
+use crate::{crawler::VisitorError, PageContent, SiteVisitor};
+
+/// A [SiteVisitor] that sends requests to a [tower](https://docs.rs/tower) [Service], such as an
+/// in-process Axum router, instead of over the network.
+///
+/// Each visit clones the service and sends it a `GET` request for the full URL with an empty body,
+/// or a `HEAD` request when only the headers are needed.
+#[derive(Clone, Debug)]
+pub struct TowerVisitor<S> {
+    service: S,
+}
+
+impl<S> TowerVisitor<S> {
+    pub fn new(service: S) -> Self {
+        Self { service }
+    }
+}
+
+impl<S, B> TowerVisitor<S>
+where
+    S: Service<Request<String>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: std::error::Error + Send + Sync + 'static,
+    B: Body + Send,
+    B::Data: Send,
+    B::Error: std::error::Error + Send + Sync + 'static,
+{
+    /// Send a request for `url` with `method`, with an `If-None-Match` header if `etag` is given.
+    async fn send(
+        &mut self,
+        method: Method,
+        url: Url,
+        etag: Option<&str>,
+    ) -> Result<PageContent, VisitorError> {
+        let mut request = Request::builder().method(method).uri(url.as_str());
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let request = request
+            .body(String::new())
+            .map_err(|e| VisitorError::Request(e.into()))?;
+
+        let mut service = self.service.clone();
+        poll_fn(|cx| service.poll_ready(cx))
+            .await
+            .map_err(|e| VisitorError::Request(e.into()))?;
+        let response = service
+            .call(request)
+            .await
+            .map_err(|e| VisitorError::Request(e.into()))?;
+
+        let (parts, body) = response.into_parts();
+        let content_type = parts.headers.get(CONTENT_TYPE).cloned();
+
+        let body = body
+            .collect()
+            .await
+            .map_err(|e| VisitorError::Request(e.into()))?
+            .to_bytes();
+
+        Ok(PageContent {
+            url,
+            status_code: parts.status,
+            content: String::from_utf8_lossy(&body).into_owned(),
+            content_type,
+            headers: parts.headers,
+            http_version: Some(parts.version),
+        })
+    }
+}
+
+impl<S, B> SiteVisitor for TowerVisitor<S>
+where
+    S: Service<Request<String>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: std::error::Error + Send + Sync + 'static,
+    B: Body + Send,
+    B::Data: Send,
+    B::Error: std::error::Error + Send + Sync + 'static,
+{
+    async fn visit(&mut self, url: Url) -> Result<PageContent, VisitorError> {
+        self.send(Method::GET, url, None).await
+    }
+
+    async fn visit_if_none_match(
+        &mut self,
+        url: Url,
+        etag: &str,
+    ) -> Result<PageContent, VisitorError> {
+        self.send(Method::GET, url, Some(etag)).await
+    }
+
+    async fn head(&mut self, url: Url) -> Result<PageContent, VisitorError> {
+        self.send(Method::HEAD, url, None).await
+    }
+}
@@ -1,8 +1,14 @@
-use http::HeaderValue;
-use spider_crab::{CrawlerBuilder, PageContent, SiteVisitor, VisitorError};
+use http::{HeaderMap, HeaderValue};
+use spider_crab::{
+    AllPages, BrokenLink, Clock, ContentKind, CrawlConfig, CrawlError, CrawlEvent, CrawlStrategy,
+    CrawlerBuilder, DownloadCheck, EtagCache, FailureReason, ManifestEntry, MemoryReader,
+    Normalize, PageContent, ParityReport, ReportOptions, ScopeMode, SiteVisitor,
+    SitemapCoverageReport, SkipReason, StatsSnapshot, TokioClock, TowerVisitor, VisitorError,
+};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     sync::{Arc, RwLock},
+    time::{Duration, Instant, SystemTime},
 };
 use url::Url;
 
@@ -33,24 +39,32 @@ impl SiteVisitor for MockUrlVisitor {
                 status_code: reqwest::StatusCode::OK,
                 url,
                 content_type: Some(content_type),
+                headers: HeaderMap::new(),
+                http_version: None,
             },
             "https://monzo.com/about" => PageContent {
                 content: r#"<a href="/about"></a> <a href="/cost"></a>"#.into(),
                 status_code: reqwest::StatusCode::ACCEPTED,
                 url,
                 content_type: Some(content_type),
+                headers: HeaderMap::new(),
+                http_version: None,
             },
             "https://monzo.com/cost" => PageContent {
                 content: r#"<a href="/cost-inner"></a>"#.into(),
                 status_code: reqwest::StatusCode::OK,
                 url,
                 content_type: Some(content_type),
+                headers: HeaderMap::new(),
+                http_version: None,
             },
             "https://monzo.com/cost-inner" => PageContent {
                 content: r#"<p></p>"#.into(),
                 status_code: reqwest::StatusCode::OK,
                 url,
                 content_type: Some(content_type),
+                headers: HeaderMap::new(),
+                http_version: None,
             },
             _ => panic!("Unexpected URL: {}", url),
         };
@@ -80,6 +94,156 @@ impl MockUrlVisitor {
     }
 }
 
+/// A mock visitor that serves fixed HTML for each URL and records when each URL was visited.
+/// URLs without a route return a 404 with an empty body.
+#[derive(Clone)]
+struct RoutedVisitor {
+    routes: Arc<HashMap<Url, String>>,
+    redirects: Arc<HashMap<Url, Url>>,
+    hanging: Arc<HashSet<Url>>,
+    content_types: Arc<HashMap<Url, String>>,
+    delay: Duration,
+    visits: Arc<RwLock<Vec<(Url, Instant)>>>,
+}
+
+impl RoutedVisitor {
+    fn new(routes: &[(&str, &str)]) -> Self {
+        let routes = routes
+            .iter()
+            .map(|&(url, content)| {
+                (
+                    Url::parse(url).expect("Failed to parse URL."),
+                    content.to_string(),
+                )
+            })
+            .collect();
+
+        Self {
+            routes: Arc::new(routes),
+            redirects: Arc::new(HashMap::new()),
+            hanging: Arc::new(HashSet::new()),
+            content_types: Arc::new(HashMap::new()),
+            delay: Duration::ZERO,
+            visits: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// A copy that records its own visits, rather than sharing them with the original.
+    fn with_fresh_visits(mut self) -> Self {
+        self.visits = Arc::new(RwLock::new(Vec::new()));
+        self
+    }
+
+    /// Visits to the first URL of each pair land on the second, as if redirected.
+    fn with_redirects(mut self, redirects: &[(&str, &str)]) -> Self {
+        self.redirects = Arc::new(
+            redirects
+                .iter()
+                .map(|&(from, to)| {
+                    (
+                        Url::parse(from).expect("Failed to parse URL."),
+                        Url::parse(to).expect("Failed to parse URL."),
+                    )
+                })
+                .collect(),
+        );
+        self
+    }
+
+    /// Visits to these URLs never complete.
+    fn with_hanging(mut self, hanging: &[&str]) -> Self {
+        self.hanging = Arc::new(urls(hanging));
+        self
+    }
+
+    /// Every visit takes at least `delay` to complete.
+    fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Serve these URLs with a `Content-Type` other than `text/html`.
+    fn with_content_types(mut self, content_types: &[(&str, &str)]) -> Self {
+        self.content_types = Arc::new(
+            content_types
+                .iter()
+                .map(|&(url, content_type)| {
+                    (
+                        Url::parse(url).expect("Failed to parse URL."),
+                        content_type.to_string(),
+                    )
+                })
+                .collect(),
+        );
+        self
+    }
+
+    fn visited_urls(&self) -> HashSet<Url> {
+        let visits = self.visits.read().expect("Could not acquire lock");
+        visits.iter().map(|(url, _)| url.clone()).collect()
+    }
+
+    /// URLs in the order their visits started.
+    fn visit_order(&self) -> Vec<Url> {
+        let visits = self.visits.read().expect("Could not acquire lock");
+        visits.iter().map(|(url, _)| url.clone()).collect()
+    }
+
+    /// Times at which visits started, in order.
+    fn visit_times(&self) -> Vec<Instant> {
+        let visits = self.visits.read().expect("Could not acquire lock");
+        let mut times: Vec<Instant> = visits.iter().map(|(_, time)| *time).collect();
+        times.sort();
+        times
+    }
+}
+
+impl SiteVisitor for RoutedVisitor {
+    async fn visit(&mut self, url: Url) -> Result<PageContent, VisitorError> {
+        self.visits
+            .write()
+            .expect("Could not acquire lock")
+            .push((url.clone(), Instant::now()));
+
+        if self.hanging.contains(&url) {
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(self.delay).await;
+
+        let url = self.redirects.get(&url).cloned().unwrap_or(url);
+        let (status_code, content) = match self.routes.get(&url) {
+            Some(content) => (reqwest::StatusCode::OK, content.clone()),
+            None => (reqwest::StatusCode::NOT_FOUND, String::new()),
+        };
+
+        let content_type = self
+            .content_types
+            .get(&url)
+            .map_or("text/html", String::as_str)
+            .parse()
+            .expect("Failed to parse header");
+
+        Ok(PageContent {
+            url,
+            status_code,
+            content,
+            content_type: Some(content_type),
+            headers: HeaderMap::new(),
+            http_version: None,
+        })
+    }
+}
+
+fn urls(urls: &[&str]) -> HashSet<Url> {
+    urls.iter()
+        .map(|&url| Url::parse(url).expect("Failed to parse URL."))
+        .collect()
+}
+
+fn page_urls(pages: &AllPages) -> HashSet<Url> {
+    pages.pages.iter().map(|page| page.url.clone()).collect()
+}
+
 #[tokio::test]
 async fn test_visitor() -> anyhow::Result<()> {
     // Expect the crawler to visit these URLs
@@ -103,7 +267,7 @@ async fn test_visitor() -> anyhow::Result<()> {
     let visited_pages = crawler.crawl(root_url).await;
 
     let visited_urls = visited_pages
-        .0
+        .pages
         .iter()
         .map(|page| page.url.clone())
         .collect::<HashSet<Url>>();
@@ -150,7 +314,7 @@ Disallow: /cost-inner";
     let visited_pages = crawler.crawl(root_url).await;
 
     let visited_urls = visited_pages
-        .0
+        .pages
         .iter()
         .map(|page| page.url.clone())
         .collect::<HashSet<Url>>();
@@ -164,7 +328,2533 @@ Disallow: /cost-inner";
     // And: The mock visitor reports that it visited each URL exactly once
     assert!(mock_visitor.visited_urls_once());
 
-    println!("Visited pages\n{:?}", visited_pages.0);
+    println!("Visited pages\n{:?}", visited_pages.pages);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_host_robots() -> anyhow::Result<()> {
+    // Given: Two sites, each disallowing a different page in its robots.txt
+    let links = r#"<a href="/private"></a> <a href="/public"></a>"#;
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/", links),
+        ("https://monzo.com/private", "<p></p>"),
+        ("https://monzo.com/public", "<p></p>"),
+        ("https://help.monzo.com/", links),
+        ("https://help.monzo.com/private", "<p></p>"),
+        ("https://help.monzo.com/public", "<p></p>"),
+    ]);
+    let robots = [
+        ("monzo.com", "User-Agent: *\nDisallow: /private"),
+        ("help.monzo.com", "User-Agent: *\nDisallow: /public"),
+    ];
+
+    // When: We crawl both with each host's robots.txt
+    CrawlerBuilder::new(visitor.clone())
+        .with_host_robots(robots, "test-agent")?
+        .build()
+        .crawl_seeds([
+            Url::parse("https://monzo.com/")?,
+            Url::parse("https://help.monzo.com/")?,
+        ])
+        .await;
+
+    // Then: Each host's pages are only checked against its own robots.txt
+    assert_eq!(
+        visitor.visited_urls(),
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/public",
+            "https://help.monzo.com/",
+            "https://help.monzo.com/private",
+        ])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_strict_politeness() -> anyhow::Result<()> {
+    let robots_txt = "User-Agent: *
+Disallow: /private
+Crawl-delay: 0.2";
+
+    // Given: A site with a disallowed path, a nofollow link and a noindex/nofollow page
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/private"></a>
+               <a href="/sponsored" rel="sponsored nofollow"></a>
+               <a href="/about"></a>
+               <a href="/hidden"></a>"#,
+        ),
+        ("https://monzo.com/about", "<p></p>"),
+        (
+            "https://monzo.com/hidden",
+            r#"<meta name="robots" content="noindex, nofollow"><a href="/secret"></a>"#,
+        ),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_robot(robots_txt, "test-agent")?
+        .with_strict_politeness()
+        .build();
+
+    // When: We crawl in strict politeness mode
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: Disallowed, nofollow and meta nofollow links are not visited
+    assert_eq!(
+        visitor.visited_urls(),
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/about",
+            "https://monzo.com/hidden"
+        ])
+    );
+
+    // And: The noindex page is not reported
+    assert_eq!(
+        page_urls(&pages),
+        urls(&["https://monzo.com/", "https://monzo.com/about"])
+    );
+
+    // And: Requests were spaced by the crawl delay
+    let times = visitor.visit_times();
+    for pair in times.windows(2) {
+        assert!(pair[1] - pair[0] >= Duration::from_millis(190));
+    }
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_max_time_with_paused_clock() -> anyhow::Result<()> {
+    // Given: A chain of 20 pages, each taking 10 seconds to visit
+    let routes: Vec<(String, String)> = (0..20)
+        .map(|i| {
+            (
+                format!("https://monzo.com/{i}"),
+                format!(r#"<a href="/{}"></a>"#, i + 1),
+            )
+        })
+        .collect();
+    let routes: Vec<(&str, &str)> = routes
+        .iter()
+        .map(|(url, content)| (url.as_str(), content.as_str()))
+        .collect();
+    let visitor = RoutedVisitor::new(&routes).with_delay(Duration::from_secs(10));
+
+    // And: A crawler limited to 45 seconds, using Tokio's paused clock
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_clock(Arc::new(TokioClock::new()))
+        .with_max_time(45)
+        .build();
+
+    // When: We crawl the chain, with the clock advancing as visits wait
+    let start = Instant::now();
+    let pages = crawler.crawl(Url::parse("https://monzo.com/0")?).await;
+
+    // Then: The crawl stops once 45 seconds have passed on the clock, without waiting in real time
+    // for the visit still in flight
+    assert_eq!(pages.pages.len(), 4);
+    assert_eq!(pages.skipped.get(&SkipReason::LimitReached), Some(&1));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_fetched_at() -> anyhow::Result<()> {
+    // Given: A chain of three pages, each taking 10 seconds to visit
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/0", r#"<a href="/1"></a>"#),
+        ("https://monzo.com/1", r#"<a href="/2"></a>"#),
+        ("https://monzo.com/2", "<p></p>"),
+    ])
+    .with_delay(Duration::from_secs(10));
+    let clock = Arc::new(TokioClock::new());
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_clock(clock.clone())
+        .build();
+
+    // When: We crawl the chain
+    let start = clock.now();
+    let mut pages = crawler
+        .crawl(Url::parse("https://monzo.com/0")?)
+        .await
+        .pages;
+    pages.sort_by_key(|page| page.url.clone());
+
+    // Then: Each page is stamped with when its response arrived
+    let fetched_after: Vec<Duration> = pages
+        .iter()
+        .map(|page| page.fetched_at.duration_since(start))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        fetched_after,
+        [10, 20, 30].map(Duration::from_secs).to_vec()
+    );
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_manifest() -> anyhow::Result<()> {
+    // Given: A site with a page and a broken link
+    let home = r#"<a href="/missing"></a>"#;
+    let visitor = RoutedVisitor::new(&[("https://monzo.com/", home)]);
+    let clock = Arc::new(TokioClock::new());
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_clock(clock.clone())
+        .build();
+
+    // When: We crawl the site and export its manifest
+    let pages = crawler.crawl(Url::parse("https://monzo.com/")?).await;
+    let manifest = pages.to_manifest();
+
+    // Then: Each visited page is listed with its status, hash, size and fetch time
+    let fetched_at = clock
+        .now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+    let home_page = pages
+        .pages
+        .iter()
+        .find(|page| page.url.path() == "/")
+        .ok_or(anyhow::anyhow!("Home page was not visited"))?;
+    assert_eq!(
+        manifest.pages,
+        [
+            ManifestEntry {
+                url: "https://monzo.com/".to_string(),
+                status: 200,
+                content_hash: format!("{:016x}", home_page.content_hash),
+                size: home.len(),
+                fetched_at,
+            },
+            ManifestEntry {
+                url: "https://monzo.com/missing".to_string(),
+                status: 404,
+                content_hash: "cbf29ce484222325".to_string(), // The hash of an empty body
+                size: 0,
+                fetched_at,
+            },
+        ]
+    );
+
+    // And: It serializes to a single JSON document
+    let json: serde_json::Value = serde_json::to_value(&manifest)?;
+    assert_eq!(json["pages"][1]["status"], 404);
+    assert_eq!(json["pages"][0]["size"], home.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parity_report() -> anyhow::Result<()> {
+    // Given: A staging site that added a page, dropped one and broke another
+    let staging = RoutedVisitor::new(&[
+        (
+            "https://staging.monzo.com/",
+            r#"<a href="/about"></a> <a href="/careers"></a> <a href="/new"></a>"#,
+        ),
+        ("https://staging.monzo.com/about", ""),
+        ("https://staging.monzo.com/new", ""),
+    ]);
+    let live = RoutedVisitor::new(&[
+        (
+            "https://www.monzo.com/",
+            r#"<a href="/about"></a> <a href="/careers"></a> <a href="/old"></a>"#,
+        ),
+        ("https://www.monzo.com/about", ""),
+        ("https://www.monzo.com/careers", ""),
+        ("https://www.monzo.com/old", ""),
+    ]);
+
+    // When: We crawl both and compare them
+    let staging = CrawlerBuilder::new(staging)
+        .build()
+        .crawl(Url::parse("https://staging.monzo.com/")?)
+        .await;
+    let live = CrawlerBuilder::new(live)
+        .build()
+        .crawl(Url::parse("https://www.monzo.com/")?)
+        .await;
+
+    // Then: Pages are matched by path
+    assert_eq!(
+        staging.parity_report(&live),
+        ParityReport {
+            only_in_a: BTreeSet::from(["/new".to_string()]),
+            only_in_b: BTreeSet::from(["/old".to_string()]),
+            status_differences: BTreeMap::from([(
+                "/careers".to_string(),
+                (reqwest::StatusCode::NOT_FOUND, reqwest::StatusCode::OK)
+            )]),
+        }
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_report() -> anyhow::Result<()> {
+    // Given: A site with a broken link, a sponsored external link, duplicated pages and a page
+    // only linked from a page that is not reported
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/about"></a> <a href="/about-us"></a> <a href="/private"></a>
+               <a href="/missing"></a> <a href="https://github.com/monzo" rel="sponsored"></a>"#,
+        ),
+        ("https://monzo.com/about", "<p>About</p>"),
+        ("https://monzo.com/about-us", "<p>About</p>"),
+        (
+            "https://monzo.com/private",
+            r#"<meta name="robots" content="noindex"> <a href="/hidden"></a>"#,
+        ),
+        ("https://monzo.com/hidden", "<p>Hidden</p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_respect_meta_robots(true)
+        .build();
+
+    // When: We crawl the site and report on it against a sitemap
+    let pages = crawler.crawl(Url::parse("https://monzo.com/")?).await;
+    let report = pages.report(&ReportOptions {
+        sitemap_urls: Some(urls(&["https://monzo.com/", "https://monzo.com/old"])),
+    });
+
+    // Then: Each section is populated
+    assert_eq!(report.stats.pages, 5);
+    assert_eq!(report.stats.links, 4);
+    assert_eq!(report.stats.external_links, 1);
+    assert_eq!(
+        report.stats.status_counts,
+        BTreeMap::from([(200, 4), (404, 1)])
+    );
+    assert_eq!(
+        report.broken_links,
+        [BrokenLink {
+            url: "https://monzo.com/missing".to_string(),
+            status: 404,
+            referrers: vec!["https://monzo.com/".to_string()],
+        }]
+    );
+    assert_eq!(report.external_links, ["https://github.com/monzo"]);
+    assert_eq!(report.sponsored_links, ["https://github.com/monzo"]);
+    assert!(report.ugc_links.is_empty());
+    assert_eq!(report.orphans, ["https://monzo.com/hidden"]);
+    assert_eq!(
+        report.duplicate_content,
+        [["https://monzo.com/about", "https://monzo.com/about-us"]]
+    );
+    assert_eq!(
+        report.sitemap_coverage,
+        Some(SitemapCoverageReport {
+            missing_from_crawl: vec!["https://monzo.com/old".to_string()],
+            missing_from_sitemap: vec![
+                "https://monzo.com/about".to_string(),
+                "https://monzo.com/about-us".to_string(),
+                "https://monzo.com/hidden".to_string(),
+            ],
+        })
+    );
+
+    // And: It serializes to a single JSON document
+    let json: serde_json::Value = serde_json::to_value(&report)?;
+    assert_eq!(json["broken_links"][0]["status"], 404);
+    assert!(json["sitemap_coverage"].is_object());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deadline() -> anyhow::Result<()> {
+    // Given: A chain of 20 pages, each taking 50ms to visit
+    let routes: Vec<(String, String)> = (0..20)
+        .map(|i| {
+            (
+                format!("https://monzo.com/{i}"),
+                format!(r#"<a href="/{}"></a>"#, i + 1),
+            )
+        })
+        .collect();
+    let routes: Vec<(&str, &str)> = routes
+        .iter()
+        .map(|(url, content)| (url.as_str(), content.as_str()))
+        .collect();
+    let visitor = RoutedVisitor::new(&routes).with_delay(Duration::from_millis(50));
+
+    // And: A deadline 200ms from now
+    let start = Instant::now();
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_deadline(SystemTime::now() + Duration::from_millis(200))
+        .build();
+
+    // When: We crawl the chain
+    let pages = crawler.crawl(Url::parse("https://monzo.com/0")?).await;
+
+    // Then: The crawl stops at the deadline, before reaching the end of the chain
+    assert!(!pages.pages.is_empty());
+    assert!(pages.pages.len() < 10);
+    assert!(start.elapsed() < Duration::from_millis(500));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deadline_with_hanging_visits() -> anyhow::Result<()> {
+    // Given: A site linking to two pages that never respond
+    let visitor = RoutedVisitor::new(&[(
+        "https://monzo.com/",
+        r#"<a href="/slow"></a> <a href="/slower"></a>"#,
+    )])
+    .with_hanging(&["https://monzo.com/slow", "https://monzo.com/slower"]);
+
+    // And: A deadline 200ms from now
+    let start = Instant::now();
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_deadline(SystemTime::now() + Duration::from_millis(200))
+        .build();
+
+    // When: We crawl the site
+    let pages = tokio::time::timeout(
+        Duration::from_secs(5),
+        crawler.crawl(Url::parse("https://monzo.com/")?),
+    )
+    .await?;
+
+    // Then: The crawl stops at the deadline without waiting for the hanging visits
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert_eq!(page_urls(&pages), urls(&["https://monzo.com/"]));
+
+    // And: The abandoned visits are counted as skipped
+    assert_eq!(pages.skipped.get(&SkipReason::LimitReached), Some(&2));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seed_list_shares_budgets() -> anyhow::Result<()> {
+    // Given: Two sites, each with three pages
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/about"></a> <a href="/cost"></a>"#,
+        ),
+        ("https://monzo.com/about", "<p>About</p>"),
+        ("https://monzo.com/cost", "<p>Cost</p>"),
+        (
+            "https://help.monzo.com/",
+            r#"<a href="/faq"></a> <a href="/contact"></a>"#,
+        ),
+        ("https://help.monzo.com/faq", "<p>FAQ</p>"),
+        ("https://help.monzo.com/contact", "<p>Contact</p>"),
+    ]);
+    let seeds = || -> anyhow::Result<Vec<Url>> {
+        Ok(vec![
+            Url::parse("https://monzo.com/")?,
+            Url::parse("https://help.monzo.com/")?,
+        ])
+    };
+
+    // When: We crawl both with a limit of four pages
+    let all_pages = CrawlerBuilder::new(visitor.clone().with_fresh_visits())
+        .with_max_pages(4)
+        .build()
+        .crawl_seeds(seeds()?)
+        .await;
+
+    // Then: The limit is shared between the seeds, rather than allowing four pages for each
+    assert_eq!(all_pages.pages.len(), 4);
+    assert_eq!(all_pages.report(&ReportOptions::default()).stats.pages, 4);
+
+    // When: We crawl both with a limit of one byte
+    let all_pages = CrawlerBuilder::new(visitor.with_fresh_visits())
+        .with_max_bytes(1)
+        .build()
+        .crawl_seeds(seeds()?)
+        .await;
+
+    // Then: The crawl stops after the first page of either seed
+    assert_eq!(all_pages.pages.len(), 1);
+
+    Ok(())
+}
+
+/// A site three links deep, with two branches from the home page.
+fn tree_site() -> RoutedVisitor {
+    RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/a"></a> <a href="/b"></a>"#,
+        ),
+        ("https://monzo.com/a", r#"<a href="/a/1"></a>"#),
+        ("https://monzo.com/a/1", r#"<a href="/a/1/x"></a>"#),
+        ("https://monzo.com/a/1/x", "<p></p>"),
+        ("https://monzo.com/b", r#"<a href="/b/1"></a>"#),
+        ("https://monzo.com/b/1", "<p></p>"),
+    ])
+}
+
+/// The number of path segments of a page in [tree_site], which is its depth from the home page.
+fn tree_depth(url: &Url) -> usize {
+    url.path_segments()
+        .map(|segments| segments.filter(|segment| !segment.is_empty()).count())
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_breadth_first_strategy() -> anyhow::Result<()> {
+    // Given: A site three links deep
+    let visitor = tree_site().with_delay(Duration::from_millis(10));
+
+    // When: We crawl it breadth first
+    let all_pages = CrawlerBuilder::new(visitor.clone())
+        .with_strategy(CrawlStrategy::BreadthFirst)
+        .build()
+        .crawl(Url::parse("https://monzo.com/")?)
+        .await;
+
+    // Then: Every page is visited, each depth before the next
+    assert_eq!(all_pages.pages.len(), 6);
+    let depths: Vec<usize> = visitor.visit_order().iter().map(tree_depth).collect();
+    assert!(depths.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    // When: We crawl it breadth first with a limit of three pages
+    let all_pages = CrawlerBuilder::new(visitor.with_fresh_visits())
+        .with_strategy(CrawlStrategy::BreadthFirst)
+        .with_max_pages(3)
+        .build()
+        .crawl(Url::parse("https://monzo.com/")?)
+        .await;
+
+    // Then: The shallowest pages are the ones visited
+    assert_eq!(
+        page_urls(&all_pages),
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/a",
+            "https://monzo.com/b"
+        ])
+    );
+
+    // And: The page left in the frontier is counted as skipped
+    assert_eq!(all_pages.skipped.get(&SkipReason::LimitReached), Some(&1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_strategy_retries() -> anyhow::Result<()> {
+    // Given: A site three links deep, where the first visit to /a fails to connect
+    // And: Every visit takes 10ms to respond
+    let in_flight: Arc<RwLock<(usize, usize)>> = Arc::default();
+    let visits: Arc<RwLock<Vec<Url>>> = Arc::default();
+    let service = {
+        let in_flight = in_flight.clone();
+        let visits = visits.clone();
+        tower::service_fn(move |request: http::Request<String>| {
+            let url = Url::parse(&request.uri().to_string());
+            let in_flight = in_flight.clone();
+            let visits = visits.clone();
+            async move {
+                let url = url.map_err(std::io::Error::other)?;
+                let attempt = {
+                    let mut visits = visits.write().expect("Could not acquire lock");
+                    visits.push(url.clone());
+                    visits.iter().filter(|visit| **visit == url).count()
+                };
+                {
+                    let mut in_flight = in_flight.write().expect("Could not acquire lock");
+                    in_flight.0 += 1;
+                    in_flight.1 = in_flight.1.max(in_flight.0);
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.write().expect("Could not acquire lock").0 -= 1;
+
+                let content = match url.path() {
+                    "/" => r#"<a href="/a"></a> <a href="/b"></a>"#,
+                    "/a" if attempt == 1 => {
+                        return Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+                    }
+                    "/a" => r#"<a href="/a/1"></a>"#,
+                    "/a/1" => r#"<a href="/a/1/x"></a>"#,
+                    "/b" => r#"<a href="/b/1"></a>"#,
+                    _ => "<p></p>",
+                };
+                Ok(http::Response::new(content.to_string()))
+            }
+        })
+    };
+
+    // When: We crawl it breadth first, two pages at a time, with page retries
+    let all_pages = CrawlerBuilder::new(TowerVisitor::new(service))
+        .with_strategy(CrawlStrategy::BreadthFirst)
+        .with_max_in_flight(2)
+        .with_page_retry(3, Duration::from_millis(10))
+        .build()
+        .crawl(Url::parse("https://monzo.com/")?)
+        .await;
+
+    // Then: Every page is visited, with /a retried before any deeper page
+    assert_eq!(all_pages.pages.len(), 6);
+    let visits = visits.read().expect("Could not acquire lock");
+    assert_eq!(visits.len(), 7);
+    let depths: Vec<usize> = visits.iter().map(tree_depth).collect();
+    assert!(depths.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    // And: No more than two visits were ever in flight
+    assert!(in_flight.read().expect("Could not acquire lock").1 <= 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_depth_first_strategy() -> anyhow::Result<()> {
+    // Given: A site three links deep
+    let visitor = tree_site();
+
+    // When: We crawl it depth first, one page at a time
+    let all_pages = CrawlerBuilder::new(visitor.clone())
+        .with_strategy(CrawlStrategy::DepthFirst)
+        .with_max_in_flight(1)
+        .build()
+        .crawl(Url::parse("https://monzo.com/")?)
+        .await;
+
+    // Then: Each branch is followed to its end before the next is started
+    assert_eq!(all_pages.pages.len(), 6);
+    let order: Vec<String> = visitor
+        .visit_order()
+        .iter()
+        .map(|url| url.path().to_string())
+        .collect();
+    assert!(
+        order == ["/", "/a", "/a/1", "/a/1/x", "/b", "/b/1"]
+            || order == ["/", "/b", "/b/1", "/a", "/a/1", "/a/1/x"],
+        "Unexpected visit order {:?}",
+        order
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_tracked_urls() -> anyhow::Result<()> {
+    // Given: 30 pages that all link to each other
+    let links: String = (0..30).map(|i| format!(r#"<a href="/{i}"></a>"#)).collect();
+    let routes: Vec<(String, String)> = (0..30)
+        .map(|i| (format!("https://monzo.com/{i}"), links.clone()))
+        .collect();
+    let routes: Vec<(&str, &str)> = routes
+        .iter()
+        .map(|(url, content)| (url.as_str(), content.as_str()))
+        .collect();
+    let visitor = RoutedVisitor::new(&routes);
+
+    // And: A crawler tracking at most 10 URLs
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_max_tracked_urls(10)
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com/0")?).await;
+
+    // Then: The crawl finishes having visited only the URLs it tracked, dropping the rest
+    assert_eq!(visitor.visited_urls().len(), 10);
+    assert_eq!(pages.pages.len(), 10);
+    assert!(pages.skipped[&SkipReason::FrontierFull] > 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_tracked_urls_with_redirects() -> anyhow::Result<()> {
+    // Given: A page redirecting to a page that links to itself
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/", r#"<a href="/old"></a>"#),
+        ("https://monzo.com/new", r#"<a href="/new"></a>"#),
+    ])
+    .with_redirects(&[("https://monzo.com/old", "https://monzo.com/new")]);
+
+    // And: A crawler tracking at most 2 URLs
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_max_tracked_urls(2)
+        .build();
+
+    // When: We crawl the site, filling the visited set before the redirect lands
+    let pages = crawler.crawl(Url::parse("https://monzo.com/")?).await;
+
+    // Then: The redirect target is not tracked beyond the limit, so the link to it is dropped
+    assert_eq!(
+        visitor.visited_urls(),
+        urls(&["https://monzo.com/", "https://monzo.com/old"])
+    );
+    assert_eq!(pages.pages.len(), 2);
+    assert_eq!(pages.skipped.get(&SkipReason::FrontierFull), Some(&1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_tracked_urls_bounds_aliases_and_external_links() -> anyhow::Result<()> {
+    // Given: A page with 20 links that normalize to other URLs, and 20 external links
+    let links: String = (0..20)
+        .map(|i| format!(r#"<a href="/%7E{i}"></a> <a href="https://github.com/monzo/{i}"></a>"#))
+        .collect();
+    let visitor = RoutedVisitor::new(&[("https://monzo.com/", &links)]);
+
+    // And: A crawler tracking at most 5 URLs
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_encoding_normalization(true)
+        .with_max_tracked_urls(5)
+        .build();
+    let mut rx = crawler.subscribe_external_links();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com/")?).await;
+
+    // Then: Aliases are only recorded for tracked URLs, and only 5 external URLs are sent
+    assert_eq!(pages.aliases.len(), 4);
+    let mut external_links = 0;
+    while rx.try_recv().is_ok() {
+        external_links += 1;
+    }
+    assert_eq!(external_links, 5);
+
+    Ok(())
+}
+
+/// A [MemoryReader] reporting a fixed memory usage.
+#[derive(Debug)]
+struct FixedMemoryReader(u64);
+
+impl MemoryReader for FixedMemoryReader {
+    fn resident_bytes(&self) -> Option<u64> {
+        Some(self.0)
+    }
+}
+
+#[tokio::test]
+async fn test_memory_limit() -> anyhow::Result<()> {
+    for (resident_bytes, paused) in [(2_000_000, true), (500_000, false)] {
+        // Given: A home page linking to three pages, each taking 100ms to visit
+        let visitor = RoutedVisitor::new(&[
+            (
+                "https://monzo.com/",
+                r#"<a href="/a"></a> <a href="/b"></a> <a href="/c"></a>"#,
+            ),
+            ("https://monzo.com/a", ""),
+            ("https://monzo.com/b", ""),
+            ("https://monzo.com/c", ""),
+        ])
+        .with_delay(Duration::from_millis(100));
+
+        // And: A crawler with a memory limit of 1MB
+        let crawler = CrawlerBuilder::new(visitor.clone())
+            .with_memory_limit(1_000_000)
+            .with_memory_reader(Arc::new(FixedMemoryReader(resident_bytes)))
+            .build();
+
+        // When: We crawl the site
+        let pages = crawler.crawl(Url::parse("https://monzo.com/")?).await;
+
+        // Then: Every page is still visited
+        assert_eq!(pages.pages.len(), 4);
+
+        // And: Above the limit, each visit is only scheduled once the last one finished
+        let times = visitor.visit_times();
+        let gaps: Vec<Duration> = times.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        if paused {
+            assert!(gaps.iter().all(|gap| *gap >= Duration::from_millis(90)));
+        } else {
+            assert!(gaps[1..].iter().all(|gap| *gap < Duration::from_millis(50)));
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_custom_scheme() -> anyhow::Result<()> {
+    // Given: A site served over a custom scheme, linking to an https page on the same host
+    let visitor = RoutedVisitor::new(&[
+        (
+            "gemini://monzo.com/",
+            r#"<a href="/about"></a> <a href="https://monzo.com/web"></a>"#,
+        ),
+        ("gemini://monzo.com/about", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_allowed_schemes(HashSet::from(["gemini".to_string()]))
+        .build();
+
+    // When: We crawl allowing only the custom scheme
+    let pages = crawler.crawl(Url::parse("gemini://monzo.com/")?).await;
+
+    // Then: Only the custom scheme pages are visited
+    let expected_urls = urls(&["gemini://monzo.com/", "gemini://monzo.com/about"]);
+    assert_eq!(visitor.visited_urls(), expected_urls);
+    assert_eq!(page_urls(&pages), expected_urls);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_allowed_ports() -> anyhow::Result<()> {
+    // Given: A site linking to the same host on another port
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/about"></a> <a href="https://monzo.com:8080/admin"></a>"#,
+        ),
+        ("https://monzo.com/about", "<p></p>"),
+        ("https://monzo.com:8080/admin", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_allowed_ports(HashSet::from([443]))
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The link on the other port is recorded but not visited
+    assert_eq!(
+        visitor.visited_urls(),
+        urls(&["https://monzo.com/", "https://monzo.com/about"])
+    );
+    let home = pages
+        .pages
+        .iter()
+        .find(|page| page.url.path() == "/")
+        .expect("Home page was not crawled");
+    assert!(home
+        .links
+        .contains(&Url::parse("https://monzo.com:8080/admin")?));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_allowed_path_prefixes() -> anyhow::Result<()> {
+    // Given: A site with several sections, including nested and similarly named ones
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/docs"></a> <a href="/blog/"></a> <a href="/about"></a> <a href="/docsearch"></a>"#,
+        ),
+        (
+            "https://monzo.com/docs",
+            r#"<a href="/docs/api/intro"></a>"#,
+        ),
+        ("https://monzo.com/docs/api/intro", "<p></p>"),
+        (
+            "https://monzo.com/blog/",
+            r#"<a href="/blog/2024/hello"></a>"#,
+        ),
+        ("https://monzo.com/blog/2024/hello", "<p></p>"),
+        ("https://monzo.com/about", "<p></p>"),
+        ("https://monzo.com/docsearch", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_allowed_path_prefixes(&["/docs", "/docs/api", "blog/"])
+        .build();
+
+    // When: We crawl from the home page
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: Only the seed and pages under the allowed sections are visited
+    assert_eq!(
+        page_urls(&pages),
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/docs",
+            "https://monzo.com/docs/api/intro",
+            "https://monzo.com/blog/",
+            "https://monzo.com/blog/2024/hello",
+        ])
+    );
+
+    // And: Links outside them are still recorded
+    let home = pages
+        .pages
+        .iter()
+        .find(|page| page.url.path() == "/")
+        .expect("Home page was not crawled");
+    assert!(home.links.contains(&Url::parse("https://monzo.com/about")?));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_abort_url() -> anyhow::Result<()> {
+    // Given: A site where one page never responds
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/slow"></a> <a href="/about"></a>"#,
+        ),
+        ("https://monzo.com/about", "<p></p>"),
+    ])
+    .with_hanging(&["https://monzo.com/slow"]);
+    let crawler = CrawlerBuilder::new(visitor.clone()).build();
+    let control = crawler.control();
+    let crawl = tokio::spawn(crawler.crawl(Url::parse("https://monzo.com")?));
+
+    // When: We abort the hanging URL once it is in flight
+    let slow_url = Url::parse("https://monzo.com/slow")?;
+    while !visitor.visited_urls().contains(&slow_url) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(control.abort_url(&slow_url));
+    let pages = tokio::time::timeout(Duration::from_secs(5), crawl).await??;
+
+    // Then: The rest of the crawl completes
+    assert_eq!(
+        page_urls(&pages),
+        urls(&["https://monzo.com/", "https://monzo.com/about"])
+    );
+
+    // And: The aborted URL is recorded as cancelled
+    assert_eq!(pages.failed.len(), 1);
+    assert_eq!(pages.failed[0].url, slow_url);
+    assert_eq!(pages.failed[0].reason, FailureReason::Cancelled);
+
+    // And: Nothing is left to abort
+    assert!(!control.abort_url(&slow_url));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parse_threads() -> anyhow::Result<()> {
+    // Given: A crawler that parses on a dedicated pool
+    let mock_visitor = MockUrlVisitor::new();
+    let crawler = CrawlerBuilder::new(mock_visitor.clone())
+        .with_parse_threads(2)?
+        .build();
+
+    // When: We crawl the (mock) Monzo website
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The results are the same as parsing on Tokio's blocking pool
+    let default_pages = CrawlerBuilder::new(MockUrlVisitor::new())
+        .build()
+        .crawl(Url::parse("https://monzo.com")?)
+        .await;
+    assert_eq!(page_urls(&pages), page_urls(&default_pages));
+    for page in &pages.pages {
+        let default_page = default_pages
+            .pages
+            .iter()
+            .find(|default_page| default_page.url == page.url)
+            .expect("Page was not crawled");
+        assert_eq!(page.links, default_page.links);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retain_content() -> anyhow::Result<()> {
+    let seed = Url::parse("https://monzo.com")?;
+
+    // By default no content is kept
+    let pages = CrawlerBuilder::new(MockUrlVisitor::new())
+        .build()
+        .crawl(seed.clone())
+        .await;
+    assert!(pages.pages.iter().all(|page| page.content.is_none()));
+
+    // Content is kept when asked for
+    let pages = CrawlerBuilder::new(MockUrlVisitor::new())
+        .with_retain_content(true)
+        .build()
+        .crawl(seed.clone())
+        .await;
+    let cost_inner = pages
+        .pages
+        .iter()
+        .find(|page| page.url.path() == "/cost-inner")
+        .expect("Page was not crawled");
+    assert_eq!(cost_inner.content.as_deref(), Some("<p></p>"));
+
+    // Subscribers and the returned pages both get the content
+    let crawler = CrawlerBuilder::new(MockUrlVisitor::new())
+        .with_retain_content(true)
+        .build();
+    let mut receiver = crawler.subscribe();
+    let pages = crawler.crawl(seed).await;
+    assert!(pages.pages.iter().all(|page| page.content.is_some()));
+    let mut received = 0;
+    while let Ok(page) = receiver.try_recv() {
+        assert!(page.content.is_some());
+        received += 1;
+    }
+    assert_eq!(received, 4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_without_retained_links() -> anyhow::Result<()> {
+    // Given: A crawler that does not retain links
+    let mock_visitor = MockUrlVisitor::new();
+    let crawler = CrawlerBuilder::new(mock_visitor.clone())
+        .with_retain_links(false)
+        .build();
+
+    // When: We crawl the (mock) Monzo website
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: Every page is still visited
+    assert_eq!(pages.pages.len(), 4);
+
+    // And: No page retains its links
+    assert!(pages.pages.iter().all(|page| page.links.is_empty()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_link_interning() -> anyhow::Result<()> {
+    // Given: A site where every page links to every other page
+    let nav = r#"<a href="/">Home</a> <a href="/a">A</a> <a href="/b">B</a>"#;
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/", nav),
+        ("https://monzo.com/a", nav),
+        ("https://monzo.com/b", nav),
+    ]);
+
+    // When: We crawl it with and without link interning
+    let seed = Url::parse("https://monzo.com/")?;
+    let plain = CrawlerBuilder::new(visitor.clone())
+        .build()
+        .crawl(seed.clone())
+        .await;
+    let interned = CrawlerBuilder::new(visitor)
+        .with_link_interning(true)
+        .build()
+        .crawl(seed)
+        .await;
+
+    // Then: The interned links are the same as each page's links without interning
+    assert_eq!(interned.pages.len(), 3);
+    assert!(plain.link_table.is_empty());
+    assert!(interned.pages.iter().all(|page| page.links.is_empty()));
+    for page in plain.pages.iter() {
+        let links: HashSet<Url> = interned
+            .link_table
+            .links(&page.url)
+            .unwrap_or_default()
+            .iter()
+            .map(|link| Url::clone(link))
+            .collect();
+        assert_eq!(links, page.links);
+    }
+
+    // And: Each distinct URL is stored once, shared by every page linking to it
+    assert_eq!(interned.link_table.len(), 3);
+    let link_to_b = |page: &str| -> anyhow::Result<_> {
+        interned
+            .link_table
+            .links(&Url::parse(page)?)
+            .unwrap_or_default()
+            .iter()
+            .find(|link| link.path() == "/b")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No link to /b on {}", page))
+    };
+    assert!(Arc::ptr_eq(
+        &link_to_b("https://monzo.com/")?,
+        &link_to_b("https://monzo.com/a")?
+    ));
+    assert!(Arc::ptr_eq(
+        &link_to_b("https://monzo.com/a")?,
+        &link_to_b("https://monzo.com/b")?
+    ));
+
+    Ok(())
+}
+
+/// Crawl a small site and return each page's URL and status code.
+async fn crawl_inventory(retain_links: bool) -> anyhow::Result<HashMap<Url, reqwest::StatusCode>> {
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/about">About</a> <a href="https://github.com/monzo"></a>"#,
+        ),
+        ("https://monzo.com/about", r#"<a href="/missing"></a>"#),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_retain_links(retain_links)
+        .build();
+
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    assert!(pages.pages.iter().all(|page| retain_links
+        || (page.links.is_empty() && page.anchors.is_empty() && page.external_links.is_empty())));
+
+    Ok(pages
+        .pages
+        .into_iter()
+        .map(|page| (page.url, page.status_code))
+        .collect())
+}
+
+#[tokio::test]
+async fn test_page_inventory_without_retained_links() -> anyhow::Result<()> {
+    // Not retaining links reports the same pages and statuses as a full crawl
+    let inventory = crawl_inventory(false).await?;
+    assert_eq!(inventory, crawl_inventory(true).await?);
+    assert_eq!(
+        inventory,
+        HashMap::from([
+            (Url::parse("https://monzo.com/")?, reqwest::StatusCode::OK),
+            (
+                Url::parse("https://monzo.com/about")?,
+                reqwest::StatusCode::OK
+            ),
+            (
+                Url::parse("https://monzo.com/missing")?,
+                reqwest::StatusCode::NOT_FOUND
+            ),
+        ])
+    );
+
+    Ok(())
+}
+
+/// Crawl a site linking to both `/page` and `/page/`, returning the URLs that were visited.
+async fn crawl_trailing_slash_variants(normalize: Normalize) -> anyhow::Result<HashSet<Url>> {
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/page"></a> <a href="/page/"></a>"#,
+        ),
+        ("https://monzo.com/page", "<p></p>"),
+        ("https://monzo.com/page/", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_trailing_slash_normalization(normalize)
+        .build();
+
+    crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    Ok(visitor.visited_urls())
+}
+
+#[tokio::test]
+async fn test_encoding_normalization() -> anyhow::Result<()> {
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/~user"></a> <a href="/%7Euser"></a> <a href="/%7euser"></a>
+               <a href="https://Monzo.COM/~user"></a>"#,
+        ),
+        ("https://monzo.com/~user", "<p></p>"),
+        ("https://monzo.com/%7Euser", "<p></p>"),
+        ("https://monzo.com/%7euser", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_encoding_normalization(true)
+        .build();
+
+    crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    assert_eq!(
+        visitor.visited_urls(),
+        urls(&["https://monzo.com/", "https://monzo.com/~user"])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sorted_query_dedup() -> anyhow::Result<()> {
+    // Given: A search page linking to itself with its parameters in another order, and to the
+    // next page of results
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/search?q=card&page=2"></a>"#,
+        ),
+        (
+            "https://monzo.com/search?q=card&page=2",
+            r#"<a href="/search?page=2&q=card"></a> <a href="/search?page=3&q=card"></a>"#,
+        ),
+        ("https://monzo.com/search?page=2&q=card", "<p></p>"),
+        ("https://monzo.com/search?page=3&q=card", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_sorted_query_dedup(true)
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The reordered search is not visited again, but other parameter values are
+    // And: The search is reported with its parameters in the order they were linked
+    let expected = urls(&[
+        "https://monzo.com/",
+        "https://monzo.com/search?q=card&page=2",
+        "https://monzo.com/search?page=3&q=card",
+    ]);
+    assert_eq!(visitor.visited_urls(), expected);
+    assert_eq!(page_urls(&pages), expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_file_collapse() -> anyhow::Result<()> {
+    // Given: A site linking to a directory both with and without its index file
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/blog/index.html"></a> <a href="/help/"></a>"#,
+        ),
+        (
+            "https://monzo.com/blog/index.html",
+            r#"<a href="/blog/"></a>"#,
+        ),
+        ("https://monzo.com/blog/", "<p></p>"),
+        (
+            "https://monzo.com/help/",
+            r#"<a href="/help/index.htm"></a>"#,
+        ),
+        ("https://monzo.com/help/index.htm", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_index_file_collapse(&["index.html", "index.htm"])
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: Each directory is visited once, and reported under the form first found
+    let expected = urls(&[
+        "https://monzo.com/",
+        "https://monzo.com/blog/index.html",
+        "https://monzo.com/help/",
+    ]);
+    assert_eq!(visitor.visit_order().len(), 3);
+    assert_eq!(visitor.visited_urls(), expected);
+    assert_eq!(page_urls(&pages), expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_file_collapse_with_trailing_slash_removal() -> anyhow::Result<()> {
+    // Given: A site linking to a directory both with and without its index file
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/dir/"></a> <a href="/dir/index.html"></a>"#,
+        ),
+        ("https://monzo.com/dir", "<p></p>"),
+        ("https://monzo.com/dir/index.html", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_trailing_slash_normalization(Normalize::Remove)
+        .with_index_file_collapse(&["index.html"])
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The directory is visited once
+    assert_eq!(visitor.visit_order().len(), 2);
+    assert_eq!(pages.pages.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_internationalized_domain_dedup() -> anyhow::Result<()> {
+    // Given: A site linking to the same page by the Unicode and punycode forms of its host
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://xn--mnchen-3ya.de/",
+            r#"<a href="https://münchen.de/about"></a> <a href="https://xn--mnchen-3ya.de/about"></a>"#,
+        ),
+        ("https://xn--mnchen-3ya.de/about", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone()).build();
+
+    // When: We crawl the site from its Unicode form
+    let pages = crawler.crawl(Url::parse("https://münchen.de")?).await;
+
+    // Then: Both forms are in scope and the page is visited once
+    let expected = urls(&[
+        "https://xn--mnchen-3ya.de/",
+        "https://xn--mnchen-3ya.de/about",
+    ]);
+    assert_eq!(visitor.visit_order().len(), 2);
+    assert_eq!(page_urls(&pages), expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_trailing_slash_normalization() -> anyhow::Result<()> {
+    // Both variants are one page, visited at whichever URL was linked first
+    let variants = urls(&[
+        "https://monzo.com/",
+        "https://monzo.com/page",
+        "https://monzo.com/page/",
+    ]);
+    for normalize in [Normalize::Add, Normalize::Remove] {
+        let visited = crawl_trailing_slash_variants(normalize).await?;
+        assert_eq!(visited.len(), 2);
+        assert!(visited.is_subset(&variants));
+    }
+    assert_eq!(
+        crawl_trailing_slash_variants(Normalize::Off).await?,
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/page",
+            "https://monzo.com/page/"
+        ])
+    );
+
+    // Normalization only decides which URLs are the same page, not the URL that is requested
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/", r#"<a href="/about"></a>"#),
+        ("https://monzo.com/about", "<p></p>"),
+    ]);
+    CrawlerBuilder::new(visitor.clone())
+        .with_trailing_slash_normalization(Normalize::Add)
+        .build()
+        .crawl(Url::parse("https://monzo.com")?)
+        .await;
+    assert_eq!(
+        visitor.visited_urls(),
+        urls(&["https://monzo.com/", "https://monzo.com/about"])
+    );
+
+    Ok(())
+}
+
+/// Crawl a site linking to an `.aspx` page and a `.pdf` file, returning the pages that were crawled.
+async fn crawl_unknown_extensions(verify_content_type: bool) -> anyhow::Result<HashSet<Url>> {
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/x.aspx"></a> <a href="/report.pdf"></a>"#,
+        ),
+        ("https://monzo.com/x.aspx", r#"<a href="/about"></a>"#),
+        ("https://monzo.com/about", "<p></p>"),
+        ("https://monzo.com/report.pdf", r#"<a href="/secret"></a>"#),
+    ])
+    .with_content_types(&[("https://monzo.com/report.pdf", "application/pdf")]);
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_verify_content_type(verify_content_type)
+        .build();
+
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    Ok(page_urls(&pages))
+}
+
+#[tokio::test]
+async fn test_verify_content_type() -> anyhow::Result<()> {
+    // Pages with an HTML Content-Type are crawled, whatever their extension
+    assert_eq!(
+        crawl_unknown_extensions(true).await?,
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/x.aspx",
+            "https://monzo.com/about"
+        ])
+    );
+
+    // Without verification, URLs are skipped based on their extension alone
+    assert_eq!(
+        crawl_unknown_extensions(false).await?,
+        urls(&["https://monzo.com/"])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_feed_links() -> anyhow::Result<()> {
+    // Given: A site linking to an RSS feed of its blog posts
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/", r#"<a href="/feed"></a>"#),
+        (
+            "https://monzo.com/feed",
+            r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+              <item><link>https://monzo.com/blog/one</link></item>
+              <item><link>/blog/two</link></item>
+              <item><link>https://medium.com/monzo</link></item>
+            </channel></rss>"#,
+        ),
+        ("https://monzo.com/blog/one", "<p></p>"),
+        ("https://monzo.com/blog/two", "<p></p>"),
+    ])
+    .with_content_types(&[("https://monzo.com/feed", "application/rss+xml")]);
+    let crawler = CrawlerBuilder::new(visitor).build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The feed's entries are discovered and crawled
+    assert_eq!(
+        page_urls(&pages),
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/feed",
+            "https://monzo.com/blog/one",
+            "https://monzo.com/blog/two"
+        ])
+    );
+    let feed = pages
+        .pages
+        .iter()
+        .find(|page| page.url.path() == "/feed")
+        .expect("The feed was crawled");
+    assert_eq!(feed.external_links, urls(&["https://medium.com/monzo"]));
+    assert_eq!(feed.content_kind, ContentKind::Feed);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_feed_extension_links() -> anyhow::Result<()> {
+    // Given: A site linking to a feed and a sitemap by their `.xml` URLs
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/feed.xml"></a> <a href="/sitemap.xml"></a>"#,
+        ),
+        (
+            "https://monzo.com/feed.xml",
+            r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+              <item><link>/blog/one</link></item>
+            </channel></rss>"#,
+        ),
+        (
+            "https://monzo.com/sitemap.xml",
+            r#"<urlset><url><loc>https://monzo.com/hidden</loc></url></urlset>"#,
+        ),
+        ("https://monzo.com/blog/one", "<p></p>"),
+    ])
+    .with_content_types(&[
+        ("https://monzo.com/feed.xml", "application/rss+xml"),
+        ("https://monzo.com/sitemap.xml", "application/xml"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor).build();
+
+    // When: We crawl the site without verifying content types
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The feed is visited and its entries crawled, while the sitemap is not parsed as a page
+    assert_eq!(
+        page_urls(&pages),
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/feed.xml",
+            "https://monzo.com/blog/one"
+        ])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_skip_unchanged_subtrees() -> anyhow::Result<()> {
+    // Given: A previous crawl of a site with two sections
+    let before = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/a"></a> <a href="/b"></a>"#,
+        ),
+        ("https://monzo.com/a", r#"<a href="/a/child"></a>"#),
+        ("https://monzo.com/a/child", "<p></p>"),
+        ("https://monzo.com/b", r#"<a href="/b/child"></a>"#),
+        ("https://monzo.com/b/child", "<p></p>"),
+    ]);
+    let manifest = CrawlerBuilder::new(before)
+        .build()
+        .crawl(Url::parse("https://monzo.com")?)
+        .await
+        .content_manifest();
+
+    // And: Since then, only the second section has changed
+    let after = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/a"></a> <a href="/b"></a>"#,
+        ),
+        ("https://monzo.com/a", r#"<a href="/a/child"></a>"#),
+        ("https://monzo.com/a/child", "<p></p>"),
+        (
+            "https://monzo.com/b",
+            r#"<a href="/b/child"></a> <a href="/b/new"></a>"#,
+        ),
+        ("https://monzo.com/b/child", "<p></p>"),
+        ("https://monzo.com/b/new", "<p></p>"),
+    ]);
+
+    // When: We crawl both sections again, skipping unchanged subtrees
+    let crawler = CrawlerBuilder::new(after.clone())
+        .with_prior_manifest(manifest)
+        .with_skip_unchanged_subtrees(true)
+        .build();
+    let pages = crawler
+        .crawl_seeds([
+            Url::parse("https://monzo.com/a")?,
+            Url::parse("https://monzo.com/b")?,
+        ])
+        .await;
+
+    // Then: The children of the unchanged page are not visited, but those of the changed page are
+    let expected = urls(&[
+        "https://monzo.com/a",
+        "https://monzo.com/b",
+        "https://monzo.com/b/child",
+        "https://monzo.com/b/new",
+    ]);
+    assert_eq!(after.visited_urls(), expected);
+    assert_eq!(page_urls(&pages), expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_preexcluded() -> anyhow::Result<()> {
+    // Given: A crawler that excludes a page up front, using a different trailing slash form
+    let mock_visitor = MockUrlVisitor::new();
+    let crawler = CrawlerBuilder::new(mock_visitor.clone())
+        .with_trailing_slash_normalization(Normalize::Remove)
+        .with_preexcluded(urls(&["https://monzo.com/cost-inner/"]))
+        .build();
+
+    // When: We crawl the (mock) Monzo website
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The excluded page is never visited, even though it is linked to
+    let expected = urls(&[
+        "https://monzo.com/",
+        "https://monzo.com/about",
+        "https://monzo.com/cost",
+    ]);
+    assert_eq!(mock_visitor.visited_urls(), expected);
+    assert_eq!(page_urls(&pages), expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parse_timeout() -> anyhow::Result<()> {
+    // Given: A page with a huge number of links and a crawler with a tiny parse timeout
+    let huge_page = (0..20_000)
+        .map(|i| format!(r#"<div><a href="/page-{i}">Page {i}</a></div>"#))
+        .collect::<String>();
+    let visitor = RoutedVisitor::new(&[("https://monzo.com/", &huge_page)]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_parse_timeout(Duration::from_millis(1))
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The page is reported as timed out, with no links
+    assert_eq!(pages.pages.len(), 1);
+    assert!(pages.pages[0].parse_timed_out);
+    assert!(pages.pages[0].links.is_empty());
+
+    // And: No links were followed
+    assert_eq!(visitor.visited_urls(), urls(&["https://monzo.com/"]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aliases() -> anyhow::Result<()> {
+    // Given: A site linking to a page with a percent-encoded `~`, crawled with encoding normalized
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/", r#"<a href="/%7Eabout"></a>"#),
+        ("https://monzo.com/~about", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_encoding_normalization(true)
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The linked URL is recorded as an alias of the page it was crawled as
+    assert_eq!(
+        pages.aliases,
+        HashMap::from([(
+            Url::parse("https://monzo.com/%7Eabout")?,
+            Url::parse("https://monzo.com/~about")?
+        )])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seed_not_looking_like_html() -> anyhow::Result<()> {
+    // Given: A seed URL whose path does not look like an HTML page
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/index.pdf", r#"<a href="/about"></a>"#),
+        ("https://monzo.com/about", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor).build();
+
+    // When: We crawl from it
+    let pages = crawler
+        .crawl(Url::parse("https://monzo.com/index.pdf")?)
+        .await;
+
+    // Then: The seed is still visited, and its links followed
+    assert_eq!(
+        page_urls(&pages),
+        urls(&["https://monzo.com/index.pdf", "https://monzo.com/about"])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_disallowed_seed() -> anyhow::Result<()> {
+    // Given: A crawler whose robots.txt disallows the seed URL
+    let visitor = RoutedVisitor::new(&[("https://monzo.com/", "<p></p>")]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_robot("User-Agent: *\nDisallow: /", "test-agent")?
+        .build();
+
+    // When: We crawl from it
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: Nothing is visited, and the seed is reported as disallowed
+    assert!(visitor.visited_urls().is_empty());
+    assert!(pages.pages.is_empty());
+    assert_eq!(pages.failed.len(), 1);
+    assert_eq!(pages.failed[0].reason, FailureReason::Disallowed);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_try_crawl_errors() -> anyhow::Result<()> {
+    let seed = Url::parse("https://monzo.com/")?;
+    let visitor = RoutedVisitor::new(&[("https://monzo.com/", "<p></p>")]);
+
+    // A crawl with no seeds cannot start
+    let result = CrawlerBuilder::new(visitor.clone())
+        .build()
+        .try_crawl_seeds([])
+        .await;
+    assert!(matches!(result, Err(CrawlError::InvalidConfiguration(_))));
+
+    // Nor can a crawl limited to no pages
+    let result = CrawlerBuilder::new(visitor.clone())
+        .with_max_pages(0)
+        .build()
+        .try_crawl(seed.clone())
+        .await;
+    assert!(matches!(result, Err(CrawlError::InvalidConfiguration(_))));
+
+    // A seed disallowed by robots.txt is fatal
+    let result = CrawlerBuilder::new(visitor.clone())
+        .with_robot("User-Agent: *\nDisallow: /", "test-agent")?
+        .build()
+        .try_crawl(seed.clone())
+        .await;
+    assert_eq!(
+        result.err(),
+        Some(CrawlError::SeedDisallowed { url: seed.clone() })
+    );
+
+    // As is a seed that cannot be reached
+    let unreachable = tower::service_fn(|_: http::Request<String>| async {
+        Err::<http::Response<String>, _>(std::io::Error::other("connection refused"))
+    });
+    let result = CrawlerBuilder::new(TowerVisitor::new(unreachable))
+        .build()
+        .try_crawl(seed.clone())
+        .await;
+    assert!(matches!(
+        result,
+        Err(CrawlError::SeedUnreachable {
+            reason: FailureReason::Visitor(_),
+            ..
+        })
+    ));
+
+    // A crawl that visits its seed succeeds
+    let pages = CrawlerBuilder::new(visitor).build().try_crawl(seed).await?;
+    assert_eq!(pages.pages.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_page_retry() -> anyhow::Result<()> {
+    // Given: A page that fails to connect on its first visit, and one that always fails with an
+    // error that retrying will not fix
+    let visits: Arc<RwLock<HashMap<String, u32>>> = Arc::default();
+    let service = {
+        let visits = visits.clone();
+        tower::service_fn(move |request: http::Request<String>| {
+            let path = request.uri().path().to_string();
+            let attempt = {
+                let mut visits = visits.write().expect("Could not acquire lock");
+                let attempt = visits.entry(path.clone()).or_insert(0);
+                *attempt += 1;
+                *attempt
+            };
+            async move {
+                match (path.as_str(), attempt) {
+                    ("/", _) => Ok(http::Response::new(
+                        r#"<a href="/flaky"></a> <a href="/broken"></a>"#.to_string(),
+                    )),
+                    ("/flaky", 1) => Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset)),
+                    ("/flaky", _) => Ok(http::Response::new("<p></p>".to_string())),
+                    _ => Err(std::io::Error::other("invalid response")),
+                }
+            }
+        })
+    };
+
+    // When: Crawling with page retries
+    let all_pages = CrawlerBuilder::new(TowerVisitor::new(service))
+        .with_page_retry(3, Duration::from_millis(10))
+        .build()
+        .crawl(Url::parse("https://monzo.com/")?)
+        .await;
+
+    // Then: The flaky page succeeds on its second attempt, and the broken page is not retried
+    let flaky = Url::parse("https://monzo.com/flaky")?;
+    assert!(all_pages
+        .pages
+        .iter()
+        .any(|page| page.url == flaky && page.status_code.is_success()));
+    assert_eq!(all_pages.failed.len(), 1);
+    assert_eq!(
+        all_pages.failed[0].url,
+        Url::parse("https://monzo.com/broken")?
+    );
+
+    let visits = visits.read().expect("Could not acquire lock");
+    assert_eq!(visits.get("/flaky"), Some(&2));
+    assert_eq!(visits.get("/broken"), Some(&1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_host_failure_threshold() -> anyhow::Result<()> {
+    // Given: A host where every page but the home page fails to connect, and a page that takes
+    // 100ms to respond and then links to another page on the failing host
+    let service = tower::service_fn(|request: http::Request<String>| async move {
+        match (request.uri().host(), request.uri().path()) {
+            (Some("monzo.com"), "/") => Ok(http::Response::new(
+                r#"<a href="/a"></a> <a href="/b"></a> <a href="/c"></a>
+                <a href="https://monzo.com/slow"></a>"#
+                    .to_string(),
+            )),
+            (Some("monzo.com"), "/slow") => {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(http::Response::new(r#"<a href="/d"></a>"#.to_string()))
+            }
+            (Some("monzo.com"), _) => {
+                Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+            }
+            _ => Ok(http::Response::new(r#"<a href="/faq"></a>"#.to_string())),
+        }
+    });
+
+    // When: Crawling it and a healthy host, giving up on hosts after two failures in a row
+    let all_pages = CrawlerBuilder::new(TowerVisitor::new(service))
+        .with_host_failure_threshold(2)
+        .build()
+        .crawl_seeds([
+            Url::parse("https://monzo.com/")?,
+            Url::parse("https://help.monzo.com/")?,
+        ])
+        .await;
+
+    // Then: The pages linked from the home page fail, and the later link to the host is skipped
+    let failed: HashSet<Url> = all_pages
+        .failed
+        .iter()
+        .map(|failed| failed.url.clone())
+        .collect();
+    assert_eq!(
+        failed,
+        urls(&[
+            "https://monzo.com/a",
+            "https://monzo.com/b",
+            "https://monzo.com/c"
+        ])
+    );
+    assert_eq!(all_pages.skipped.get(&SkipReason::HostDown), Some(&1));
+
+    // And: The healthy host is still crawled
+    assert_eq!(
+        page_urls(&all_pages),
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/slow",
+            "https://help.monzo.com/",
+            "https://help.monzo.com/faq"
+        ])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_host_failure_threshold_ignores_other_errors() -> anyhow::Result<()> {
+    // Given: A host that answers pages with malformed responses, and a page that takes 100ms to
+    // respond and then links to another such page
+    let service = tower::service_fn(|request: http::Request<String>| async move {
+        match request.uri().path() {
+            "/" => Ok(http::Response::new(
+                r#"<a href="/a"></a> <a href="/b"></a> <a href="/slow"></a>"#.to_string(),
+            )),
+            "/slow" => {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(http::Response::new(r#"<a href="/c"></a>"#.to_string()))
+            }
+            _ => Err(std::io::Error::from(std::io::ErrorKind::InvalidData)),
+        }
+    });
+
+    // When: Crawling it, giving up on hosts after two failures in a row
+    let all_pages = CrawlerBuilder::new(TowerVisitor::new(service))
+        .with_host_failure_threshold(2)
+        .build()
+        .crawl(Url::parse("https://monzo.com/")?)
+        .await;
+
+    // Then: The later link is still visited, as the host is reachable
+    let failed: HashSet<Url> = all_pages
+        .failed
+        .iter()
+        .map(|failed| failed.url.clone())
+        .collect();
+    assert_eq!(
+        failed,
+        urls(&[
+            "https://monzo.com/a",
+            "https://monzo.com/b",
+            "https://monzo.com/c"
+        ])
+    );
+    assert_eq!(all_pages.skipped.get(&SkipReason::HostDown), None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ignore_robots_for_seed_host() -> anyhow::Result<()> {
+    // Given: A site and its subdomain, both linking to a path disallowed by robots.txt
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/private"></a> <a href="https://help.monzo.com/"></a>"#,
+        ),
+        ("https://monzo.com/private", "<p></p>"),
+        ("https://help.monzo.com/", r#"<a href="/private"></a>"#),
+        ("https://help.monzo.com/private", "<p></p>"),
+    ]);
+    let list = std::fs::read_to_string("./tests/test_data/public_suffix_list.dat")?.parse()?;
+    let crawler = CrawlerBuilder::new(visitor)
+        .with_scope_mode(ScopeMode::RegistrableDomain(Arc::new(list)))
+        .with_robot("User-Agent: *\nDisallow: /private", "test-agent")?
+        .with_ignore_robots_for_seed_host(true)
+        .build();
+
+    // When: We crawl from the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The disallowed path is only visited on the seed's host
+    assert_eq!(
+        page_urls(&pages),
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/private",
+            "https://help.monzo.com/",
+        ])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_self_referential_hrefs_visit_once() -> anyhow::Result<()> {
+    // Given: A page linking to itself with empty, fragment-only and relative hrefs
+    let docs = r##"<a href=""></a> <a href="#"></a> <a href="."></a> <a href="?x=1"></a>"##;
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/docs/", docs),
+        ("https://monzo.com/docs/?x=1", docs),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone()).build();
+
+    // When: We crawl it
+    crawler.crawl(Url::parse("https://monzo.com/docs/")?).await;
+
+    // Then: The page and its query variant are each visited once
+    assert_eq!(
+        visitor.visit_order(),
+        [
+            Url::parse("https://monzo.com/docs/")?,
+            Url::parse("https://monzo.com/docs/?x=1")?,
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_diamond_visits_once() -> anyhow::Result<()> {
+    // Given: A diamond-shaped site, where the two middle pages finish at the same time and link
+    // to the same page, once with a trailing slash
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/b"></a> <a href="/c"></a>"#,
+        ),
+        ("https://monzo.com/b", r#"<a href="/d"></a>"#),
+        (
+            "https://monzo.com/c",
+            r#"<a href="/d/"></a> <a href="/d"></a>"#,
+        ),
+        ("https://monzo.com/d", "<p></p>"),
+    ])
+    .with_delay(Duration::from_millis(20));
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_trailing_slash_normalization(Normalize::Remove)
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: Every page, including the shared one, is visited exactly once, at whichever of its
+    // URLs was linked first
+    assert_eq!(visitor.visit_times().len(), 4);
+    let page_urls = page_urls(&pages);
+    assert_eq!(page_urls.len(), 4);
+    assert!(page_urls.is_subset(&urls(&[
+        "https://monzo.com/",
+        "https://monzo.com/b",
+        "https://monzo.com/c",
+        "https://monzo.com/d",
+        "https://monzo.com/d/",
+    ])));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parent() -> anyhow::Result<()> {
+    // Given: A site where some pages are linked from several others
+    let visitor = RoutedVisitor::new(&[
+        ("https://monzo.com/", r#"<a href="/a"></a>"#),
+        (
+            "https://monzo.com/a",
+            r#"<a href="/b"></a> <a href="/c"></a>"#,
+        ),
+        (
+            "https://monzo.com/b",
+            r#"<a href="/c"></a> <a href="/"></a>"#,
+        ),
+        ("https://monzo.com/c", r#"<a href="/a"></a>"#),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor).build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: Each page records the page it was first discovered on, and the seed has no parent
+    let parents: HashMap<&str, Option<&str>> = pages
+        .pages
+        .iter()
+        .map(|page| (page.url.path(), page.parent.as_ref().map(|url| url.path())))
+        .collect();
+    assert_eq!(
+        parents,
+        HashMap::from([
+            ("/", None),
+            ("/a", Some("/")),
+            ("/b", Some("/a")),
+            ("/c", Some("/a")),
+        ])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_robots_matcher() -> anyhow::Result<()> {
+    // Given: A site with a private section
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/about"></a> <a href="/private"></a> <a href="/private/accounts"></a>"#,
+        ),
+        ("https://monzo.com/about", "<p></p>"),
+        ("https://monzo.com/private", "<p></p>"),
+        ("https://monzo.com/private/accounts", "<p></p>"),
+    ]);
+
+    // And: A crawler with a custom matcher denying it
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_robots_matcher(|url| !url.path().starts_with("/private"))
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: The private section is never visited
+    let expected = urls(&["https://monzo.com/", "https://monzo.com/about"]);
+    assert_eq!(page_urls(&pages), expected);
+    assert_eq!(visitor.visited_urls(), expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schedule_seed() -> anyhow::Result<()> {
+    // Given: A site with many links on its home page
+    let links: String = (0..20)
+        .map(|i| format!(r#"<a href="/page-{}"></a>"#, i))
+        .collect();
+    let visitor = RoutedVisitor::new(&[("https://monzo.com/", &links)]);
+
+    // When: We crawl it with a schedule seed, twice with the same seed and once with another
+    let mut orders = Vec::new();
+    for schedule_seed in [1, 1, 2] {
+        let visitor = visitor.clone().with_fresh_visits();
+        CrawlerBuilder::new(visitor.clone())
+            .with_schedule_seed(schedule_seed)
+            .build()
+            .crawl(Url::parse("https://monzo.com")?)
+            .await;
+        orders.push(visitor.visit_order());
+    }
+
+    // Then: The same seed visits pages in the same order, and a different seed in another order
+    assert_eq!(orders[0].len(), 21);
+    assert_eq!(orders[0], orders[1]);
+    assert_ne!(orders[0], orders[2]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crawl_seeds() -> anyhow::Result<()> {
+    // Given: Two sites, each crawled with exact host scope, one of which links to the other
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/about"></a> <a href="https://example.com/other"></a>"#,
+        ),
+        ("https://monzo.com/about", "<p></p>"),
+        ("https://example.com/", r#"<a href="/contact"></a>"#),
+        ("https://example.com/contact", r#"<a href="/"></a>"#),
+        ("https://example.com/other", "<p></p>"),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor.clone())
+        .with_scope_mode(ScopeMode::ExactHost)
+        .build();
+
+    // When: We crawl from both seeds, repeating one of them
+    let pages = crawler
+        .crawl_seeds([
+            Url::parse("https://monzo.com")?,
+            Url::parse("https://example.com")?,
+            Url::parse("https://example.com/")?,
+        ])
+        .await;
+
+    // Then: Each seed's links are followed within its own scope, and every page is visited once
+    assert_eq!(
+        page_urls(&pages),
+        urls(&[
+            "https://monzo.com/",
+            "https://monzo.com/about",
+            "https://example.com/",
+            "https://example.com/contact",
+        ])
+    );
+    assert_eq!(visitor.visit_times().len(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_config_summary() -> anyhow::Result<()> {
+    let robots_txt = "User-Agent: *
+Crawl-delay: 2";
+
+    let builder = CrawlerBuilder::new(MockUrlVisitor::new())
+        .with_robot(robots_txt, "test-agent")?
+        .with_max_pages(10)
+        .with_max_time(60)
+        .with_scope_mode(ScopeMode::ExactHost)
+        .with_trailing_slash_normalization(Normalize::Remove)
+        .with_strict_politeness();
+
+    assert_eq!(
+        builder.config_summary(),
+        CrawlConfig {
+            max_time: Some(Duration::from_secs(60)),
+            deadline: None,
+            max_pages: Some(10),
+            max_bytes: None,
+            max_tracked_urls: None,
+            memory_limit: None,
+            host_failure_threshold: None,
+            page_retry: None,
+            shutdown_drain: None,
+            robots: true,
+            ignore_robots_for_seed_host: false,
+            schedule_seed: None,
+            strategy: None,
+            max_in_flight: 32,
+            scope_mode: ScopeMode::ExactHost,
+            allowed_schemes: ["http".to_string(), "https".to_string()].into(),
+            allowed_ports: None,
+            allowed_path_prefixes: None,
+            trailing_slash: Normalize::Remove,
+            normalize_encoding: false,
+            sorted_query_dedup: false,
+            index_files: Vec::new(),
+            verify_content_type: false,
+            probe_content_types: false,
+            check_downloads: false,
+            preexcluded: 0,
+            prior_manifest: 0,
+            skip_unchanged_subtrees: false,
+            parse_timeout: None,
+            link_region: None,
+            parse_noscript: false,
+            max_anchor_text_len: None,
+            parse_threads: None,
+            retain_content: false,
+            etag_cache: false,
+            retain_links: true,
+            intern_links: false,
+            respect_nofollow: true,
+            respect_meta_robots: true,
+            respect_crawl_delay: true,
+            crawl_delay: Some(Duration::from_secs(2)),
+            page_store: false,
+            event_sender: false,
+        }
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subscribe_external_links() -> anyhow::Result<()> {
+    // Given: A site with links to other sites, some repeated across pages
+    let visitor = RoutedVisitor::new(&[
+        (
+            "https://monzo.com/",
+            r#"<a href="/about"></a>
+               <a href="https://github.com/monzo"></a>
+               <a href="https://twitter.com/monzo"></a>"#,
+        ),
+        (
+            "https://monzo.com/about",
+            r#"<a href="https://github.com/monzo"></a> <a href="https://jobs.monzo.com/"></a>"#,
+        ),
+    ]);
+    let crawler = CrawlerBuilder::new(visitor).build();
+    let mut rx = crawler.subscribe_external_links();
+
+    // When: We crawl the site
+    crawler.crawl(Url::parse("https://monzo.com")?).await;
+
+    // Then: Each external URL is received exactly once
+    let mut external_links = Vec::new();
+    while let Ok(link) = rx.try_recv() {
+        external_links.push(link);
+    }
+    assert_eq!(external_links.len(), 3);
+    assert_eq!(
+        external_links.into_iter().collect::<HashSet<Url>>(),
+        urls(&[
+            "https://github.com/monzo",
+            "https://twitter.com/monzo",
+            "https://jobs.monzo.com/"
+        ])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_event_sender() -> anyhow::Result<()> {
+    // Given: A site with more pages than a broadcast receiver holds, each linking to another site
+    let mut routes: Vec<(String, String)> = (0..150)
+        .map(|i| {
+            (
+                format!("https://monzo.com/{i}"),
+                format!(r#"<a href="https://github.com/monzo/{i}"></a>"#),
+            )
+        })
+        .collect();
+    routes.push((
+        "https://monzo.com/".to_string(),
+        (0..150)
+            .map(|i| format!(r#"<a href="/{i}"></a>"#))
+            .collect(),
+    ));
+    let routes: Vec<(&str, &str)> = routes
+        .iter()
+        .map(|(url, content)| (url.as_str(), content.as_str()))
+        .collect();
+
+    // And: A slow consumer of a small channel
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    let consumer = tokio::spawn(async move {
+        let (mut pages, mut external_links) = (0, 0);
+        while let Some(event) = rx.recv().await {
+            match event {
+                CrawlEvent::Page(_) => pages += 1,
+                CrawlEvent::ExternalLink(_) => external_links += 1,
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        (pages, external_links)
+    });
+
+    // When: We crawl the site, sending events to the channel
+    let all_pages = CrawlerBuilder::new(RoutedVisitor::new(&routes))
+        .with_event_sender(tx)
+        .build()
+        .crawl(Url::parse("https://monzo.com/")?)
+        .await;
+
+    // Then: The consumer receives every event
+    let (pages, external_links) = consumer.await?;
+    assert_eq!(all_pages.pages.len(), 151);
+    assert_eq!(pages, 151);
+    assert_eq!(external_links, 150);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_drain() -> anyhow::Result<()> {
+    // Given: A site where one linked page responds quickly, one a little later and one very slowly
+    let service = tower::service_fn(|request: http::Request<String>| async move {
+        let (delay, body) = match request.uri().path() {
+            "/" => (
+                0,
+                r#"<a href="/fast"></a> <a href="/later"></a> <a href="/slow"></a>"#,
+            ),
+            "/later" => (50, r#"<a href="/more"></a>"#),
+            "/slow" => (10_000, ""),
+            _ => (0, ""),
+        };
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        http::Response::builder().status(200).body(body.to_string())
+    });
+
+    // And: A crawler limited to two pages that drains in-flight visits for 300ms
+    let crawler = CrawlerBuilder::new(TowerVisitor::new(service))
+        .with_max_pages(2)
+        .with_shutdown_drain(Duration::from_millis(300))
+        .build();
+
+    // When: We crawl the site
+    let start = Instant::now();
+    let pages = crawler.crawl(Url::parse("http://localhost")?).await;
+
+    // Then: The page finishing within the drain window is included, without following its links,
+    // and the slow page is abandoned
+    assert_eq!(
+        page_urls(&pages),
+        urls(&[
+            "http://localhost/",
+            "http://localhost/fast",
+            "http://localhost/later"
+        ])
+    );
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_etag_cache() -> anyhow::Result<()> {
+    // Given: A site whose home page has an ETag, and only links anywhere in its full response
+    let service = tower::service_fn(|request: http::Request<String>| async move {
+        let response = http::Response::builder();
+        match request.uri().path() {
+            "/" if request.headers().get(http::header::IF_NONE_MATCH)
+                == Some(&HeaderValue::from_static("\"v1\"")) =>
+            {
+                response.status(304).body(String::new())
+            }
+            "/" => response
+                .header(http::header::ETAG, "\"v1\"")
+                .body(r#"<a href="/about"></a>"#.to_string()),
+            _ => response.body("<p></p>".to_string()),
+        }
+    });
+    let etag_cache = Arc::new(EtagCache::new());
+
+    // When: We crawl the site twice, sharing the cache
+    let first = CrawlerBuilder::new(TowerVisitor::new(service))
+        .with_etag_cache(etag_cache.clone())
+        .build()
+        .crawl(Url::parse("http://localhost")?)
+        .await;
+    let second = CrawlerBuilder::new(TowerVisitor::new(service))
+        .with_etag_cache(etag_cache.clone())
+        .build()
+        .crawl(Url::parse("http://localhost")?)
+        .await;
+
+    // Then: Only the page with an ETag is cached
+    assert_eq!(etag_cache.len(), 1);
+
+    // And: The second crawl reuses the unchanged page, whose cached links are followed
+    let expected = urls(&["http://localhost/", "http://localhost/about"]);
+    assert_eq!(page_urls(&first), expected);
+    assert_eq!(page_urls(&second), expected);
+    let home = second
+        .pages
+        .iter()
+        .find(|page| page.url.path() == "/")
+        .expect("Home page was not crawled");
+    assert_eq!(home.status_code, reqwest::StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_content_type_probe() -> anyhow::Result<()> {
+    // Given: A site linking to pages, images and documents, recording every request
+    let requests = Arc::new(RwLock::new(Vec::new()));
+    let service = tower::service_fn({
+        let requests = requests.clone();
+        move |request: http::Request<String>| {
+            let requests = requests.clone();
+            async move {
+                let path = request.uri().path().to_string();
+                let (content_type, body) = match path.as_str() {
+                    "/" => (
+                        "text/html; charset=utf-8",
+                        r#"<a href="/about"></a> <a href="/logo.png"></a> <a href="/a.pdf"></a>
+                           <a href="/b.pdf"></a> <a href="https://github.com/monzo.png"></a>"#,
+                    ),
+                    "/about" => ("text/html", r#"<a href="/a.pdf"></a>"#),
+                    "/logo.png" => ("image/png", ""),
+                    _ => ("application/pdf", ""),
+                };
+                requests
+                    .write()
+                    .expect("Could not acquire lock")
+                    .push((request.method().clone(), path));
+                http::Response::builder()
+                    .header(http::header::CONTENT_TYPE, content_type)
+                    .body(body.to_string())
+            }
+        }
+    });
+    let crawler = CrawlerBuilder::new(TowerVisitor::new(service))
+        .with_content_type_probe(true)
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("http://localhost")?).await;
+
+    // Then: The histogram counts visited pages and each probed in-scope link once
+    assert_eq!(
+        pages.content_type_histogram,
+        BTreeMap::from([
+            ("application/pdf".to_string(), 2),
+            ("image/png".to_string(), 1),
+            ("text/html".to_string(), 2),
+        ])
+    );
+
+    // And: Links that are not pages are only requested with HEAD
+    let mut heads: Vec<String> = requests
+        .read()
+        .expect("Could not acquire lock")
+        .iter()
+        .filter(|(method, _)| method == http::Method::HEAD)
+        .map(|(_, path)| path.clone())
+        .collect();
+    heads.sort();
+    assert_eq!(heads, ["/a.pdf", "/b.pdf", "/logo.png"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_download_check() -> anyhow::Result<()> {
+    // Given: A site linking to a file download, a missing download and a page, recording requests
+    let requests = Arc::new(RwLock::new(Vec::new()));
+    let service = tower::service_fn({
+        let requests = requests.clone();
+        move |request: http::Request<String>| {
+            let requests = requests.clone();
+            async move {
+                let path = request.uri().path().to_string();
+                requests
+                    .write()
+                    .expect("Could not acquire lock")
+                    .push((request.method().clone(), path.clone()));
+                match path.as_str() {
+                    "/" => http::Response::builder().body(
+                        r#"<a href="/report.zip" download></a> <a href="/old.zip" download></a>
+                           <a href="/about"></a>"#
+                            .to_string(),
+                    ),
+                    "/about" => http::Response::builder()
+                        .body(r#"<a href="/report.zip" download></a>"#.to_string()),
+                    "/report.zip" => http::Response::builder()
+                        .header(http::header::CONTENT_LENGTH, "2048")
+                        .body(String::new()),
+                    _ => http::Response::builder()
+                        .status(http::StatusCode::NOT_FOUND)
+                        .body(String::new()),
+                }
+            }
+        }
+    });
+    let crawler = CrawlerBuilder::new(TowerVisitor::new(service))
+        .with_download_check(true)
+        .build();
+
+    // When: We crawl the site
+    let pages = crawler.crawl(Url::parse("http://localhost")?).await;
+
+    // Then: Each download is checked once, recording its status and size
+    assert_eq!(
+        pages.download_checks,
+        HashMap::from([
+            (
+                Url::parse("http://localhost/report.zip")?,
+                DownloadCheck::Responded {
+                    status_code: http::StatusCode::OK,
+                    content_length: Some(2048),
+                }
+            ),
+            (
+                Url::parse("http://localhost/old.zip")?,
+                DownloadCheck::Responded {
+                    status_code: http::StatusCode::NOT_FOUND,
+                    content_length: None,
+                }
+            ),
+        ])
+    );
+
+    // And: Downloads are only requested with HEAD, and are not crawled as pages
+    let mut downloads: Vec<(http::Method, String)> = requests
+        .read()
+        .expect("Could not acquire lock")
+        .iter()
+        .filter(|(_, path)| path.ends_with(".zip"))
+        .cloned()
+        .collect();
+    downloads.sort_by(|a, b| a.1.cmp(&b.1));
+    assert_eq!(
+        downloads,
+        [
+            (http::Method::HEAD, "/old.zip".to_string()),
+            (http::Method::HEAD, "/report.zip".to_string())
+        ]
+    );
+    assert_eq!(pages.pages.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tower_visitor() -> anyhow::Result<()> {
+    // Given: An in-memory service with a couple of linked routes
+    let service = tower::service_fn(|request: http::Request<String>| async move {
+        let (status, body) = match request.uri().path() {
+            "/" => (200, r#"<a href="/about"></a> <a href="/missing"></a>"#),
+            "/about" => (200, r#"<a href="/"></a>"#),
+            _ => (404, "Not found"),
+        };
+        http::Response::builder()
+            .status(status)
+            .header(http::header::CONTENT_TYPE, "text/html")
+            .body(body.to_string())
+    });
+    let crawler = CrawlerBuilder::new(TowerVisitor::new(service)).build();
+
+    // When: We crawl the service
+    let pages = crawler.crawl(Url::parse("http://localhost")?).await;
+
+    // Then: Every linked route is visited
+    let statuses: HashMap<Url, reqwest::StatusCode> = pages
+        .pages
+        .into_iter()
+        .map(|page| (page.url, page.status_code))
+        .collect();
+    assert_eq!(
+        statuses,
+        HashMap::from([
+            (Url::parse("http://localhost/")?, reqwest::StatusCode::OK),
+            (
+                Url::parse("http://localhost/about")?,
+                reqwest::StatusCode::OK
+            ),
+            (
+                Url::parse("http://localhost/missing")?,
+                reqwest::StatusCode::NOT_FOUND
+            ),
+        ])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_live_stats() -> anyhow::Result<()> {
+    // Given: A site with a page that fails to connect
+    let service = tower::service_fn(|request: http::Request<String>| async move {
+        match request.uri().path() {
+            "/" => Ok(http::Response::new(
+                r#"<a href="/about"></a> <a href="/broken"></a>"#.to_string(),
+            )),
+            "/about" => Ok(http::Response::new("<p></p>".to_string())),
+            _ => Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused)),
+        }
+    });
+    let crawler = CrawlerBuilder::new(TowerVisitor::new(service)).build();
+    let live_stats = crawler.live_stats();
+
+    // Then: Nothing is counted before the crawl starts
+    assert_eq!(live_stats.snapshot(), StatsSnapshot::default());
+
+    // When: We crawl the site
+    crawler.crawl(Url::parse("https://monzo.com/")?).await;
+
+    // Then: The final counts are kept once the crawl finishes
+    let snapshot = live_stats.snapshot();
+    assert_eq!(snapshot.pages_visited, 2);
+    assert_eq!(snapshot.failed, 1);
+    assert_eq!(snapshot.frontier, 0);
+    assert!(snapshot.finished);
 
     Ok(())
 }
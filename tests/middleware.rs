@@ -1,18 +1,28 @@
 use std::{
+    collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 
-use http::StatusCode;
+use http::{StatusCode, Version};
+use url::Url;
 
-use reqwest_middleware::ClientBuilder;
+use reqwest_middleware::{ClientBuilder, Middleware, Next};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use tracing::{debug, error};
 
-use spider_crab::client_middleware::RetryTooManyRequestsMiddleware;
+use spider_crab::{
+    client_middleware::{
+        login, redirect_policy_same_scope, scoped_redirect_policy, seeds_redirect_policy,
+        DownloadTooSlowError, LatencyBackoffMiddleware, LimitedResolver, MaxConcurrentMiddleware,
+        PerHostConcurrentMiddleware, RetryTooManyRequestsMiddleware,
+    },
+    ClientWithMiddlewareVisitor, CrawlerBuilder, FailureReason, Redirect, ScopeMode, SiteVisitor,
+    SkipReason, VisitorError,
+};
 use tracing_test::traced_test;
 use wiremock::{
-    matchers::{method, path},
+    matchers::{body_string_contains, header, method, path},
     Match, Mock, MockServer, ResponseTemplate,
 };
 
@@ -121,3 +131,1131 @@ async fn test_too_many_request_middleware() -> anyhow::Result<()> {
     // ToDo: Assert that the Retry-After header was respected.
     Ok(())
 }
+
+#[tokio::test]
+async fn test_out_of_scope_redirect() -> anyhow::Result<()> {
+    let site = MockServer::start().await;
+    let other_site = MockServer::start().await;
+    let seed = Url::parse(&site.uri())?;
+
+    // Given: A site that links to a page which redirects to another host
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"<a href="/away"></a>"#))
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/away"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::FOUND)
+                .append_header("Location", format!("{}/landing", other_site.uri())),
+        )
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&other_site)
+        .await;
+
+    // When: We crawl with a redirect policy scoped to the site
+    let client = ClientBuilder::new(
+        reqwest::Client::builder()
+            .redirect(scoped_redirect_policy(
+                ScopeMode::ExactHost,
+                seed.clone(),
+                10,
+            ))
+            .build()?,
+    )
+    .build();
+    let crawler = CrawlerBuilder::new(ClientWithMiddlewareVisitor::new(client))
+        .with_scope_mode(ScopeMode::ExactHost)
+        .build();
+    let pages = crawler.crawl(seed.clone()).await;
+
+    // Then: The redirect is recorded as external and the other host is never visited
+    assert_eq!(pages.pages.len(), 2);
+    let away = pages
+        .pages
+        .iter()
+        .find(|page| page.url.path() == "/away")
+        .expect("Redirecting page was not recorded");
+    assert_eq!(away.status_code, StatusCode::FOUND);
+    assert_eq!(
+        away.redirect,
+        Some(Redirect {
+            location: Url::parse(&format!("{}/landing", other_site.uri()))?,
+            external: true,
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_redirect_policy_same_scope() -> anyhow::Result<()> {
+    let site = MockServer::start().await;
+    let other_site = MockServer::start().await;
+    let seed = Url::parse(&site.uri())?;
+    let other_site_url = format!("http://localhost:{}/landing", other_site.address().port());
+
+    // Given: A page redirecting within the site, and one redirecting to another domain
+    Mock::given(method("GET"))
+        .and(path("/old"))
+        .respond_with(ResponseTemplate::new(StatusCode::FOUND).append_header("Location", "/new"))
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/new"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/away"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::FOUND)
+                .append_header("Location", other_site_url.as_str()),
+        )
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&other_site)
+        .await;
+
+    // When: We request them with a client using the seed's redirect policy
+    let client = reqwest::Client::builder()
+        .redirect(redirect_policy_same_scope(seed.clone(), 10))
+        .build()?;
+    let within = client.get(seed.join("/old")?).send().await?;
+    let away = client.get(seed.join("/away")?).send().await?;
+
+    // Then: The in-scope redirect is followed, and the off-scope hop is returned instead
+    assert_eq!(within.status(), StatusCode::OK);
+    assert_eq!(within.url(), &seed.join("/new")?);
+    assert_eq!(away.status(), StatusCode::FOUND);
+    assert_eq!(away.headers()["Location"], other_site_url.as_str());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seeds_redirect_policy() -> anyhow::Result<()> {
+    // Given: Two sites, each with a page redirecting within the site
+    let sites = [MockServer::start().await, MockServer::start().await];
+    for site in sites.iter() {
+        Mock::given(method("GET"))
+            .and(path("/old"))
+            .respond_with(
+                ResponseTemplate::new(StatusCode::FOUND).append_header("Location", "/new"),
+            )
+            .mount(site)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/new"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(site)
+            .await;
+    }
+    let seeds = sites
+        .iter()
+        .map(|site| Url::parse(&site.uri()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // When: We request them with a client using the redirect policy of both seeds
+    let client = reqwest::Client::builder()
+        .redirect(seeds_redirect_policy(
+            ScopeMode::ExactHost,
+            seeds.clone(),
+            10,
+        ))
+        .build()?;
+
+    // Then: The redirects on both seeds' sites are followed
+    for seed in seeds.iter() {
+        let response = client.get(seed.join("/old")?).send().await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.url(), &seed.join("/new")?);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_relative_links_after_redirect() -> anyhow::Result<()> {
+    let site = MockServer::start().await;
+    let url = |path: &str| Url::parse(&format!("{}{}", site.uri(), path));
+
+    // Given: A page that moved to a new directory and links relative to it
+    Mock::given(method("GET"))
+        .and(path("/old"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::MOVED_PERMANENTLY).append_header("Location", "/new/"),
+        )
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/new/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"<a href="child"></a>"#))
+        .expect(1)
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/new/child"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"<a href="./"></a>"#))
+        .expect(1)
+        .mount(&site)
+        .await;
+
+    // When: We crawl from the old URL, following redirects
+    let client = ClientBuilder::new(reqwest::Client::new()).build();
+    let pages = CrawlerBuilder::new(ClientWithMiddlewareVisitor::new(client))
+        .build()
+        .crawl(url("/old")?)
+        .await;
+
+    // Then: The relative link resolves against the new URL
+    let mut visited: Vec<&str> = pages.pages.iter().map(|page| page.url.path()).collect();
+    visited.sort();
+    assert_eq!(visited, ["/new/", "/new/child"]);
+    let new = pages
+        .pages
+        .iter()
+        .find(|page| page.url.path() == "/new/")
+        .expect("Redirected page was not recorded");
+    assert_eq!(new.links, HashSet::from([url("/new/child")?]));
+
+    // And: The old URL is an alias of the new one, which is not visited again when linked
+    assert_eq!(pages.aliases.get(&url("/old")?), Some(&url("/new/")?));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_x_robots_tag_nofollow() -> anyhow::Result<()> {
+    let site = MockServer::start().await;
+    let url = |path: &str| Url::parse(&format!("{}{}", site.uri(), path));
+
+    // Given: A page whose links are marked nofollow by a response header
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .append_header("X-Robots-Tag", "nofollow")
+                .set_body_string(r#"<a href="/about"></a>"#),
+        )
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/about"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&site)
+        .await;
+
+    // When: We crawl it, respecting robots directives
+    let client = ClientBuilder::new(reqwest::Client::new()).build();
+    let pages = CrawlerBuilder::new(ClientWithMiddlewareVisitor::new(client))
+        .with_respect_meta_robots(true)
+        .build()
+        .crawl(url("/")?)
+        .await;
+
+    // Then: The link is recorded but not visited
+    assert_eq!(pages.pages.len(), 1);
+    assert!(pages.pages[0].meta_robots.nofollow);
+    assert!(pages.pages[0].links.contains(&url("/about")?));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_redirect_loop() -> anyhow::Result<()> {
+    let site = MockServer::start().await;
+    let seed = Url::parse(&site.uri())?;
+
+    // Given: A site that links to an in-scope redirect cycle /a -> /b -> /c -> /a
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"<a href="/a"></a>"#))
+        .mount(&site)
+        .await;
+    for (from, to) in [("/a", "/b"), ("/b", "/c"), ("/c", "/a")] {
+        Mock::given(method("GET"))
+            .and(path(from))
+            .respond_with(ResponseTemplate::new(StatusCode::FOUND).append_header("Location", to))
+            .mount(&site)
+            .await;
+    }
+
+    // When: We crawl with a scoped redirect policy
+    let client = ClientBuilder::new(
+        reqwest::Client::builder()
+            .redirect(scoped_redirect_policy(
+                ScopeMode::default(),
+                seed.clone(),
+                10,
+            ))
+            .build()?,
+    )
+    .build();
+    let crawler = CrawlerBuilder::new(ClientWithMiddlewareVisitor::new(client)).build();
+    let pages = tokio::time::timeout(Duration::from_secs(5), crawler.crawl(seed.clone())).await?;
+
+    // Then: The crawl terminates, recording the redirecting page as a redirect loop
+    assert_eq!(pages.pages.len(), 1);
+    assert_eq!(pages.failed.len(), 1);
+    assert_eq!(pages.failed[0].url, seed.join("/a")?);
+    assert_eq!(
+        pages.failed[0].reason,
+        FailureReason::RedirectLoop { hops: 3 }
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_redirects_boundary() -> anyhow::Result<()> {
+    let site = MockServer::start().await;
+    let seed = Url::parse(&site.uri())?;
+
+    // Given: A site linking to a page one redirect away and a page two redirects away
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(r#"<a href="/one"></a> <a href="/two"></a>"#),
+        )
+        .mount(&site)
+        .await;
+    for (from, to) in [("/one", "/landing"), ("/two", "/one")] {
+        Mock::given(method("GET"))
+            .and(path(from))
+            .respond_with(ResponseTemplate::new(StatusCode::FOUND).append_header("Location", to))
+            .mount(&site)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path("/landing"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<p></p>"))
+        .mount(&site)
+        .await;
+
+    // When: We crawl allowing a single redirect
+    let client = ClientBuilder::new(
+        reqwest::Client::builder()
+            .redirect(scoped_redirect_policy(
+                ScopeMode::default(),
+                seed.clone(),
+                1,
+            ))
+            .build()?,
+    )
+    .build();
+    let crawler = CrawlerBuilder::new(ClientWithMiddlewareVisitor::new(client)).build();
+    let pages = crawler.crawl(seed.clone()).await;
+
+    // Then: The single redirect is followed, and the chain of two is a redirect loop
+    let landing = seed.join("/landing")?;
+    assert_eq!(pages.pages.len(), 2);
+    assert!(pages.pages.iter().any(|page| page.url == landing));
+    assert_eq!(pages.failed.len(), 1);
+    assert_eq!(pages.failed[0].url, seed.join("/two")?);
+    assert_eq!(
+        pages.failed[0].reason,
+        FailureReason::RedirectLoop { hops: 2 }
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_login_session() -> anyhow::Result<()> {
+    let site = MockServer::start().await;
+    let seed = Url::parse(&site.uri())?;
+
+    // Given: A site where logging in sets a session cookie that protected pages require
+    Mock::given(method("POST"))
+        .and(path("/login"))
+        .and(body_string_contains("user=alice"))
+        .and(body_string_contains("pass=secret"))
+        .respond_with(
+            ResponseTemplate::new(200).append_header("Set-Cookie", "session=abc123; Path=/"),
+        )
+        .expect(1)
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .and(header("cookie", "session=abc123"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"<a href="/protected"></a>"#))
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/protected"))
+        .and(header("cookie", "session=abc123"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<p>secret</p>"))
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(StatusCode::UNAUTHORIZED))
+        .mount(&site)
+        .await;
+
+    // When: We log in and then crawl with the same client
+    let client = ClientBuilder::new(reqwest::Client::builder().cookie_store(true).build()?).build();
+    let fields = [
+        ("user".to_string(), "alice".to_string()),
+        ("pass".to_string(), "secret".to_string()),
+    ];
+    login(&client, &seed.join("/login")?, &fields).await?;
+
+    let crawler = CrawlerBuilder::new(ClientWithMiddlewareVisitor::new(client)).build();
+    let pages = crawler.crawl(seed.clone()).await;
+
+    // Then: The protected page is crawled successfully
+    let protected = pages
+        .pages
+        .iter()
+        .find(|page| page.url.path() == "/protected")
+        .expect("Protected page was not crawled");
+    assert_eq!(protected.status_code, StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_too_many_requests_delay_is_per_host() -> anyhow::Result<()> {
+    let host_a = MockServer::start().await;
+    let host_b = MockServer::start().await;
+
+    // Given: Host A asks the client to slow down
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::TOO_MANY_REQUESTS)
+                .append_header(reqwest::header::RETRY_AFTER, "1"),
+        )
+        .mount(&host_a)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&host_b)
+        .await;
+
+    let client = ClientBuilder::new(reqwest::Client::builder().build()?)
+        .with(RetryTooManyRequestsMiddleware::new(Duration::from_secs(1)))
+        .build();
+    client.get(host_a.uri()).send().await?;
+
+    // When: We make requests to host B and then host A
+    let start = Instant::now();
+    client.get(host_b.uri()).send().await?;
+    let host_b_elapsed = start.elapsed();
+
+    let start = Instant::now();
+    client.get(host_a.uri()).send().await?;
+    let host_a_elapsed = start.elapsed();
+
+    // Then: Only requests to host A are delayed
+    assert!(host_b_elapsed < Duration::from_millis(500));
+    assert!(host_a_elapsed >= Duration::from_millis(500));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_user_agent_pool() -> anyhow::Result<()> {
+    let site = MockServer::start().await;
+    let seed = Url::parse(&site.uri())?;
+
+    // Given: A site with three pages
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(r#"<a href="/a"></a> <a href="/b"></a>"#),
+        )
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<p></p>"))
+        .mount(&site)
+        .await;
+
+    // When: We crawl it with a pool of user agents
+    let client = ClientBuilder::new(reqwest::Client::new()).build();
+    let visitor = ClientWithMiddlewareVisitor::new(client).with_user_agent_pool(vec![
+        "agent-one".to_string(),
+        "agent-two".to_string(),
+        "agent-three".to_string(),
+    ]);
+    let pages = CrawlerBuilder::new(visitor).build().crawl(seed).await;
+    assert_eq!(pages.pages.len(), 3);
+
+    // Then: Each request carries a different user agent from the pool
+    let mut user_agents: Vec<String> = site
+        .received_requests()
+        .await
+        .expect("Request recording is enabled")
+        .iter()
+        .filter_map(|request| request.headers.get("user-agent"))
+        .filter_map(|user_agent| user_agent.to_str().ok())
+        .map(str::to_string)
+        .collect();
+    user_agents.sort();
+    assert_eq!(user_agents, ["agent-one", "agent-three", "agent-two"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_strict_decoding() -> anyhow::Result<()> {
+    // Given: A site whose home page contains invalid UTF-8 alongside valid links
+    let site = MockServer::start().await;
+    let seed = Url::parse(&site.uri())?;
+
+    let mut body = br#"<a href="/about"></a>"#.to_vec();
+    body.extend_from_slice(&[0xff, 0xfe, 0x00, 0xc3]);
+    body.extend_from_slice(br#"<a href="/cost"></a>"#);
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/html"))
+        .mount(&site)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("<p></p>", "text/html"))
+        .mount(&site)
+        .await;
+
+    // When: We crawl it with the default lossy decoding
+    let client = ClientBuilder::new(reqwest::Client::new()).build();
+    let pages = CrawlerBuilder::new(ClientWithMiddlewareVisitor::new(client))
+        .build()
+        .crawl(seed.clone())
+        .await;
+
+    // Then: Every link is still extracted
+    let mut paths: Vec<&str> = pages.pages.iter().map(|page| page.url.path()).collect();
+    paths.sort();
+    assert_eq!(paths, ["/", "/about", "/cost"]);
+
+    // When: We crawl it with strict decoding
+    let client = ClientBuilder::new(reqwest::Client::new()).build();
+    let visitor = ClientWithMiddlewareVisitor::new(client).with_strict_decoding(true);
+    let pages = CrawlerBuilder::new(visitor).build().crawl(seed).await;
+
+    // Then: The page fails to be visited
+    assert!(pages.pages.is_empty());
+    assert_eq!(pages.failed.len(), 1);
+
+    Ok(())
+}
+
+/// A middleware that records the most requests in flight to each port at once.
+#[derive(Clone, Default)]
+struct InFlightTracker {
+    counts: Arc<Mutex<HashMap<u16, (usize, usize)>>>,
+}
+
+impl InFlightTracker {
+    fn max_in_flight(&self, port: u16) -> usize {
+        let counts = self.counts.lock().expect("Could not acquire lock");
+        counts.get(&port).map_or(0, |&(_, max)| max)
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for InFlightTracker {
+    async fn handle(
+        &self,
+        req: reqwest::Request,
+        extensions: &mut http::Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let port = req.url().port().unwrap_or_default();
+        {
+            let mut counts = self.counts.lock().expect("Could not acquire lock");
+            let (current, max) = counts.entry(port).or_default();
+            *current += 1;
+            *max = (*max).max(*current);
+        }
+        let response = next.run(req, extensions).await;
+        let mut counts = self.counts.lock().expect("Could not acquire lock");
+        if let Some((current, _)) = counts.get_mut(&port) {
+            *current -= 1;
+        }
+        response
+    }
+}
+
+#[tokio::test]
+async fn test_per_host_concurrency() -> anyhow::Result<()> {
+    let host_a = MockServer::start().await;
+    let host_b = MockServer::start().await;
+
+    // Given: Two slow hosts
+    for host in [&host_a, &host_b] {
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(100)))
+            .mount(host)
+            .await;
+    }
+
+    // And: A client allowing two requests per host at a time
+    let tracker = InFlightTracker::default();
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with(PerHostConcurrentMiddleware::new(2))
+        .with(tracker.clone())
+        .build();
+
+    // When: We fire many concurrent requests at both hosts
+    let mut requests = tokio::task::JoinSet::new();
+    for i in 0..8 {
+        for host in [&host_a, &host_b] {
+            let client = client.clone();
+            let url = format!("{}/page-{}", host.uri(), i);
+            requests.spawn(async move { client.get(url).send().await });
+        }
+    }
+    while let Some(response) = requests.join_next().await {
+        response??.error_for_status()?;
+    }
+
+    // Then: Neither host ever had more than two requests in flight
+    for host in [&host_a, &host_b] {
+        assert_eq!(tracker.max_in_flight(host.address().port()), 2);
+    }
+
+    // And: A limit of zero is treated as one, rather than blocking every request
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with(PerHostConcurrentMiddleware::new(0))
+        .build();
+    let response =
+        tokio::time::timeout(Duration::from_secs(5), client.get(host_a.uri()).send()).await??;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_slow_start_after_too_many_requests() -> anyhow::Result<()> {
+    let mock_server = MockServer::start().await;
+
+    // Given: A host that responds slowly, and once asks the client to slow down
+    Mock::given(method("GET"))
+        .and(path("/slow-down"))
+        .respond_with(ResponseTemplate::new(429))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(25)))
+        .mount(&mock_server)
+        .await;
+
+    // And: A client allowing four requests at a time, halving that after a 429
+    let max_concurrent =
+        Arc::new(MaxConcurrentMiddleware::new(4).with_slow_start(0.5, Duration::from_millis(300)));
+    let port = mock_server.address().port();
+    let max_in_flight = |max_concurrent: Arc<MaxConcurrentMiddleware>| {
+        let uri = mock_server.uri();
+        async move {
+            let tracker = InFlightTracker::default();
+            let client = ClientBuilder::new(reqwest::Client::new())
+                .with_arc(max_concurrent)
+                .with(tracker.clone())
+                .build();
+            let mut requests = tokio::task::JoinSet::new();
+            for i in 0..8 {
+                let client = client.clone();
+                let url = format!("{}/page-{}", uri, i);
+                requests.spawn(async move { client.get(url).send().await });
+            }
+            while let Some(response) = requests.join_next().await {
+                response??.error_for_status()?;
+            }
+            anyhow::Ok(tracker.max_in_flight(port))
+        }
+    };
+    assert_eq!(max_in_flight(max_concurrent.clone()).await?, 4);
+
+    // When: The host responds 429
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with_arc(max_concurrent.clone())
+        .build();
+    let response = client
+        .get(format!("{}/slow-down", mock_server.uri()))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Then: Fewer requests are made at once
+    assert_eq!(max_concurrent.limit(), 2);
+    assert_eq!(max_in_flight(max_concurrent.clone()).await?, 2);
+
+    // And: The limit recovers over time
+    tokio::time::sleep(Duration::from_millis(650)).await;
+    assert_eq!(max_concurrent.limit(), 4);
+    assert_eq!(max_in_flight(max_concurrent.clone()).await?, 4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_latency_backoff() -> anyhow::Result<()> {
+    let mock_server = MockServer::start().await;
+
+    // Given: A host whose responses get slower and then recover
+    for (page, latency) in [("/1", 0), ("/2", 60), ("/3", 120), ("/4", 240), ("/5", 0)] {
+        Mock::given(method("GET"))
+            .and(path(page))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(latency)))
+            .mount(&mock_server)
+            .await;
+    }
+
+    // And: A client backing off from responses slower than 50ms
+    let backoff = Arc::new(
+        LatencyBackoffMiddleware::new(Duration::from_millis(50))
+            .with_initial_delay(Duration::from_millis(10)),
+    );
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with_arc(backoff.clone())
+        .build();
+    let url = Url::parse(&mock_server.uri())?;
+
+    // When: We request each page in turn
+    let mut delays = Vec::new();
+    for page in ["/1", "/2", "/3", "/4", "/5"] {
+        client
+            .get(url.join(page)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        delays.push(backoff.delay(&url));
+    }
+
+    // Then: The delay grows while responses are slow, and relaxes once they are fast again
+    assert_eq!(
+        delays,
+        [0, 10, 20, 40, 20].map(Duration::from_millis).to_vec()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dns_failure() -> anyhow::Result<()> {
+    // Given: A client that gives up connecting after a few seconds
+    let client = ClientBuilder::new(
+        reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(5))
+            .build()?,
+    )
+    .build();
+    let crawler = CrawlerBuilder::new(ClientWithMiddlewareVisitor::new(client)).build();
+
+    // When: We crawl a host that cannot be resolved
+    let start = Instant::now();
+    let pages = crawler
+        .crawl(Url::parse("http://unresolvable.invalid/")?)
+        .await;
+
+    // Then: The visit fails promptly with a DNS failure
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(pages.failed.len(), 1);
+    assert_eq!(
+        pages.failed[0].reason,
+        FailureReason::Dns {
+            host: "unresolvable.invalid".to_string()
+        }
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_html_sniff() -> anyhow::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Given: A server that sends the start of a large binary file and then stalls
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let binary_url = Url::parse(&format!("http://{}/", listener.local_addr()?))?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\n\
+                          Content-Type: application/octet-stream\r\n\
+                          Content-Length: 100000000\r\n\r\n",
+                    )
+                    .await;
+                let _ = stream.write_all(&[0; 8192]).await;
+                std::future::pending::<()>().await;
+            });
+        }
+    });
+
+    // And: A server sending HTML with the wrong Content-Type
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<!DOCTYPE html><p>Hello</p>", "application/octet-stream"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let visitor =
+        ClientWithMiddlewareVisitor::new(ClientBuilder::new(reqwest::Client::new()).build())
+            .with_html_sniff(true)
+            .with_sniff_bytes(1024);
+
+    // When: Visiting the binary file
+    let result = tokio::time::timeout(
+        Duration::from_secs(5),
+        visitor.clone().visit(binary_url.clone()),
+    )
+    .await?;
+
+    // Then: The download is abandoned after the first bytes, without waiting for the rest
+    assert!(matches!(result, Err(VisitorError::NotHtml { .. })));
+
+    // And: A crawl counts it as skipped rather than failed
+    let all_pages = tokio::time::timeout(
+        Duration::from_secs(5),
+        CrawlerBuilder::new(visitor.clone())
+            .build()
+            .crawl(binary_url),
+    )
+    .await?;
+    assert!(all_pages.pages.is_empty());
+    assert!(all_pages.failed.is_empty());
+    assert_eq!(all_pages.skipped.get(&SkipReason::NotHtml), Some(&1));
+
+    // And: HTML is still downloaded whatever its Content-Type
+    let page = visitor
+        .clone()
+        .visit(Url::parse(&mock_server.uri())?)
+        .await?;
+    assert_eq!(page.content, "<!DOCTYPE html><p>Hello</p>");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dropped_connection() -> anyhow::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Given: A server whose home page links to a page that closes the connection without responding
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = Url::parse(&format!("http://{}/", listener.local_addr()?))?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut request = [0; 1024];
+                let Ok(n) = stream.read(&mut request).await else {
+                    return;
+                };
+                if request[..n].starts_with(b"GET /dropped ") {
+                    return;
+                }
+                let body = r#"<a href="/dropped"></a>"#;
+                let _ = stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                            body.len(),
+                            body
+                        )
+                        .as_bytes(),
+                    )
+                    .await;
+            });
+        }
+    });
+
+    let visitor =
+        ClientWithMiddlewareVisitor::new(ClientBuilder::new(reqwest::Client::new()).build());
+
+    // When: We crawl the site
+    let all_pages = tokio::time::timeout(
+        Duration::from_secs(5),
+        CrawlerBuilder::new(visitor).build().crawl(url.clone()),
+    )
+    .await?;
+
+    // Then: The crawl completes, recording the dropped page as failed rather than panicking
+    assert_eq!(all_pages.pages.len(), 1);
+    assert_eq!(all_pages.pages[0].url, url);
+    assert_eq!(all_pages.failed.len(), 1);
+    assert_eq!(all_pages.failed[0].url, url.join("/dropped")?);
+    assert!(matches!(
+        all_pages.failed[0].reason,
+        FailureReason::Visitor(_)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_min_download_rate() -> anyhow::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Given: A server that trickles a body at 100 bytes/s
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let trickle_url = Url::parse(&format!("http://{}/", listener.local_addr()?))?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\n\
+                          Content-Type: text/html\r\n\
+                          Content-Length: 100000\r\n\r\n",
+                    )
+                    .await;
+                loop {
+                    if stream.write_all(&[b' '; 10]).await.is_err() {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            });
+        }
+    });
+
+    // And: A server sending a large body quickly
+    let mock_server = MockServer::start().await;
+    let large_body = format!("<p>{}</p>", "a".repeat(5_000_000));
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(large_body.clone(), "text/html"))
+        .mount(&mock_server)
+        .await;
+
+    let visitor =
+        ClientWithMiddlewareVisitor::new(ClientBuilder::new(reqwest::Client::new()).build())
+            .with_min_download_rate(10_000, Duration::from_secs(1));
+
+    // When: Visiting the trickling body
+    let start = Instant::now();
+    let result = tokio::time::timeout(
+        Duration::from_secs(5),
+        visitor.clone().visit(trickle_url.clone()),
+    )
+    .await?;
+
+    // Then: The download is abandoned once it falls behind the rate by the grace period
+    let Err(VisitorError::Request(e)) = result else {
+        panic!("Expected the download to be abandoned");
+    };
+    let too_slow = e
+        .downcast_ref::<DownloadTooSlowError>()
+        .expect("Expected a DownloadTooSlowError");
+    assert_eq!(too_slow.url, trickle_url);
+    assert!(too_slow.received > 0);
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert!(start.elapsed() < Duration::from_secs(3));
+
+    // And: The large body arriving quickly is downloaded in full
+    let page = visitor
+        .clone()
+        .visit(Url::parse(&mock_server.uri())?)
+        .await?;
+    assert_eq!(page.content, large_body);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_min_download_rate_after_burst() -> anyhow::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Given: A server that sends half of a 2MB body at once and then stalls
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = Url::parse(&format!("http://{}/", listener.local_addr()?))?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\n\
+                          Content-Type: text/html\r\n\
+                          Content-Length: 2000000\r\n\r\n",
+                    )
+                    .await;
+                let _ = stream.write_all(&[b' '; 1_000_000]).await;
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            });
+        }
+    });
+
+    let mut visitor =
+        ClientWithMiddlewareVisitor::new(ClientBuilder::new(reqwest::Client::new()).build())
+            .with_min_download_rate(10_000, Duration::from_secs(1));
+
+    // When: Visiting it
+    let start = Instant::now();
+    let result = tokio::time::timeout(Duration::from_secs(10), visitor.visit(url.clone())).await?;
+
+    // Then: The stall is caught within the grace period, despite the fast start being
+    // 100 seconds ahead of the rate
+    let Err(VisitorError::Request(e)) = result else {
+        panic!("Expected the download to be abandoned");
+    };
+    let too_slow = e
+        .downcast_ref::<DownloadTooSlowError>()
+        .expect("Expected a DownloadTooSlowError");
+    assert_eq!(too_slow.received, 1_000_000);
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert!(start.elapsed() < Duration::from_secs(3));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_min_download_rate_pause() -> anyhow::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Given: A server that sends half of a 2MB body at once, pauses for 1.5 seconds, and then
+    // sends the rest
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = Url::parse(&format!("http://{}/", listener.local_addr()?))?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\n\
+                          Content-Type: text/html\r\n\
+                          Content-Length: 2000000\r\n\r\n",
+                    )
+                    .await;
+                let _ = stream.write_all(&[b' '; 1_000_000]).await;
+                tokio::time::sleep(Duration::from_millis(1500)).await;
+                let _ = stream.write_all(&[b' '; 1_000_000]).await;
+            });
+        }
+    });
+
+    // When: Visiting it with a minimum rate far below its average, and a grace of 3 seconds
+    let mut visitor =
+        ClientWithMiddlewareVisitor::new(ClientBuilder::new(reqwest::Client::new()).build())
+            .with_min_download_rate(10_000, Duration::from_secs(3));
+    let page = tokio::time::timeout(Duration::from_secs(10), visitor.visit(url)).await??;
+
+    // Then: The pause is tolerated and the whole body is downloaded
+    assert_eq!(page.content.len(), 2_000_000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_version() -> anyhow::Result<()> {
+    // Given: A server speaking HTTP/1.1
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<p>Hello</p>"))
+        .mount(&mock_server)
+        .await;
+    let client = ClientBuilder::new(reqwest::Client::new()).build();
+
+    // When: We crawl it
+    let pages = CrawlerBuilder::new(ClientWithMiddlewareVisitor::new(client))
+        .build()
+        .crawl(Url::parse(&mock_server.uri())?)
+        .await;
+
+    // Then: The page records the version it was served over
+    assert_eq!(pages.pages.len(), 1);
+    assert_eq!(pages.pages[0].http_version, Some(Version::HTTP_11));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limited_resolver() -> anyhow::Result<()> {
+    use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+    use std::{
+        net::SocketAddr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// Resolves every name to localhost slowly, recording the most lookups in flight at once.
+    /// Clones share the counts.
+    #[derive(Clone, Default)]
+    struct SlowResolver {
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl Resolve for SlowResolver {
+        fn resolve(&self, _name: Name) -> Resolving {
+            let resolver = self.clone();
+            Box::pin(async move {
+                let in_flight = resolver.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                resolver
+                    .max_in_flight
+                    .fetch_max(in_flight, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                resolver.in_flight.fetch_sub(1, Ordering::SeqCst);
+                let addrs: Addrs = Box::new(std::iter::once(SocketAddr::from(([127, 0, 0, 1], 0))));
+                Ok(addrs)
+            })
+        }
+    }
+
+    // Given: A client limited to two DNS lookups at once
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+    let port = mock_server.address().port();
+    let resolver = SlowResolver::default();
+    let client = reqwest::Client::builder()
+        .dns_resolver(Arc::new(
+            LimitedResolver::new(2).with_resolver(Arc::new(resolver.clone())),
+        ))
+        .build()?;
+
+    // When: Requesting many hosts at once
+    let mut requests = tokio::task::JoinSet::new();
+    for i in 0..8 {
+        let request = client.get(format!("http://host-{i}.test:{port}/")).send();
+        requests.spawn(request);
+    }
+
+    // Then: Every request succeeds, but no more than two hosts were resolved at once
+    while let Some(response) = requests.join_next().await {
+        assert_eq!(response??.status(), StatusCode::OK);
+    }
+    assert_eq!(resolver.max_in_flight.load(Ordering::SeqCst), 2);
+
+    // And: A limit of zero is treated as one, rather than blocking every lookup
+    let client = reqwest::Client::builder()
+        .dns_resolver(Arc::new(
+            LimitedResolver::new(0).with_resolver(Arc::new(resolver.clone())),
+        ))
+        .build()?;
+    let response = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.get(format!("http://host.test:{port}/")).send(),
+    )
+    .await??;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    Ok(())
+}